@@ -0,0 +1,84 @@
+// Blocked GEMM, with and without explicit SIMD, against the naive triple loop
+// on the shapes ECGNN actually multiplies
+//
+// Run with `cargo bench --bench gemm`. Each case is timed over enough
+// repetitions to take roughly half a second and reports the best mean.
+use ecgnn::matrix_math::{matrix_multiply, matrix_multiply_naive, Matrix};
+use ecgnn::parallel;
+use ecgnn::rng::Rng;
+use ecgnn::simd::{self, SimdLevel};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn random_matrix(rows: usize, cols: usize, rng: &mut Rng) -> Matrix {
+    Matrix::from_vec(rows, cols, (0..rows * cols).map(|_| rng.uniform(-1.0, 1.0)).collect())
+}
+
+/// Mean time per call, best of three batches
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    // Calibrate the repetition count on a single call
+    let start = Instant::now();
+    f();
+    let single = start.elapsed().max(Duration::from_nanos(100));
+    let reps = ((Duration::from_millis(150).as_nanos() / single.as_nanos()) as u32).clamp(1, 100_000);
+
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..reps {
+                f();
+            }
+            start.elapsed() / reps
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let mut rng = Rng::new(42);
+
+    // (description, m, k, n)
+    let cases = [
+        ("forward, one segment     (1x750 * 750x64)", 1, 750, 64),
+        ("weight grad, one segment (750x1 * 1x64)", 750, 1, 64),
+        ("output layer, one segment(1x64 * 64x5)", 1, 64, 5),
+        ("forward, batch of 32     (32x750 * 750x64)", 32, 750, 64),
+        ("forward, batch of 256    (256x750 * 750x64)", 256, 750, 64),
+        ("weight grad, batch of 256(750x256 * 256x64)", 750, 256, 64),
+        ("square 512               (512x512 * 512x512)", 512, 512, 512),
+    ];
+
+    println!("Threads: {} (set with parallel::set_num_threads)", parallel::num_threads());
+    println!("SIMD: {:?} detected\n", simd::detected_level());
+    println!(
+        "{:<46} {:>12} {:>12} {:>12} {:>9} {:>10}",
+        "shape", "naive", "blocked", "blocked+simd", "speedup", "GFLOP/s"
+    );
+    for (name, m, k, n) in cases {
+        let a = random_matrix(m, k, &mut rng);
+        let b = random_matrix(k, n, &mut rng);
+
+        let naive = time(|| {
+            black_box(matrix_multiply_naive(black_box(&a), black_box(&b)));
+        });
+        simd::set_max_level(Some(SimdLevel::Scalar));
+        let blocked = time(|| {
+            black_box(matrix_multiply(black_box(&a), black_box(&b)));
+        });
+        simd::set_max_level(None);
+        let vectorised = time(|| {
+            black_box(matrix_multiply(black_box(&a), black_box(&b)));
+        });
+
+        let flops = 2.0 * (m * k * n) as f64;
+        println!(
+            "{:<46} {:>10.1}us {:>10.1}us {:>10.1}us {:>8.2}x {:>10.2}",
+            name,
+            naive.as_secs_f64() * 1e6,
+            blocked.as_secs_f64() * 1e6,
+            vectorised.as_secs_f64() * 1e6,
+            naive.as_secs_f64() / vectorised.as_secs_f64(),
+            flops / vectorised.as_secs_f64() / 1e9,
+        );
+    }
+}
@@ -1,5 +1,22 @@
-use ecgnn::data::{load_all_data, data_scaling, data_segmentation};
+use ecgnn::data::{load_records, data_scaling, data_segmentation, Record};
+use ecgnn::batching::BatchConfig;
 use ecgnn::brains::*;
+use ecgnn::clipping::GradientClip;
+use ecgnn::conv::{Conv1D, Conv1DConfig};
+use ecgnn::divergence::{DivergenceGuard, Recovery};
+use ecgnn::init::Initializer;
+use ecgnn::layers::{Dense, Dropout, ReLU, Sigmoid};
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::Matrix;
+use ecgnn::optim::{Adam, Optimizer};
+use ecgnn::regularization::Regularization;
+use ecgnn::rng::Rng;
+use ecgnn::schedule::{OneCycle, Scheduled};
+use ecgnn::sequential::Sequential;
+use ecgnn::imbalance::inverse_frequency_weights;
+use ecgnn::augment::{AmplitudeScale, BaselineShift, GaussianNoise, Pipeline, TimeShift, TimeWarp};
+use ecgnn::split::{de_chazal_split, patient_holdout_split, select, DS1, DS2};
+use ecgnn::synthetic::{generate_ecg, EcgSynConfig, Episode, Morphology, Rhythm, SyntheticEcg};
 use std::io::{self, Write};
 
 /// Display a progress bar
@@ -22,7 +39,7 @@ fn display_progress(current: usize, total: usize, loss: f64, accuracy: f64) {
 }
 
 /// Display training statistics
-fn display_training_stats(epoch: usize, total_epochs: usize, loss: f64, accuracy: f64, elapsed_time: f64) {
+fn display_training_stats(epoch: usize, total_epochs: usize, loss: f64, accuracy: f64, learning_rate: f64, elapsed_time: f64) {
     let remaining_time = if epoch > 0 {
         elapsed_time * (total_epochs - epoch - 1) as f64 / epoch as f64
     } else {
@@ -32,6 +49,7 @@ fn display_training_stats(epoch: usize, total_epochs: usize, loss: f64, accuracy
     println!("\n Epoch {}/{} Complete:", epoch + 1, total_epochs);
     println!("   Loss: {:.4}", loss);
     println!("   Accuracy: {:.2}%", accuracy * 100.0);
+    println!("   Learning rate: {:.2e}", learning_rate);
     println!("   Time: {:.2}s | Est. remaining: {:.2}s", elapsed_time, remaining_time);
     println!("   {}", "─".repeat(60));
 }
@@ -39,35 +57,62 @@ fn display_training_stats(epoch: usize, total_epochs: usize, loss: f64, accuracy
 fn main() {
     println!("=== ECGNN - ECG Arrhythmia Detection Neural Network ===");
     
-    // Try to load your actual ECG data
+    // Try to load your actual ECG data, one record (patient) per CSV file
     let data_folder = "/Users/tyloftin/Downloads/MIT Data";
-    let data = match load_all_data(data_folder) {
-        Ok(data) => {
-            println!("Loaded {} data points from {}", data.len(), data_folder);
-            data
+    let (records, synthetic) = match load_records(data_folder) {
+        Ok(records) => {
+            println!("Loaded {} records from {}", records.len(), data_folder);
+            (records, None)
         },
         Err(e) => {
             eprintln!("Error loading data from {}: {}", data_folder, e);
             println!("Falling back to synthetic data for demonstration...");
-            generate_synthetic_data()
+            let ecgs = generate_synthetic_data();
+            let records = ecgs.iter()
+                .enumerate()
+                .map(|(i, ecg)| Record { id: format!("synthetic-{}", i), data: ecg.to_rows() })
+                .collect();
+            (records, Some(ecgs))
         }
     };
     
-    // Scale the data
-    let scaled_data = data_scaling(&data);
-    println!("Data scaled successfully");
-    
     // Display some dataset statistics
-    if !data.is_empty() {
+    let total_rows: usize = records.iter().map(|r| r.data.len()).sum();
+    if total_rows > 0 {
+        let features_per_sample = records.iter().find(|r| !r.data.is_empty()).map_or(0, |r| r.data[0].len());
         println!("Dataset info:");
-        println!("  Total samples: {}", data.len());
-        println!("  Features per sample: {}", data[0].len());
-        println!("  Dataset size: {:.2} MB", (data.len() * data[0].len() * 8) as f64 / (1024.0 * 1024.0));
+        println!("  Records: {}", records.len());
+        println!("  Total samples: {}", total_rows);
+        println!("  Features per sample: {}", features_per_sample);
+        println!("  Dataset size: {:.2} MB", (total_rows * features_per_sample * 8) as f64 / (1024.0 * 1024.0));
     }
     
-    // Create segments
+    // Scale and segment each record on its own, so segments never span two patients
+    // and no statistics are shared between the training and test patients
     let segment_size = 250; // Increased for real ECG data (typically better for heart rhythm analysis)
-    let segments = data_segmentation(&scaled_data, segment_size);
+    let mut segments = Vec::new();
+    let mut segment_records = Vec::new();
+    let mut labels = Vec::new();
+    
+    for (index, record) in records.iter().enumerate() {
+        let record_segments = data_segmentation(&data_scaling(&record.data), segment_size);
+        
+        // Create labels - synthetic data carries ground truth; for real ECG data you'd load actual labels
+        // For now, real data gets heuristic labels based on data characteristics
+        let record_labels = match &synthetic {
+            Some(ecgs) => ecgs[index].segment_labels(segment_size),
+            None => {
+                let flattened: Vec<Vec<f64>> = record_segments.iter()
+                    .map(|segment| segment.iter().flatten().cloned().collect())
+                    .collect();
+                create_ecg_labels(&flattened)
+            }
+        };
+        
+        labels.extend(record_labels);
+        segment_records.extend(std::iter::repeat_n(record.id.clone(), record_segments.len()));
+        segments.extend(record_segments);
+    }
     println!("Created {} segments of size {}", segments.len(), segment_size);
     
     if segments.is_empty() {
@@ -75,85 +120,116 @@ fn main() {
         return;
     }
     
-    // Convert segments to flattened feature vectors
-    let flattened_segments: Vec<Vec<f64>> = segments.iter()
-        .map(|segment| {
-            segment.iter().flatten().cloned().collect()
-        })
-        .collect();
+    // Convert segments to flattened feature vectors, one per matrix row
+    let flattened_segments = to_network_input(&segments);
     
-    let num_features = flattened_segments[0].len();
+    let num_features = flattened_segments.cols;
     println!("Each segment has {} features", num_features);
     
-    // Create labels - for real ECG data, you'd load actual labels
-    // For now, we'll create synthetic labels based on data characteristics
-    let labels = create_ecg_labels(&flattened_segments);
+    // Patient-wise split: the standard DS1/DS2 partition for MIT-BIH records,
+    // otherwise a random 20% of the patients are held out
+    let is_mit_bih = segment_records.iter().any(|id| DS1.contains(&id.as_str()) || DS2.contains(&id.as_str()));
+    let split = if is_mit_bih {
+        println!("Using de Chazal DS1/DS2 inter-patient split");
+        de_chazal_split(&segment_records)
+    } else {
+        println!("Using random patient-level holdout split");
+        patient_holdout_split(&segment_records, 0.2, 42)
+    };
+    
+    let train_segments = flattened_segments.select_rows(&split.train);
+    let test_segments = flattened_segments.select_rows(&split.test);
+    let train_labels = select(&labels, &split.train);
+    let test_labels = select(&labels, &split.test);
     
-    // Split data (80/20)
-    let split_index = (flattened_segments.len() as f64 * 0.8) as usize;
-    let train_segments = flattened_segments[..split_index].to_vec();
-    let test_segments = flattened_segments[split_index..].to_vec();
-    let train_labels = labels[..split_index].to_vec();
-    let test_labels = labels[split_index..].to_vec();
+    let train_raw_segments = select(&segments, &split.train);
     
-    println!("Training set: {} samples", train_segments.len());
-    println!("Test set: {} samples", test_segments.len());
+    println!("Training set: {} samples", train_segments.rows);
+    println!("Test set: {} samples", test_segments.rows);
     
-    // Initialize neural network
+    // Arrhythmic segments are rare, so weight the loss by inverse class frequency
+    let train_classes: Vec<usize> = train_labels.iter().map(|&label| label as usize).collect();
+    let class_weights = inverse_frequency_weights(&train_classes, 2);
+    println!("Class weights: normal {:.3}, arrhythmia {:.3}", class_weights[0], class_weights[1]);
+    
+    // Per-sample loss weights and the (N x 1) target column the network is trained on
+    let sample_weights: Vec<f64> = train_classes.iter().map(|&class| class_weights[class]).collect();
+    let train_targets = Matrix::from_vec(train_labels.len(), 1, train_labels.clone());
+    
+    // Initialize neural network: Conv1D -> ReLU -> Dense -> ReLU -> Dropout -> Dense -> Sigmoid
+    // The convolution slides the same filters along the segment, one input channel per
+    // column of a time step; He-initialised layers so the units start (and stay) different
+    // One channel per lead; the index column is dropped by to_network_input
+    let num_channels = segments[0][0].len() - 1;
+    let conv = Conv1DConfig { stride: 2, ..Conv1DConfig::new(num_channels, 8, 7, num_features / num_channels) };
     let num_hidden = 64;
-    let (mut weights_input_hidden, mut bias_hidden) = initialize_weights_and_bias(num_features, num_hidden);
-    let mut weights_hidden_output = vec![0.01; num_hidden];
-    let mut bias_output = 0.01;
+    let mut rng = Rng::new(42);
+    let mut model = Sequential::new(Vec::new());
+    model.push(Conv1D::new(conv, Initializer::HeNormal, &mut rng));
+    model.push(ReLU::new());
+    model.push(Dense::with_initializer(conv.output_size(), num_hidden, Initializer::HeNormal, &mut rng));
+    model.push(ReLU::new());
+    model.push(Dropout::new(0.3, 42));
+    model.push(Dense::with_initializer(num_hidden, 1, Initializer::XavierUniform, &mut rng));
+    model.push(Sigmoid::new());
+    // The fully connected weights overfit the training patients without a penalty
+    let mut model = model.with_regularization(Regularization { l1: 0.0, l2: 1e-4, max_norm: Some(3.0) })
+        .with_gradient_clip(GradientClip::GlobalNorm(5.0));
     
     println!("
 Initialized neural network:");
     println!("  Input features: {}", num_features);
+    println!("  Conv1D: {} channels -> {} filters of width {}, stride {} ({} outputs)", conv.in_channels, conv.out_channels, conv.kernel_size, conv.stride, conv.output_size());
     println!("  Hidden neurons: {}", num_hidden);
     
-    // Convert to format expected by neural network
-    let train_segments_nn: Vec<Vec<Vec<f64>>> = train_segments.iter()
-        .map(|segment| vec![segment.clone()])
-        .collect();
-    let test_segments_nn: Vec<Vec<Vec<f64>>> = test_segments.iter()
-        .map(|segment| vec![segment.clone()])
-        .collect();
+    // Augmentations are applied to a fresh copy of the training batch every epoch
+    let mut augmenter = Pipeline::new(42)
+        .with(GaussianNoise { std_dev: 0.05 }, 0.5)
+        .with(AmplitudeScale { min: 0.8, max: 1.2 }, 0.5)
+        .with(BaselineShift { max_shift: 0.2 }, 0.3)
+        .with(TimeShift { max_shift: 20 }, 0.3)
+        .with(TimeWarp { max_strength: 0.2 }, 0.2);
     
     // Training loop
     println!("\nStarting training...");
     let epochs = 50;
-    let learning_rate = 0.01;
+    // Shuffled mini-batches: many weight updates per epoch instead of one
+    let batches = BatchConfig::new(64, 42);
+    // One-cycle schedule over every mini-batch step of the run
+    let total_steps = epochs * batches.batches(train_segments.rows, 0).len();
+    let mut optimizer = Scheduled::new(Adam::new(0.003), OneCycle::new(0.003, total_steps));
+    // Stop at the first NaN/Inf and keep the weights from the start of that epoch
+    let mut guard = DivergenceGuard::new(Recovery::Rollback);
     
     let start_time = std::time::Instant::now();
     
     for epoch in 0..epochs {
         let epoch_start = std::time::Instant::now();
+        let learning_rate = optimizer.learning_rate();
+        
+        let augmented_batch = to_network_input(&augmenter.apply_batch(&train_raw_segments));
+        model.train();
+        let loss = match model.train_epoch_guarded(&augmented_batch, &train_targets, &sample_weights, Loss::BinaryCrossEntropy, &batches, epoch, &mut optimizer, &mut guard) {
+            Ok(loss) => loss,
+            Err(divergence) => {
+                println!("\n{}", divergence);
+                break;
+            }
+        };
         
-        let loss = train_epoch(
-            &train_segments_nn,
-            &train_labels,
-            &mut weights_input_hidden,
-            &mut bias_hidden,
-            &mut weights_hidden_output,
-            &mut bias_output,
-            learning_rate,
-        );
+        optimizer.end_epoch(None);
         
         // Show progress immediately after each epoch
         display_progress(epoch, epochs, loss, 0.0); // Use 0.0 for accuracy to avoid slow calculation
         
         // Calculate accuracy and show detailed stats only every 10 epochs
         if epoch % 2 == 0 || epoch == epochs - 1 {
-            let train_predictions = predict_batch(
-                &train_segments_nn,
-                &weights_input_hidden,
-                &bias_hidden,
-                &weights_hidden_output,
-                bias_output,
-            );
+            model.eval();
+            let train_predictions = model.forward(&train_segments).data;
             let train_accuracy = calculate_accuracy(&train_predictions, &train_labels);
             
             let elapsed = epoch_start.elapsed().as_secs_f64();
-            display_training_stats(epoch, epochs, loss, train_accuracy, elapsed);
+            display_training_stats(epoch, epochs, loss, train_accuracy, learning_rate, elapsed);
         }
     }
     
@@ -163,13 +239,8 @@ Initialized neural network:");
     // Test the model
     println!("
 Testing the model...");
-    let test_predictions = predict_batch(
-        &test_segments_nn,
-        &weights_input_hidden,
-        &bias_hidden,
-        &weights_hidden_output,
-        bias_output,
-    );
+    model.eval();
+    let test_predictions = model.forward(&test_segments).data;
     
     let test_accuracy = calculate_accuracy(&test_predictions, &test_labels);
     let test_loss = loss_function(&test_predictions, &test_labels);
@@ -182,24 +253,41 @@ Testing the model...");
 === Training Complete ===");
 }
 
-fn generate_synthetic_data() -> Vec<Vec<f64>> {
-    let mut data = Vec::new();
-    
-    for i in 0..1000 {
-        let mut sample = Vec::new();
-        
-        for j in 0..200 {
-            let base_signal = (j as f64 * 0.1).sin() * 0.5;
-            let noise = (i as f64 * 0.01 + j as f64 * 0.02).sin() * 0.1;
-            let heart_beat = if j % 50 == 0 { 1.0 } else { 0.0 };
-            
-            sample.push(base_signal + noise + heart_beat);
-        }
-        
-        data.push(sample);
-    }
-    
-    data
+/// Flatten each segment into one row of the matrix the network expects
+///
+/// Column 0 of a segment row is the unscaled sample index, so only the leads are kept;
+/// the row is time-major, the layout `Conv1D` reads its channels from.
+fn to_network_input(segments: &[Vec<Vec<f64>>]) -> Matrix {
+    let rows: Vec<Vec<f64>> = segments.iter()
+        .map(|segment| segment.iter().flat_map(|row| &row[1..]).copied().collect())
+        .collect();
+    Matrix::from_rows(&rows)
+}
+
+/// Two-lead ECGSYN recordings for several synthetic patients, each with its own
+/// heart rate, ectopic beat rates and rhythm episodes
+fn generate_synthetic_data() -> Vec<SyntheticEcg> {
+    (0..6)
+        .map(|patient| {
+            let p = patient as f64;
+            let config = EcgSynConfig {
+                duration: 120.0,
+                heart_rate: 60.0 + 5.0 * p,
+                leads: vec![Morphology::lead_ii(), Morphology::lead_v1()],
+                noise_std: 0.02,
+                pvc_probability: 0.02 * p,
+                apc_probability: 0.01 * (5.0 - p),
+                episodes: vec![match patient % 3 {
+                    0 => Episode { rhythm: Rhythm::Bradycardia, start: 30.0, duration: 30.0, heart_rate: 45.0 },
+                    1 => Episode { rhythm: Rhythm::Tachycardia, start: 40.0, duration: 30.0, heart_rate: 130.0 },
+                    _ => Episode { rhythm: Rhythm::AtrialFibrillation, start: 20.0, duration: 60.0, heart_rate: 100.0 },
+                }],
+                seed: 42 + patient,
+                ..EcgSynConfig::default()
+            };
+            generate_ecg(&config)
+        })
+        .collect()
 }
 
 fn create_synthetic_labels(num_samples: usize) -> Vec<f64> {
@@ -1,27 +1,37 @@
+use crate::float::Float;
+use crate::init::Initializer;
 use crate::matrix_math::*;
+use crate::rng::Rng;
+use crate::simd;
 
 /// Small constant to prevent division by zero and log(0) in loss calculations
 const EPSILON: f64 = 1e-15;
 
+/// `EPSILON` in the element type; f32 cannot tell 1 - 1e-15 from 1, so it uses its machine epsilon instead
+pub(crate) fn epsilon<T: Float>() -> T {
+    T::from_f64(EPSILON).max(T::EPSILON)
+}
+
 /// Initialize weights and bias for the neural network
 /// 
 /// # Arguments
 /// * `num_features` - Number of input features
 /// * `num_hidden` - Number of hidden neurons
+/// * `initializer` - How to draw the weights; `Initializer::HeNormal` suits the ReLU hidden layer.
+///   A constant gives every hidden unit the same weights, so they all learn the same feature
+/// * `rng` - Source of the random weights; the same state gives the same weights
 /// 
 /// # Returns
-/// * Tuple containing (weights_matrix, bias_vector)
-pub fn initialize_weights_and_bias(num_features: usize, num_hidden: usize) -> (Vec<Vec<f64>>, Vec<f64>) {
-    let weights = vec![vec![0.01; num_hidden]; num_features];
-    let biases = vec![0.01; num_hidden];
-    (weights, biases)
+/// * Tuple containing (weights_matrix, bias_vector), with zero biases
+pub fn initialize_weights_and_bias<T: Float>(num_features: usize, num_hidden: usize, initializer: Initializer, rng: &mut Rng) -> (Matrix<T>, Vec<T>) {
+    (initializer.weights(num_features, num_hidden, rng), vec![T::ZERO; num_hidden])
 }
 
 /// ReLU activation function
 /// Returns 0 for negative inputs, x for positive inputs
-pub fn relu(x: f64) -> f64 {
-    if x < 0.0 {
-        0.0
+pub fn relu<T: Float>(x: T) -> T {
+    if x < T::ZERO {
+        T::ZERO
     } else {
         x
     }
@@ -29,30 +39,39 @@ pub fn relu(x: f64) -> f64 {
 
 /// Sigmoid activation function
 /// Maps any real number to a value between 0 and 1
-pub fn sigmoid(x: f64) -> f64 {
-    1.0 / (1.0 + (-x).exp())
+pub fn sigmoid<T: Float>(x: T) -> T {
+    T::ONE / (T::ONE + (-x).exp())
 }
 
 // Apply ReLU activation element-wise to a vector
-pub fn apply_relu(input: &Vec<f64>) -> Vec<f64> {
-    input.iter().map(|&x| relu(x)).collect()
-}
-
-// Forward pass function
-pub fn forward_pass(
-    segment: &Vec<Vec<f64>>,
-    weights_input_hidden: &Vec<Vec<f64>>,
-    bias_hidden: &Vec<f64>,
-    weights_hidden_output: &Vec<f64>,
-    bias_output: f64,
-) -> (f64, Vec<f64>) {
+pub fn apply_relu<T: Float>(input: &[T]) -> Vec<T> {
+    let mut output = vec![T::ZERO; input.len()];
+    T::relu_into(simd::active_level(), input, &mut output);
+    output
+}
+
+// Apply sigmoid activation element-wise to a vector
+pub fn apply_sigmoid<T: Float>(input: &[T]) -> Vec<T> {
+    let mut output = vec![T::ZERO; input.len()];
+    T::sigmoid_into(simd::active_level(), input, &mut output);
+    output
+}
+
+// Forward pass function for one segment (a flat feature vector)
+pub fn forward_pass<T: Float>(
+    segment: &[T],
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+) -> (T, Vec<T>) {
     // Calculate hidden layer raw values
-    let hidden_raw_matrix = matrix_multiply(segment, weights_input_hidden);
-    let hidden_raw = &hidden_raw_matrix[0]; // Assuming single sample
+    let hidden_raw_matrix = matrix_multiply(MatrixView::from_row(segment), weights_input_hidden);
+    let hidden_raw = hidden_raw_matrix.row(0);
     
     // Add bias to hidden layer
-    let hidden_with_bias: Vec<f64> = hidden_raw.iter().zip(bias_hidden.iter())
-        .map(|(h, b)| h + b)
+    let hidden_with_bias: Vec<T> = hidden_raw.iter().zip(bias_hidden.iter())
+        .map(|(&h, &b)| h + b)
         .collect();
     
     // Apply ReLU activation
@@ -65,35 +84,77 @@ pub fn forward_pass(
     (output, hidden_activated)
 }
 
+// Hidden layer for a whole batch: ReLU(segments * W + b), one row per segment
+fn hidden_layer_batch<T: Float>(segments: &Matrix<T>, weights_input_hidden: &Matrix<T>, bias_hidden: &[T]) -> Matrix<T> {
+    let mut hidden = matrix_multiply(segments, weights_input_hidden);
+    add_row_vector_inplace(&mut hidden, bias_hidden);
+    hidden.data = apply_relu(&hidden.data);
+    hidden
+}
+
+// Batched forward pass: `segments` holds one flattened segment per row
+// One GEMM per layer; returns (output of every segment, hidden activations with one row per segment)
+pub fn forward_pass_batch<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+) -> (Vec<T>, Matrix<T>) {
+    let hidden_activated = hidden_layer_batch(segments, weights_input_hidden, bias_hidden);
+
+    // (N x hidden) times the hidden x 1 output weights
+    let output_raw = matrix_multiply(&hidden_activated, MatrixView::from_col(weights_hidden_output));
+    let output_with_bias: Vec<T> = output_raw.data.iter().map(|&o| o + bias_output).collect();
+
+    (apply_sigmoid(&output_with_bias), hidden_activated)
+}
+
 // Derivative functions
-pub fn relu_derivative(x: f64) -> f64 {
-    if x > 0.0 { 1.0 } else { 0.0 }
+pub fn relu_derivative<T: Float>(x: T) -> T {
+    if x > T::ZERO { T::ONE } else { T::ZERO }
 }
 
-pub fn sigmoid_derivative(x: f64) -> f64 {
-    x * (1.0 - x)
+pub fn sigmoid_derivative<T: Float>(x: T) -> T {
+    x * (T::ONE - x)
 }
 
 // Loss function (Binary Cross-Entropy)
-pub fn loss_function(output: &Vec<f64>, true_labels: &Vec<f64>) -> f64 {
-    let mut total_loss = 0.0;
+pub fn loss_function<T: Float>(output: &[T], true_labels: &[T]) -> T {
+    let mut total_loss = T::ZERO;
     
     for (y, t) in output.iter().zip(true_labels.iter()) {
-        let clamped_output = y.max(EPSILON).min(1.0 - EPSILON);
-        let loss = -(t * clamped_output.ln() + (1.0 - t) * (1.0 - clamped_output).ln());
+        let clamped_output = y.clamp(epsilon(), T::ONE - epsilon());
+        let loss = -(*t * clamped_output.ln() + (T::ONE - *t) * (T::ONE - clamped_output).ln());
         total_loss += loss;
     }
     
-    total_loss / output.len() as f64
+    total_loss / T::from_usize(output.len())
+}
+
+// Weighted Binary Cross-Entropy
+// Each sample is weighted by `class_weights[label]`; the sum is normalised by the total weight
+pub fn weighted_loss_function<T: Float>(output: &[T], true_labels: &[T], class_weights: &[T]) -> T {
+    let mut total_loss = T::ZERO;
+    let mut total_weight = T::ZERO;
+    
+    for (y, t) in output.iter().zip(true_labels.iter()) {
+        let weight = class_weights[t.to_f64() as usize];
+        let clamped_output = y.clamp(epsilon(), T::ONE - epsilon());
+        total_loss -= weight * (*t * clamped_output.ln() + (T::ONE - *t) * (T::ONE - clamped_output).ln());
+        total_weight += weight;
+    }
+    
+    total_loss / total_weight
 }
 
 // Find gradient for output layer
-pub fn find_gradient(output: &Vec<f64>, true_labels: &Vec<f64>) -> Vec<f64> {
+pub fn find_gradient<T: Float>(output: &[T], true_labels: &[T]) -> Vec<T> {
     let mut output_gradient = Vec::new();
     
     for (y, t) in output.iter().zip(true_labels.iter()) {
-        let clamped_output = y.max(EPSILON).min(1.0 - EPSILON);
-        let grad = ((-t / clamped_output) + (1.0 - t) / (1.0 - clamped_output)) * sigmoid_derivative(clamped_output);
+        let clamped_output = y.clamp(epsilon(), T::ONE - epsilon());
+        let grad = ((-*t / clamped_output) + (T::ONE - *t) / (T::ONE - clamped_output)) * sigmoid_derivative(clamped_output);
         output_gradient.push(grad);
     }
     
@@ -101,96 +162,117 @@ pub fn find_gradient(output: &Vec<f64>, true_labels: &Vec<f64>) -> Vec<f64> {
 }
 
 // Apply ReLU derivative element-wise
-pub fn apply_relu_derivative(input: &Vec<f64>) -> Vec<f64> {
+pub fn apply_relu_derivative<T: Float>(input: &[T]) -> Vec<T> {
     input.iter().map(|&x| relu_derivative(x)).collect()
 }
 
+// ReLU derivative of a whole batch of activations
+fn relu_derivative_matrix<T: Float>(activations: &Matrix<T>) -> Matrix<T> {
+    Matrix::from_vec(activations.rows, activations.cols, apply_relu_derivative(&activations.data))
+}
+
 // Backpropagation function
-pub fn backprop(
-    segments: &Vec<Vec<Vec<f64>>>,
-    weights_input_hidden: &Vec<Vec<f64>>,
-    bias_hidden: &Vec<f64>,
-    weights_hidden_output: &Vec<f64>,
-    bias_output: f64,
-    true_labels: &Vec<f64>,
-) -> (Vec<Vec<f64>>, Vec<f64>, Vec<f64>, f64) {
-    let mut grad_w_input_hidden_sum = initialize_gradient_matrix_like(weights_input_hidden);
-    let mut grad_b_hidden_sum = initialize_gradient_vector_like(bias_hidden);
-    let mut grad_w_hidden_output_sum = vec![0.0; weights_hidden_output.len()];
-    let mut grad_b_output_sum = 0.0;
-
-    for (segment, true_label) in segments.iter().zip(true_labels.iter()) {
-        let (output, hidden_activations) = forward_pass(
-            segment,
-            weights_input_hidden,
-            bias_hidden,
-            weights_hidden_output,
-            bias_output,
-        );
-        
-        let grad_output_layer = find_gradient(&vec![output], &vec![*true_label]);
-        let grad_output = grad_output_layer[0];
-
-        // Calculate gradients for output layer
-        let grad_w_hidden_output: Vec<f64> = hidden_activations.iter()
-            .map(|h| h * grad_output)
-            .collect();
-        let grad_b_output = grad_output;
-
-        // Calculate error for hidden layer
-        let hidden_error_raw: Vec<f64> = weights_hidden_output.iter()
-            .map(|w| w * grad_output)
-            .collect();
-        let hidden_error = vector_elementwise_multiply(&hidden_error_raw, &apply_relu_derivative(&hidden_activations));
-
-        // Calculate gradients for input-hidden layer
-        let segment_transposed = matrix_transpose(segment);
-        let grad_w_input_hidden = matrix_multiply(&segment_transposed, &vec![hidden_error.clone()]);
-        let grad_b_hidden = hidden_error;
-
-        // Accumulate gradients
-        matrix_add_inplace(&mut grad_w_input_hidden_sum, &grad_w_input_hidden);
-        vector_add_inplace(&mut grad_b_hidden_sum, &grad_b_hidden);
-        vector_add_inplace(&mut grad_w_hidden_output_sum, &grad_w_hidden_output);
-        grad_b_output_sum += grad_b_output;
-    }
+// `segments` holds one flattened segment per row
+pub fn backprop<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+    true_labels: &[T],
+) -> (Matrix<T>, Vec<T>, Vec<T>, T) {
+    let sample_weights = vec![T::ONE; segments.rows];
+    backprop_weighted(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output, true_labels, &sample_weights)
+}
 
-    let n = segments.len() as f64;
-    (
-        matrix_scalar_divide(&grad_w_input_hidden_sum, n),
-        vector_scalar_divide(&grad_b_hidden_sum, n),
-        vector_scalar_divide(&grad_w_hidden_output_sum, n),
-        grad_b_output_sum / n,
-    )
+// Backpropagation with a weight per sample
+// Gradients are the weighted sum over samples divided by the total weight
+pub fn backprop_weighted<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+    true_labels: &[T],
+    sample_weights: &[T],
+) -> (Matrix<T>, Vec<T>, Vec<T>, T) {
+    backprop_weighted_with_outputs(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output, true_labels, sample_weights).1
+}
+
+// `backprop_weighted` that also returns the network outputs of its forward pass,
+// so training can report the loss without a second forward pass
+#[allow(clippy::type_complexity)]
+fn backprop_weighted_with_outputs<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+    true_labels: &[T],
+    sample_weights: &[T],
+) -> (Vec<T>, (Matrix<T>, Vec<T>, Vec<T>, T)) {
+    let (outputs, hidden_activations) = forward_pass_batch(
+        segments,
+        weights_input_hidden,
+        bias_hidden,
+        weights_hidden_output,
+        bias_output,
+    );
+
+    // Gradient of the loss with respect to each raw output, scaled by the sample weight
+    let grad_output: Vec<T> = find_gradient(&outputs, true_labels).iter().zip(sample_weights.iter())
+        .map(|(&g, &w)| w * g)
+        .collect();
+    let grad_output = MatrixView::from_col(&grad_output);
+
+    // Calculate gradients for output layer, summed over the batch
+    let grad_w_hidden_output = matrix_multiply(&matrix_transpose(&hidden_activations), grad_output).data;
+    let grad_b_output: T = grad_output.data.iter().copied().sum();
+
+    // Calculate error for hidden layer (N x hidden)
+    let hidden_error_raw = matrix_multiply(grad_output, MatrixView::from_row(weights_hidden_output));
+    let hidden_error = elementwise_multiply(&hidden_error_raw, &relu_derivative_matrix(&hidden_activations));
+
+    // Calculate gradients for input-hidden layer, summed over the batch
+    let grad_w_input_hidden = matrix_multiply(&matrix_transpose(segments), &hidden_error);
+    let grad_b_hidden = column_sums(&hidden_error);
+
+    let n: T = sample_weights.iter().copied().sum();
+    let gradients = (
+        matrix_scalar_divide(&grad_w_input_hidden, n),
+        vector_scalar_divide(&grad_b_hidden, n),
+        vector_scalar_divide(&grad_w_hidden_output, n),
+        grad_b_output / n,
+    );
+    (outputs, gradients)
 }
 
 // Update weights and biases using gradients
-pub fn update_weights(
-    weights_input_hidden: &mut Vec<Vec<f64>>,
-    bias_hidden: &mut Vec<f64>,
-    weights_hidden_output: &mut Vec<f64>,
-    bias_output: &mut f64,
-    grad_w_input_hidden: &Vec<Vec<f64>>,
-    grad_b_hidden: &Vec<f64>,
-    grad_w_hidden_output: &Vec<f64>,
-    grad_b_output: f64,
-    learning_rate: f64,
+#[allow(clippy::too_many_arguments)]
+pub fn update_weights<T: Float>(
+    weights_input_hidden: &mut Matrix<T>,
+    bias_hidden: &mut [T],
+    weights_hidden_output: &mut [T],
+    bias_output: &mut T,
+    grad_w_input_hidden: &Matrix<T>,
+    grad_b_hidden: &[T],
+    grad_w_hidden_output: &[T],
+    grad_b_output: T,
+    learning_rate: T,
 ) {
     // Update input-hidden weights
-    for i in 0..weights_input_hidden.len() {
-        for j in 0..weights_input_hidden[0].len() {
-            weights_input_hidden[i][j] -= learning_rate * grad_w_input_hidden[i][j];
-        }
+    for (w, g) in weights_input_hidden.data.iter_mut().zip(grad_w_input_hidden.data.iter()) {
+        *w -= learning_rate * *g;
     }
 
     // Update hidden bias
-    for i in 0..bias_hidden.len() {
-        bias_hidden[i] -= learning_rate * grad_b_hidden[i];
+    for (b, g) in bias_hidden.iter_mut().zip(grad_b_hidden.iter()) {
+        *b -= learning_rate * *g;
     }
 
     // Update hidden-output weights
-    for i in 0..weights_hidden_output.len() {
-        weights_hidden_output[i] -= learning_rate * grad_w_hidden_output[i];
+    for (w, g) in weights_hidden_output.iter_mut().zip(grad_w_hidden_output.iter()) {
+        *w -= learning_rate * *g;
     }
 
     // Update output bias
@@ -200,12 +282,12 @@ pub fn update_weights(
 // Utility functions for training
 
 // Calculate accuracy for binary classification
-pub fn calculate_accuracy(outputs: &Vec<f64>, true_labels: &Vec<f64>) -> f64 {
+pub fn calculate_accuracy<T: Float>(outputs: &[T], true_labels: &[T]) -> f64 {
     let mut correct = 0;
     let total = outputs.len();
     
     for (output, true_label) in outputs.iter().zip(true_labels.iter()) {
-        let predicted = if *output > 0.5 { 1.0 } else { 0.0 };
+        let predicted = if *output > T::from_f64(0.5) { T::ONE } else { T::ZERO };
         if predicted == *true_label {
             correct += 1;
         }
@@ -215,41 +297,55 @@ pub fn calculate_accuracy(outputs: &Vec<f64>, true_labels: &Vec<f64>) -> f64 {
 }
 
 // Train the neural network for one epoch
-pub fn train_epoch(
-    segments: &Vec<Vec<Vec<f64>>>,
-    true_labels: &Vec<f64>,
-    weights_input_hidden: &mut Vec<Vec<f64>>,
-    bias_hidden: &mut Vec<f64>,
-    weights_hidden_output: &mut Vec<f64>,
-    bias_output: &mut f64,
-    learning_rate: f64,
-) -> f64 {
-    // Forward pass to get predictions
-    let mut outputs = Vec::new();
-    for segment in segments {
-        let (output, _) = forward_pass(
-            segment,
-            weights_input_hidden,
-            bias_hidden,
-            weights_hidden_output,
-            *bias_output,
-        );
-        outputs.push(output);
-    }
-    
-    // Calculate loss
-    let loss = loss_function(&outputs, true_labels);
-    
-    // Backward pass
-    let (grad_w_input_hidden, grad_b_hidden, grad_w_hidden_output, grad_b_output) = backprop(
+pub fn train_epoch<T: Float>(
+    segments: &Matrix<T>,
+    true_labels: &[T],
+    weights_input_hidden: &mut Matrix<T>,
+    bias_hidden: &mut [T],
+    weights_hidden_output: &mut [T],
+    bias_output: &mut T,
+    learning_rate: T,
+) -> T {
+    train_epoch_weighted(
+        segments,
+        true_labels,
+        &[T::ONE, T::ONE],
+        weights_input_hidden,
+        bias_hidden,
+        weights_hidden_output,
+        bias_output,
+        learning_rate,
+    )
+}
+
+// Train the neural network for one epoch with per-class loss weights
+// `class_weights` holds the weight of label 0 and label 1
+#[allow(clippy::too_many_arguments)]
+pub fn train_epoch_weighted<T: Float>(
+    segments: &Matrix<T>,
+    true_labels: &[T],
+    class_weights: &[T],
+    weights_input_hidden: &mut Matrix<T>,
+    bias_hidden: &mut [T],
+    weights_hidden_output: &mut [T],
+    bias_output: &mut T,
+    learning_rate: T,
+) -> T {
+    // Forward and backward pass
+    let sample_weights: Vec<T> = true_labels.iter().map(|t| class_weights[t.to_f64() as usize]).collect();
+    let (outputs, (grad_w_input_hidden, grad_b_hidden, grad_w_hidden_output, grad_b_output)) = backprop_weighted_with_outputs(
         segments,
         weights_input_hidden,
         bias_hidden,
         weights_hidden_output,
         *bias_output,
         true_labels,
+        &sample_weights,
     );
     
+    // Loss of the same forward pass, before the update
+    let loss = weighted_loss_function(&outputs, true_labels, class_weights);
+    
     // Update weights
     update_weights(
         weights_input_hidden,
@@ -267,13 +363,13 @@ pub fn train_epoch(
 }
 
 // Predict using the trained model
-pub fn predict(
-    segment: &Vec<Vec<f64>>,
-    weights_input_hidden: &Vec<Vec<f64>>,
-    bias_hidden: &Vec<f64>,
-    weights_hidden_output: &Vec<f64>,
-    bias_output: f64,
-) -> f64 {
+pub fn predict<T: Float>(
+    segment: &[T],
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+) -> T {
     let (output, _) = forward_pass(
         segment,
         weights_input_hidden,
@@ -285,14 +381,301 @@ pub fn predict(
 }
 
 // Batch prediction
-pub fn predict_batch(
-    segments: &Vec<Vec<Vec<f64>>>,
-    weights_input_hidden: &Vec<Vec<f64>>,
-    bias_hidden: &Vec<f64>,
-    weights_hidden_output: &Vec<f64>,
-    bias_output: f64,
-) -> Vec<f64> {
-    segments.iter()
-        .map(|segment| predict(segment, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output))
+pub fn predict_batch<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &[T],
+    bias_output: T,
+) -> Vec<T> {
+    forward_pass_batch(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output).0
+}
+
+// Multi-class classification (softmax output layer)
+
+/// Numerically stable softmax
+/// Subtracts the largest logit before exponentiating so large logits cannot overflow
+pub fn softmax<T: Float>(logits: &[T]) -> Vec<T> {
+    let max = logits.iter().copied().fold(T::NEG_INFINITY, T::max);
+    let exps: Vec<T> = logits.iter().map(|&x| (x - max).exp()).collect();
+    let sum: T = exps.iter().copied().sum();
+    exps.iter().map(|&e| e / sum).collect()
+}
+
+/// Index of the largest value (first one on ties)
+pub fn argmax<T: Float>(values: &[T]) -> usize {
+    let mut best = 0;
+    for (i, &value) in values.iter().enumerate() {
+        if value > values[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+// Loss function (Categorical Cross-Entropy), averaged over samples
+// `probabilities` and `targets` hold one sample per row; targets are one-hot (or soft) class distributions
+pub fn categorical_cross_entropy<T: Float>(probabilities: &Matrix<T>, targets: &Matrix<T>) -> T {
+    let mut total_loss = T::ZERO;
+
+    for (p, t) in probabilities.iter_rows().zip(targets.iter_rows()) {
+        for (prob, target) in p.iter().zip(t.iter()) {
+            if *target != T::ZERO {
+                total_loss -= *target * prob.max(epsilon()).ln();
+            }
+        }
+    }
+
+    total_loss / T::from_usize(probabilities.rows)
+}
+
+// Weighted Categorical Cross-Entropy
+// Each sample is weighted by the class weight of its target; the sum is normalised by the total weight
+pub fn weighted_categorical_cross_entropy<T: Float>(probabilities: &Matrix<T>, targets: &Matrix<T>, class_weights: &[T]) -> T {
+    let mut total_loss = T::ZERO;
+    let mut total_weight = T::ZERO;
+
+    for (p, t) in probabilities.iter_rows().zip(targets.iter_rows()) {
+        let weight = target_weight(t, class_weights);
+        for (prob, target) in p.iter().zip(t.iter()) {
+            if *target != T::ZERO {
+                total_loss -= weight * *target * prob.max(epsilon()).ln();
+            }
+        }
+        total_weight += weight;
+    }
+
+    total_loss / total_weight
+}
+
+// Weight of one sample: the class weights averaged under its (one-hot or soft) target
+pub fn target_weight<T: Float>(target: &[T], class_weights: &[T]) -> T {
+    target.iter().zip(class_weights.iter()).map(|(&t, &w)| t * w).sum()
+}
+
+// Gradient of softmax + categorical cross-entropy with respect to the logits
+// The fused form (p - t) avoids the ill-conditioned softmax Jacobian
+pub fn softmax_cross_entropy_gradient<T: Float>(probabilities: &[T], target: &[T]) -> Vec<T> {
+    probabilities.iter().zip(target.iter()).map(|(&p, &t)| p - t).collect()
+}
+
+// Forward pass with K output logits
+// Returns (class probabilities, hidden activations)
+pub fn forward_pass_multiclass<T: Float>(
+    segment: &[T],
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+) -> (Vec<T>, Vec<T>) {
+    // Calculate hidden layer raw values
+    let hidden_raw_matrix = matrix_multiply(MatrixView::from_row(segment), weights_input_hidden);
+    let hidden_raw = hidden_raw_matrix.row(0);
+
+    // Add bias and apply ReLU activation
+    let hidden_with_bias: Vec<T> = hidden_raw.iter().zip(bias_hidden.iter())
+        .map(|(&h, &b)| h + b)
+        .collect();
+    let hidden_activated = apply_relu(&hidden_with_bias);
+
+    // Calculate output logits (hidden x K weights) and class probabilities
+    let logits_matrix = matrix_multiply(MatrixView::from_row(&hidden_activated), weights_hidden_output);
+    let logits: Vec<T> = logits_matrix.row(0).iter().zip(bias_output.iter())
+        .map(|(&l, &b)| l + b)
+        .collect();
+
+    (softmax(&logits), hidden_activated)
+}
+
+// Batched forward pass for the softmax network, one GEMM per layer
+// Returns (class probabilities, hidden activations), both with one row per segment
+pub fn forward_pass_multiclass_batch<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+) -> (Matrix<T>, Matrix<T>) {
+    let hidden_activated = hidden_layer_batch(segments, weights_input_hidden, bias_hidden);
+
+    let mut probabilities = matrix_multiply(&hidden_activated, weights_hidden_output);
+    add_row_vector_inplace(&mut probabilities, bias_output);
+    for row in probabilities.iter_rows_mut() {
+        let p = softmax(row);
+        row.copy_from_slice(&p);
+    }
+
+    (probabilities, hidden_activated)
+}
+
+/// Gradients of the softmax network: (grad_w_input_hidden, grad_b_hidden, grad_w_hidden_output, grad_b_output)
+pub type MulticlassGradients<T = f64> = (Matrix<T>, Vec<T>, Matrix<T>, Vec<T>);
+
+// Backpropagation for the softmax network, averaged over all segments
+pub fn backprop_multiclass<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+    targets: &Matrix<T>,
+) -> MulticlassGradients<T> {
+    let sample_weights = vec![T::ONE; segments.rows];
+    backprop_multiclass_weighted(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output, targets, &sample_weights)
+}
+
+// Backpropagation for the softmax network with a weight per sample
+// Gradients are the weighted sum over samples divided by the total weight
+pub fn backprop_multiclass_weighted<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+    targets: &Matrix<T>,
+    sample_weights: &[T],
+) -> MulticlassGradients<T> {
+    backprop_multiclass_weighted_with_probabilities(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output, targets, sample_weights).1
+}
+
+// `backprop_multiclass_weighted` that also returns the probabilities of its forward pass
+fn backprop_multiclass_weighted_with_probabilities<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+    targets: &Matrix<T>,
+    sample_weights: &[T],
+) -> (Matrix<T>, MulticlassGradients<T>) {
+    let (probabilities, hidden_activations) = forward_pass_multiclass_batch(
+        segments,
+        weights_input_hidden,
+        bias_hidden,
+        weights_hidden_output,
+        bias_output,
+    );
+
+    // Gradient with respect to the logits (N x K), each row scaled by its sample weight
+    let mut grad_logits = Matrix::zeros(probabilities.rows, probabilities.cols);
+    let rows = probabilities.iter_rows().zip(targets.iter_rows()).zip(sample_weights.iter());
+    for (grad_row, ((p, t), &weight)) in grad_logits.iter_rows_mut().zip(rows) {
+        for (g, d) in grad_row.iter_mut().zip(softmax_cross_entropy_gradient(p, t)) {
+            *g = weight * d;
+        }
+    }
+
+    // Calculate gradients for output layer, summed over the batch
+    let grad_w_hidden_output = matrix_multiply(&matrix_transpose(&hidden_activations), &grad_logits);
+    let grad_b_output = column_sums(&grad_logits);
+
+    // Calculate error for hidden layer (N x hidden)
+    let hidden_error_raw = matrix_multiply(&grad_logits, &matrix_transpose(weights_hidden_output));
+    let hidden_error = elementwise_multiply(&hidden_error_raw, &relu_derivative_matrix(&hidden_activations));
+
+    // Calculate gradients for input-hidden layer, summed over the batch
+    let grad_w_input_hidden = matrix_multiply(&matrix_transpose(segments), &hidden_error);
+    let grad_b_hidden = column_sums(&hidden_error);
+
+    let n: T = sample_weights.iter().copied().sum();
+    let gradients = (
+        matrix_scalar_divide(&grad_w_input_hidden, n),
+        vector_scalar_divide(&grad_b_hidden, n),
+        matrix_scalar_divide(&grad_w_hidden_output, n),
+        vector_scalar_divide(&grad_b_output, n),
+    );
+    (probabilities, gradients)
+}
+
+// Train the softmax network for one epoch
+// Returns the categorical cross-entropy computed before the update
+pub fn train_epoch_multiclass<T: Float>(
+    segments: &Matrix<T>,
+    targets: &Matrix<T>,
+    weights_input_hidden: &mut Matrix<T>,
+    bias_hidden: &mut [T],
+    weights_hidden_output: &mut Matrix<T>,
+    bias_output: &mut [T],
+    learning_rate: T,
+) -> T {
+    let class_weights = vec![T::ONE; bias_output.len()];
+    train_epoch_multiclass_weighted(
+        segments,
+        targets,
+        &class_weights,
+        weights_input_hidden,
+        bias_hidden,
+        weights_hidden_output,
+        bias_output,
+        learning_rate,
+    )
+}
+
+// Train the softmax network for one epoch with per-class loss weights
+// Returns the weighted categorical cross-entropy computed before the update
+#[allow(clippy::too_many_arguments)]
+pub fn train_epoch_multiclass_weighted<T: Float>(
+    segments: &Matrix<T>,
+    targets: &Matrix<T>,
+    class_weights: &[T],
+    weights_input_hidden: &mut Matrix<T>,
+    bias_hidden: &mut [T],
+    weights_hidden_output: &mut Matrix<T>,
+    bias_output: &mut [T],
+    learning_rate: T,
+) -> T {
+    let sample_weights: Vec<T> = targets.iter_rows().map(|t| target_weight(t, class_weights)).collect();
+    let (probabilities, (grad_w_input_hidden, grad_b_hidden, grad_w_hidden_output, grad_b_output)) = backprop_multiclass_weighted_with_probabilities(
+        segments,
+        weights_input_hidden,
+        bias_hidden,
+        weights_hidden_output,
+        bias_output,
+        targets,
+        &sample_weights,
+    );
+    let loss = weighted_categorical_cross_entropy(&probabilities, targets, class_weights);
+
+    // Plain SGD step on all four parameter tensors
+    matrix_add_inplace(weights_input_hidden, &scalar_multiply(&grad_w_input_hidden, -learning_rate));
+    matrix_add_inplace(weights_hidden_output, &scalar_multiply(&grad_w_hidden_output, -learning_rate));
+    for (b, g) in bias_hidden.iter_mut().zip(grad_b_hidden.iter()) {
+        *b -= learning_rate * *g;
+    }
+    for (b, g) in bias_output.iter_mut().zip(grad_b_output.iter()) {
+        *b -= learning_rate * *g;
+    }
+
+    loss
+}
+
+// Class probabilities for every segment, one row per segment
+pub fn predict_proba_batch<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+) -> Matrix<T> {
+    forward_pass_multiclass_batch(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output).0
+}
+
+// Predicted class index (argmax of the probabilities) for every segment
+pub fn predict_class_batch<T: Float>(
+    segments: &Matrix<T>,
+    weights_input_hidden: &Matrix<T>,
+    bias_hidden: &[T],
+    weights_hidden_output: &Matrix<T>,
+    bias_output: &[T],
+) -> Vec<usize> {
+    predict_proba_batch(segments, weights_input_hidden, bias_hidden, weights_hidden_output, bias_output)
+        .iter_rows()
+        .map(argmax)
         .collect()
-}
\ No newline at end of file
+}
+
+// Calculate accuracy for multi-class classification from predicted and true class indices
+pub fn calculate_multiclass_accuracy(predicted: &[usize], true_classes: &[usize]) -> f64 {
+    let correct = predicted.iter().zip(true_classes.iter()).filter(|(p, t)| p == t).count();
+    correct as f64 / predicted.len() as f64
+}
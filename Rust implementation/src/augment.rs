@@ -0,0 +1,213 @@
+use crate::rng::Rng;
+use std::f64::consts::PI;
+
+/// A random transformation of one segment
+///
+/// Segments use the `data_segmentation` layout: one row per sample,
+/// `[index, lead_1, lead_2, ...]`. Column 0 is never modified and the
+/// segment length is always preserved.
+pub trait Augmentation {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng);
+}
+
+/// Number of signal columns (leads) in a segment
+fn num_leads(segment: &[Vec<f64>]) -> usize {
+    segment.first().map_or(0, |row| row.len().saturating_sub(1))
+}
+
+/// Linearly interpolate one lead at a fractional sample position, clamping at the edges
+fn interpolate(segment: &[Vec<f64>], lead: usize, position: f64) -> f64 {
+    let last = segment.len() - 1;
+    let position = position.clamp(0.0, last as f64);
+    let lo = position.floor() as usize;
+    let hi = (lo + 1).min(last);
+    let frac = position - lo as f64;
+    segment[lo][lead] * (1.0 - frac) + segment[hi][lead] * frac
+}
+
+/// Resample every lead at the given positions (one per output sample)
+fn resample(segment: &mut [Vec<f64>], positions: &[f64]) {
+    let original = segment.to_vec();
+    for lead in 1..=num_leads(&original) {
+        for (row, &position) in segment.iter_mut().zip(positions.iter()) {
+            row[lead] = interpolate(&original, lead, position);
+        }
+    }
+}
+
+/// Additive white Gaussian noise
+pub struct GaussianNoise {
+    pub std_dev: f64,
+}
+
+impl Augmentation for GaussianNoise {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        for row in segment.iter_mut() {
+            for value in row.iter_mut().skip(1) {
+                *value += rng.normal(0.0, self.std_dev);
+            }
+        }
+    }
+}
+
+/// Multiply all leads by one random factor in [min, max)
+pub struct AmplitudeScale {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Augmentation for AmplitudeScale {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        let factor = rng.uniform(self.min, self.max);
+        for row in segment.iter_mut() {
+            for value in row.iter_mut().skip(1) {
+                *value *= factor;
+            }
+        }
+    }
+}
+
+/// Add a random constant offset in [-max_shift, max_shift) to each lead
+pub struct BaselineShift {
+    pub max_shift: f64,
+}
+
+impl Augmentation for BaselineShift {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        let offsets: Vec<f64> = (0..num_leads(segment))
+            .map(|_| rng.uniform(-self.max_shift, self.max_shift))
+            .collect();
+        for row in segment.iter_mut() {
+            for (value, offset) in row.iter_mut().skip(1).zip(offsets.iter()) {
+                *value += offset;
+            }
+        }
+    }
+}
+
+/// Shift the signal by up to `max_shift` samples in either direction, repeating the edge sample
+pub struct TimeShift {
+    pub max_shift: usize,
+}
+
+impl Augmentation for TimeShift {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        if segment.is_empty() {
+            return;
+        }
+        let shift = rng.below(2 * self.max_shift + 1) as f64 - self.max_shift as f64;
+        let positions: Vec<f64> = (0..segment.len()).map(|i| i as f64 - shift).collect();
+        resample(segment, &positions);
+    }
+}
+
+/// Smooth, monotone time warp: locally speeds up and slows down the signal
+/// by up to `max_strength` (0..1) while keeping both ends in place
+pub struct TimeWarp {
+    pub max_strength: f64,
+}
+
+impl Augmentation for TimeWarp {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        if segment.len() < 2 {
+            return;
+        }
+        let len = (segment.len() - 1) as f64;
+        let strength = rng.uniform(0.0, self.max_strength.min(0.99));
+        let cycles = (1 + rng.below(2)) as f64;
+
+        // p(t) = t + k sin(2 pi c t / L); p'(t) = 1 + strength cos(...) stays positive
+        let k = strength * len / (2.0 * PI * cycles);
+        let positions: Vec<f64> = (0..segment.len())
+            .map(|i| {
+                let t = i as f64;
+                t + k * (2.0 * PI * cycles * t / len).sin()
+            })
+            .collect();
+        resample(segment, &positions);
+    }
+}
+
+/// Take a random window covering at least `min_fraction` of the segment and stretch it back to full length
+pub struct RandomCrop {
+    pub min_fraction: f64,
+}
+
+impl Augmentation for RandomCrop {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        if segment.len() < 2 {
+            return;
+        }
+        let len = (segment.len() - 1) as f64;
+        let fraction = rng.uniform(self.min_fraction.clamp(0.0, 1.0), 1.0);
+        let start = rng.uniform(0.0, len * (1.0 - fraction));
+        let positions: Vec<f64> = (0..segment.len())
+            .map(|i| start + i as f64 * fraction)
+            .collect();
+        resample(segment, &positions);
+    }
+}
+
+/// Zero each lead with the given probability, always keeping at least one lead
+pub struct LeadDropout {
+    pub probability: f64,
+}
+
+impl Augmentation for LeadDropout {
+    fn apply(&self, segment: &mut [Vec<f64>], rng: &mut Rng) {
+        let leads = num_leads(segment);
+        let mut dropped: Vec<bool> = (0..leads).map(|_| rng.chance(self.probability)).collect();
+        if leads > 0 && dropped.iter().all(|&d| d) {
+            dropped[rng.below(leads)] = false;
+        }
+        for row in segment.iter_mut() {
+            for (value, &drop) in row.iter_mut().skip(1).zip(dropped.iter()) {
+                if drop {
+                    *value = 0.0;
+                }
+            }
+        }
+    }
+}
+
+/// Seeded sequence of augmentations, each applied with its own probability
+///
+/// The pipeline works on copies, so the stored dataset is never modified.
+/// Its generator advances with every batch, so each epoch sees different
+/// augmentations while a run as a whole stays reproducible from the seed.
+pub struct Pipeline {
+    steps: Vec<(Box<dyn Augmentation>, f64)>,
+    rng: Rng,
+}
+
+impl Pipeline {
+    pub fn new(seed: u64) -> Self {
+        Pipeline { steps: Vec::new(), rng: Rng::new(seed) }
+    }
+
+    /// Append an augmentation that is applied to each segment with the given probability
+    pub fn with<A: Augmentation + 'static>(mut self, augmentation: A, probability: f64) -> Self {
+        self.steps.push((Box::new(augmentation), probability));
+        self
+    }
+
+    /// Augment a single segment in place
+    pub fn apply(&mut self, segment: &mut [Vec<f64>]) {
+        for (augmentation, probability) in &self.steps {
+            if self.rng.chance(*probability) {
+                augmentation.apply(segment, &mut self.rng);
+            }
+        }
+    }
+
+    /// Return augmented copies of a (mini-)batch of segments
+    pub fn apply_batch(&mut self, batch: &[Vec<Vec<f64>>]) -> Vec<Vec<Vec<f64>>> {
+        batch.iter()
+            .map(|segment| {
+                let mut copy = segment.clone();
+                self.apply(&mut copy);
+                copy
+            })
+            .collect()
+    }
+}
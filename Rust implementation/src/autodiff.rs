@@ -0,0 +1,372 @@
+use crate::brains::{apply_relu, apply_sigmoid, softmax};
+use crate::float::Float;
+use crate::loss::Loss;
+use crate::matrix_math::*;
+use std::ops::Index;
+
+/// Handle to a matrix recorded on a `Tape`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Var(usize);
+
+/// How a recorded value was computed from earlier ones
+#[derive(Debug, Clone)]
+enum Op<T> {
+    Input,
+    Add(Var, Var),
+    Sub(Var, Var),
+    Mul(Var, Var),
+    Scale(Var, T),
+    MatMul(Var, Var),
+    AddRow(Var, Var),
+    Transpose(Var),
+    Relu(Var),
+    Sigmoid(Var),
+    Exp(Var),
+    Ln(Var),
+    Softmax(Var),
+    Sum(Var),
+    Mean(Var),
+    ColumnSums(Var),
+    /// Scalar loss of `Var`; the gradient with respect to it is computed with the value
+    Loss(Var, Matrix<T>),
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: Matrix<T>,
+    op: Op<T>,
+}
+
+/// Reverse-mode automatic differentiation over `Matrix`
+///
+/// Every operation evaluates immediately and appends its result to the tape;
+/// `backward` then walks the tape in reverse, applying the chain rule once per
+/// recorded operation. Gradients of values used several times are summed.
+///
+/// Leaves (`input`) are usually network inputs and parameters; after
+/// `let grads = tape.backward(loss)`, `grads[w]` is dL/dw with the shape of `w`.
+#[derive(Debug, Clone)]
+pub struct Tape<T: Float = f64> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Float> Default for Tape<T> {
+    fn default() -> Self {
+        Tape::new()
+    }
+}
+
+/// Element-wise function of a matrix
+fn map<T: Float>(matrix: &Matrix<T>, f: impl Fn(T) -> T) -> Matrix<T> {
+    Matrix::from_vec(matrix.rows, matrix.cols, matrix.data.iter().map(|&x| f(x)).collect())
+}
+
+/// Element-wise function of two matrices of the same shape
+fn zip_map<T: Float>(a: &Matrix<T>, b: &Matrix<T>, f: impl Fn(T, T) -> T) -> Matrix<T> {
+    if a.shape() != b.shape() {
+        panic!("Matrix dimensions do not match: {}x{} and {}x{}", a.rows, a.cols, b.rows, b.cols);
+    }
+    Matrix::from_vec(a.rows, a.cols, a.data.iter().zip(&b.data).map(|(&x, &y)| f(x, y)).collect())
+}
+
+/// Check the per-row weights of a loss and return their sum
+fn total_weight<T: Float>(predictions: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T]) -> T {
+    if predictions.shape() != targets.shape() || sample_weights.len() != predictions.rows {
+        panic!(
+            "Loss needs predictions and targets of the same shape and one weight per row (got {}x{}, {}x{} and {} weights)",
+            predictions.rows, predictions.cols, targets.rows, targets.cols, sample_weights.len()
+        );
+    }
+    sample_weights.iter().copied().sum()
+}
+
+impl<T: Float> Tape<T> {
+    pub fn new() -> Self {
+        Tape { nodes: Vec::new() }
+    }
+
+    /// Number of recorded values
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Current value of a recorded matrix
+    pub fn value(&self, var: Var) -> &Matrix<T> {
+        &self.nodes[var.0].value
+    }
+
+    fn push(&mut self, value: Matrix<T>, op: Op<T>) -> Var {
+        self.nodes.push(Node { value, op });
+        Var(self.nodes.len() - 1)
+    }
+
+    /// Record a matrix with respect to which gradients can be taken (inputs, weights, biases)
+    pub fn input(&mut self, value: Matrix<T>) -> Var {
+        self.push(value, Op::Input)
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        let value = matrix_add(self.value(a), self.value(b));
+        self.push(value, Op::Add(a, b))
+    }
+
+    pub fn sub(&mut self, a: Var, b: Var) -> Var {
+        let value = zip_map(self.value(a), self.value(b), |x, y| x - y);
+        self.push(value, Op::Sub(a, b))
+    }
+
+    /// Element-wise product
+    pub fn mul(&mut self, a: Var, b: Var) -> Var {
+        let value = elementwise_multiply(self.value(a), self.value(b));
+        self.push(value, Op::Mul(a, b))
+    }
+
+    pub fn scale(&mut self, a: Var, scalar: T) -> Var {
+        let value = scalar_multiply(self.value(a), scalar);
+        self.push(value, Op::Scale(a, scalar))
+    }
+
+    pub fn matmul(&mut self, a: Var, b: Var) -> Var {
+        let value = matrix_multiply(self.value(a), self.value(b));
+        self.push(value, Op::MatMul(a, b))
+    }
+
+    /// Add a 1 x n row (e.g. a bias) to every row of an m x n matrix
+    pub fn add_row(&mut self, a: Var, row: Var) -> Var {
+        let mut value = self.value(a).clone();
+        let bias = self.value(row);
+        if bias.rows != 1 {
+            panic!("add_row needs a 1xn row, got {}x{}", bias.rows, bias.cols);
+        }
+        add_row_vector_inplace(&mut value, &bias.data);
+        self.push(value, Op::AddRow(a, row))
+    }
+
+    pub fn transpose(&mut self, a: Var) -> Var {
+        let value = matrix_transpose(self.value(a));
+        self.push(value, Op::Transpose(a))
+    }
+
+    pub fn relu(&mut self, a: Var) -> Var {
+        let input = self.value(a);
+        let value = Matrix::from_vec(input.rows, input.cols, apply_relu(&input.data));
+        self.push(value, Op::Relu(a))
+    }
+
+    pub fn sigmoid(&mut self, a: Var) -> Var {
+        let input = self.value(a);
+        let value = Matrix::from_vec(input.rows, input.cols, apply_sigmoid(&input.data));
+        self.push(value, Op::Sigmoid(a))
+    }
+
+    pub fn exp(&mut self, a: Var) -> Var {
+        let value = map(self.value(a), T::exp);
+        self.push(value, Op::Exp(a))
+    }
+
+    /// Natural logarithm; no clamping, so zeros give -inf
+    pub fn ln(&mut self, a: Var) -> Var {
+        let value = map(self.value(a), T::ln);
+        self.push(value, Op::Ln(a))
+    }
+
+    /// Softmax of every row
+    pub fn softmax(&mut self, a: Var) -> Var {
+        let mut value = self.value(a).clone();
+        for row in value.iter_rows_mut() {
+            let p = softmax(row);
+            row.copy_from_slice(&p);
+        }
+        self.push(value, Op::Softmax(a))
+    }
+
+    /// Sum of all elements, as a 1 x 1 matrix
+    pub fn sum(&mut self, a: Var) -> Var {
+        let value = Matrix::filled(1, 1, matrix_sum(self.value(a)));
+        self.push(value, Op::Sum(a))
+    }
+
+    /// Mean of all elements, as a 1 x 1 matrix
+    pub fn mean(&mut self, a: Var) -> Var {
+        let input = self.value(a);
+        let value = Matrix::filled(1, 1, matrix_sum(input) / T::from_usize(input.data.len()));
+        self.push(value, Op::Mean(a))
+    }
+
+    /// Sum of every column, as a 1 x n matrix
+    pub fn column_sums(&mut self, a: Var) -> Var {
+        let input = self.value(a);
+        let value = Matrix::from_vec(1, input.cols, column_sums(input));
+        self.push(value, Op::ColumnSums(a))
+    }
+
+    /// Weighted mean `loss` of network outputs against constant targets, as a 1 x 1 matrix
+    ///
+    /// Same value and gradient as `Loss::evaluate`, including its clamping of probabilities.
+    /// That clamping loses the gradient once a sigmoid or softmax saturates, so binary
+    /// cross-entropy of a `sigmoid` and categorical cross-entropy of a `softmax` are rejected:
+    /// use `sigmoid_cross_entropy` or `softmax_cross_entropy` on the logits instead.
+    pub fn loss(&mut self, predictions: Var, targets: &Matrix<T>, sample_weights: &[T], loss: Loss) -> Var {
+        match (loss, &self.nodes[predictions.0].op) {
+            (Loss::BinaryCrossEntropy, Op::Sigmoid(_)) => {
+                panic!("Tape::loss of a sigmoid loses its gradient at saturation; use sigmoid_cross_entropy on the logits")
+            }
+            (Loss::CategoricalCrossEntropy, Op::Softmax(_)) => {
+                panic!("Tape::loss of a softmax loses its gradient at saturation; use softmax_cross_entropy on the logits")
+            }
+            _ => {}
+        }
+        let (value, gradient) = loss.evaluate(self.value(predictions), targets, sample_weights);
+        self.push(Matrix::filled(1, 1, value), Op::Loss(predictions, gradient))
+    }
+
+    /// Binary cross-entropy of `sigmoid(logits)`, computed from the logits
+    ///
+    /// Unlike `Loss::evaluate` on the sigmoid output, nothing is clamped: the value stays
+    /// finite and the gradient is exactly `sigmoid(z) - t` for saturated logits too.
+    pub fn sigmoid_cross_entropy(&mut self, logits: Var, targets: &Matrix<T>, sample_weights: &[T]) -> Var {
+        let z = self.value(logits);
+        let total = total_weight(z, targets, sample_weights);
+        let mut loss = T::ZERO;
+        let mut gradient = Matrix::zeros(z.rows, z.cols);
+        let rows = z.iter_rows().zip(targets.iter_rows()).zip(sample_weights);
+        for (grad_row, ((z, t), &weight)) in gradient.iter_rows_mut().zip(rows) {
+            for ((g, &z), &t) in grad_row.iter_mut().zip(z).zip(t) {
+                // -t ln(s(z)) - (1 - t) ln(1 - s(z)) = max(z, 0) - z t + ln(1 + e^-|z|)
+                loss += weight * (z.max(T::ZERO) - z * t + (T::ONE + (-z.abs()).exp()).ln());
+                *g = weight / total * (T::ONE / (T::ONE + (-z).exp()) - t);
+            }
+        }
+        self.push(Matrix::filled(1, 1, loss / total), Op::Loss(logits, gradient))
+    }
+
+    /// Categorical cross-entropy of the row-wise softmax of `logits`, computed from the logits
+    pub fn softmax_cross_entropy(&mut self, logits: Var, targets: &Matrix<T>, sample_weights: &[T]) -> Var {
+        let z = self.value(logits);
+        let total = total_weight(z, targets, sample_weights);
+        let mut loss = T::ZERO;
+        let mut gradient = Matrix::zeros(z.rows, z.cols);
+        let rows = z.iter_rows().zip(targets.iter_rows()).zip(sample_weights);
+        for (grad_row, ((z, t), &weight)) in gradient.iter_rows_mut().zip(rows) {
+            // ln p_j = z_j - logsumexp(z)
+            let max = z.iter().copied().fold(T::NEG_INFINITY, T::max);
+            let log_sum = max + z.iter().map(|&x| (x - max).exp()).sum::<T>().ln();
+            let target_sum: T = t.iter().copied().sum();
+            for ((g, (&z_j, &t_j)), p_j) in grad_row.iter_mut().zip(z.iter().zip(t)).zip(softmax(z)) {
+                loss -= weight * t_j * (z_j - log_sum);
+                *g = weight / total * (p_j * target_sum - t_j);
+            }
+        }
+        self.push(Matrix::filled(1, 1, loss / total), Op::Loss(logits, gradient))
+    }
+
+    /// Gradients of a 1 x 1 `output` with respect to every value recorded before it
+    ///
+    /// Values the output does not depend on get zero gradients.
+    pub fn backward(&self, output: Var) -> Gradients<T> {
+        let shape = self.value(output).shape();
+        if shape != (1, 1) {
+            panic!("backward needs a 1x1 output, got {}x{}", shape.0, shape.1);
+        }
+
+        let mut grads: Vec<Option<Matrix<T>>> = (0..self.nodes.len()).map(|_| None).collect();
+        grads[output.0] = Some(Matrix::filled(1, 1, T::ONE));
+        for i in (0..=output.0).rev() {
+            if let Some(grad) = grads[i].take() {
+                self.propagate(i, &grad, &mut grads);
+                grads[i] = Some(grad);
+            }
+        }
+
+        let grads = grads.into_iter().zip(&self.nodes)
+            .map(|(grad, node)| grad.unwrap_or_else(|| Matrix::zeros(node.value.rows, node.value.cols)))
+            .collect();
+        Gradients { grads }
+    }
+
+    /// Chain rule for one node: add its inputs' share of `grad` to their gradients
+    fn propagate(&self, i: usize, grad: &Matrix<T>, grads: &mut [Option<Matrix<T>>]) {
+        let output = &self.nodes[i].value;
+        let mut accumulate = |var: Var, contribution: Matrix<T>| match &mut grads[var.0] {
+            Some(total) => matrix_add_inplace(total, &contribution),
+            slot => *slot = Some(contribution),
+        };
+
+        match &self.nodes[i].op {
+            Op::Input => {}
+            Op::Add(a, b) => {
+                accumulate(*a, grad.clone());
+                accumulate(*b, grad.clone());
+            }
+            Op::Sub(a, b) => {
+                accumulate(*a, grad.clone());
+                accumulate(*b, map(grad, |g| -g));
+            }
+            Op::Mul(a, b) => {
+                accumulate(*a, elementwise_multiply(grad, self.value(*b)));
+                accumulate(*b, elementwise_multiply(grad, self.value(*a)));
+            }
+            Op::Scale(a, scalar) => accumulate(*a, scalar_multiply(grad, *scalar)),
+            Op::MatMul(a, b) => {
+                accumulate(*a, matrix_multiply(grad, &matrix_transpose(self.value(*b))));
+                accumulate(*b, matrix_multiply(&matrix_transpose(self.value(*a)), grad));
+            }
+            Op::AddRow(a, row) => {
+                accumulate(*a, grad.clone());
+                accumulate(*row, Matrix::from_vec(1, grad.cols, column_sums(grad)));
+            }
+            Op::Transpose(a) => accumulate(*a, matrix_transpose(grad)),
+            // The output is positive exactly where the input was
+            Op::Relu(a) => accumulate(*a, zip_map(grad, output, |g, y| if y > T::ZERO { g } else { T::ZERO })),
+            Op::Sigmoid(a) => accumulate(*a, zip_map(grad, output, |g, y| g * y * (T::ONE - y))),
+            Op::Exp(a) => accumulate(*a, elementwise_multiply(grad, output)),
+            Op::Ln(a) => accumulate(*a, zip_map(grad, self.value(*a), |g, x| g / x)),
+            Op::Softmax(a) => {
+                // Jacobian-vector product of each row: y * (g - <g, y>)
+                let mut grad_input = Matrix::zeros(output.rows, output.cols);
+                let rows = output.iter_rows().zip(grad.iter_rows());
+                for (grad_row, (y, g)) in grad_input.iter_rows_mut().zip(rows) {
+                    let inner = dot_product(g, y);
+                    for ((gi, &y_j), &g_j) in grad_row.iter_mut().zip(y).zip(g) {
+                        *gi = y_j * (g_j - inner);
+                    }
+                }
+                accumulate(*a, grad_input);
+            }
+            Op::Sum(a) => {
+                let (rows, cols) = self.value(*a).shape();
+                accumulate(*a, Matrix::filled(rows, cols, grad.data[0]));
+            }
+            Op::Mean(a) => {
+                let (rows, cols) = self.value(*a).shape();
+                accumulate(*a, Matrix::filled(rows, cols, grad.data[0] / T::from_usize(rows * cols)));
+            }
+            Op::ColumnSums(a) => {
+                let mut grad_input = Matrix::zeros(self.value(*a).rows, grad.cols);
+                for row in grad_input.iter_rows_mut() {
+                    row.copy_from_slice(&grad.data);
+                }
+                accumulate(*a, grad_input);
+            }
+            Op::Loss(a, gradient) => accumulate(*a, scalar_multiply(gradient, grad.data[0])),
+        }
+    }
+}
+
+/// Result of `Tape::backward`: index it with a `Var` to get that value's gradient
+#[derive(Debug, Clone)]
+pub struct Gradients<T = f64> {
+    grads: Vec<Matrix<T>>,
+}
+
+impl<T> Index<Var> for Gradients<T> {
+    type Output = Matrix<T>;
+
+    fn index(&self, var: Var) -> &Matrix<T> {
+        &self.grads[var.0]
+    }
+}
@@ -0,0 +1,47 @@
+use crate::rng::Rng;
+
+/// How one epoch of training data is split into mini-batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Samples per gradient step
+    pub batch_size: usize,
+    /// Visit the samples in a new random order every epoch
+    pub shuffle: bool,
+    /// Skip the final batch when it is smaller than `batch_size`
+    pub drop_last: bool,
+    /// Shuffling seed; each epoch derives its own order from it
+    pub seed: u64,
+}
+
+impl BatchConfig {
+    /// Shuffled batches of `batch_size`, keeping the smaller last batch
+    pub fn new(batch_size: usize, seed: u64) -> Self {
+        BatchConfig { batch_size, shuffle: true, drop_last: false, seed }
+    }
+
+    /// The whole data set as one batch in its original order: one step per epoch
+    pub fn full_batch(num_samples: usize) -> Self {
+        BatchConfig { batch_size: num_samples.max(1), shuffle: false, drop_last: false, seed: 0 }
+    }
+
+    /// Row indices of every batch of `epoch`, in training order
+    ///
+    /// The order depends only on `seed` and `epoch`, so a run can be repeated exactly.
+    pub fn batches(&self, num_samples: usize, epoch: usize) -> Vec<Vec<usize>> {
+        if self.batch_size == 0 {
+            panic!("Batch size must be at least 1");
+        }
+
+        let mut order: Vec<usize> = (0..num_samples).collect();
+        if self.shuffle {
+            // SplitMix64 inside `Rng::new` turns these nearby seeds into unrelated streams
+            let epoch_seed = self.seed ^ (epoch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            Rng::new(epoch_seed).shuffle(&mut order);
+        }
+
+        order.chunks(self.batch_size)
+            .filter(|batch| !self.drop_last || batch.len() == self.batch_size)
+            .map(|batch| batch.to_vec())
+            .collect()
+    }
+}
@@ -0,0 +1,58 @@
+use crate::float::Float;
+use crate::layers::Param;
+
+/// Limit on the gradients applied by `Sequential` before every update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientClip {
+    /// Clamp every gradient element to [-limit, limit]; changes the gradient's direction
+    Value(f64),
+    /// Rescale all gradients together so their combined L2 norm is at most the limit;
+    /// keeps the direction
+    GlobalNorm(f64),
+}
+
+impl GradientClip {
+    /// Clip the gradients of `params` in place
+    ///
+    /// # Returns
+    /// * The global gradient norm before clipping, useful for spotting exploding gradients
+    pub fn apply<T: Float>(&self, params: &mut [&mut Param<T>]) -> f64 {
+        let norm = global_norm(params.iter().map(|p| &**p));
+        match *self {
+            GradientClip::Value(limit) => {
+                if limit.is_nan() || limit < 0.0 {
+                    panic!("GradientClip::Value limit must be a non-negative number, got {}", limit);
+                }
+                let limit = T::from_f64(limit);
+                for param in params.iter_mut() {
+                    for g in param.grad.data.iter_mut() {
+                        *g = g.clamp(-limit, limit);
+                    }
+                }
+            }
+            GradientClip::GlobalNorm(max_norm) => {
+                if max_norm.is_nan() || max_norm < 0.0 {
+                    panic!("GradientClip::GlobalNorm limit must be a non-negative number, got {}", max_norm);
+                }
+                if norm > max_norm {
+                    let scale = T::from_f64(max_norm / norm);
+                    for param in params.iter_mut() {
+                        for g in param.grad.data.iter_mut() {
+                            *g *= scale;
+                        }
+                    }
+                }
+            }
+        }
+        norm
+    }
+}
+
+/// L2 norm of every gradient element of `params` taken together, accumulated in f64
+pub fn global_norm<'a, T: Float + 'a>(params: impl IntoIterator<Item = &'a Param<T>>) -> f64 {
+    params.into_iter()
+        .flat_map(|p| p.grad.data.iter())
+        .map(|g| g.to_f64() * g.to_f64())
+        .sum::<f64>()
+        .sqrt()
+}
@@ -0,0 +1,157 @@
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::layers::{cached, Layer, Param};
+use crate::matrix_math::*;
+use crate::rng::Rng;
+
+/// Shape of a `Conv1D` layer
+///
+/// Rows of the input and output are time-major, one sample per row: the value of channel
+/// `c` at time `t` is at column `t * channels + c`. This is the layout produced by
+/// concatenating the per-time-step rows of a segment, as the training data is flattened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conv1DConfig {
+    /// Number of input channels, e.g. one per ECG lead
+    pub in_channels: usize,
+    /// Number of filters
+    pub out_channels: usize,
+    pub kernel_size: usize,
+    /// Time steps per input channel
+    pub input_length: usize,
+    pub stride: usize,
+    /// Zeros added at both ends of every channel
+    pub padding: usize,
+    /// Spacing between kernel taps; 1 for a contiguous kernel
+    pub dilation: usize,
+}
+
+impl Conv1DConfig {
+    /// Stride 1, no padding, no dilation
+    pub fn new(in_channels: usize, out_channels: usize, kernel_size: usize, input_length: usize) -> Self {
+        Conv1DConfig { in_channels, out_channels, kernel_size, input_length, stride: 1, padding: 0, dilation: 1 }
+    }
+
+    /// Time steps per output channel
+    pub fn output_length(&self) -> usize {
+        if self.in_channels == 0 || self.kernel_size == 0 || self.stride == 0 || self.dilation == 0 {
+            panic!("Conv1D channels, kernel size, stride and dilation must be at least 1");
+        }
+        let span = self.dilation * (self.kernel_size - 1) + 1;
+        let padded = self.input_length + 2 * self.padding;
+        if span > padded {
+            panic!("Conv1D kernel spans {} steps but the padded input has only {}", span, padded);
+        }
+        (padded - span) / self.stride + 1
+    }
+
+    /// Values per input row
+    pub fn input_size(&self) -> usize {
+        self.in_channels * self.input_length
+    }
+
+    /// Values per output row
+    pub fn output_size(&self) -> usize {
+        self.out_channels * self.output_length()
+    }
+
+    /// Input time step read by kernel tap `k` at output position `p`, or None inside the padding
+    fn input_step(&self, p: usize, k: usize) -> Option<usize> {
+        (p * self.stride + k * self.dilation).checked_sub(self.padding).filter(|&t| t < self.input_length)
+    }
+}
+
+/// Unfold every receptive field of a batch into a row
+///
+/// # Returns
+/// * A (batch * output_length) x (kernel_size * in_channels) matrix; row `b * output_length + p`
+///   holds the taps of output position `p` of sample `b`, tap by tap, with zeros for padding
+pub fn im2col<T: Float>(input: &Matrix<T>, config: &Conv1DConfig) -> Matrix<T> {
+    let (output_length, channels) = (config.output_length(), config.in_channels);
+    let width = config.kernel_size * channels;
+    let mut columns = Matrix::zeros(input.rows * output_length, width);
+    for (field, chunk) in columns.data.chunks_mut(width).enumerate() {
+        let sample = &input.data[(field / output_length) * input.cols..][..input.cols];
+        for k in 0..config.kernel_size {
+            if let Some(t) = config.input_step(field % output_length, k) {
+                chunk[k * channels..(k + 1) * channels].copy_from_slice(&sample[t * channels..(t + 1) * channels]);
+            }
+        }
+    }
+    columns
+}
+
+/// Inverse of `im2col` for gradients: sum every row's taps back into the input positions they came from
+pub fn col2im<T: Float>(columns: &Matrix<T>, batch_size: usize, config: &Conv1DConfig) -> Matrix<T> {
+    let (output_length, channels) = (config.output_length(), config.in_channels);
+    let width = config.kernel_size * channels;
+    let mut input = Matrix::zeros(batch_size, config.input_size());
+    for (field, chunk) in columns.data.chunks(width).enumerate() {
+        let sample = &mut input.data[(field / output_length) * config.input_size()..][..config.input_size()];
+        for k in 0..config.kernel_size {
+            if let Some(t) = config.input_step(field % output_length, k) {
+                vector_add_inplace(&mut sample[t * channels..(t + 1) * channels], &chunk[k * channels..(k + 1) * channels]);
+            }
+        }
+    }
+    input
+}
+
+/// 1D convolution (cross-correlation) over multi-channel sequences, computed as im2col + GEMM
+#[derive(Debug, Clone)]
+pub struct Conv1D<T = f64> {
+    pub config: Conv1DConfig,
+    /// (kernel_size * in_channels) x out_channels, one column per filter with its taps in `im2col` order
+    pub weights: Param<T>,
+    /// 1 x out_channels
+    pub bias: Param<T>,
+    /// `im2col` of the last input
+    columns: Option<Matrix<T>>,
+}
+
+impl<T: Float> Conv1D<T> {
+    /// Layer with filters drawn by `initializer` (fan-in kernel_size * in_channels) and a zero bias
+    pub fn new(config: Conv1DConfig, initializer: Initializer, rng: &mut Rng) -> Self {
+        // Fails early on a kernel that does not fit the input
+        config.output_length();
+        let weights = initializer.weights(config.kernel_size * config.in_channels, config.out_channels, rng);
+        Conv1D { config, weights: Param::new("weights", weights), bias: Param::new("bias", Matrix::zeros(1, config.out_channels)), columns: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Conv1D<T> {
+    fn name(&self) -> &'static str {
+        "Conv1D"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let config = &self.config;
+        if input.cols != config.input_size() {
+            panic!("Conv1D expects {} values per row ({} channels x {} steps), got {}", config.input_size(), config.in_channels, config.input_length, input.cols);
+        }
+        let columns = im2col(input, config);
+        let mut output = matrix_multiply(&columns, &self.weights.value);
+        add_row_vector_inplace(&mut output, &self.bias.value.data);
+        self.columns = Some(columns);
+        // Row b * output_length + p of the product is output position p of sample b,
+        // so the time-major output rows are the same data
+        Matrix::from_vec(input.rows, config.output_size(), output.data)
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let columns = cached(&self.columns, "Conv1D");
+        let config = &self.config;
+        let grad = Matrix::from_vec(columns.rows, config.out_channels, grad_output.data.clone());
+        self.weights.grad = matrix_multiply(&matrix_transpose(columns), &grad);
+        self.bias.grad = Matrix::from_vec(1, config.out_channels, column_sums(&grad));
+        let grad_columns = matrix_multiply(&grad, &matrix_transpose(&self.weights.value));
+        col2im(&grad_columns, grad_output.rows, config)
+    }
+
+    fn parameters(&self) -> Vec<&Param<T>> {
+        vec![&self.weights, &self.bias]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Param<T>> {
+        vec![&mut self.weights, &mut self.bias]
+    }
+}
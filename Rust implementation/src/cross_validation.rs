@@ -0,0 +1,238 @@
+use crate::brains::*;
+use crate::loss::Loss;
+use crate::matrix_math::Matrix;
+use crate::rng::Rng;
+use crate::sequential::Sequential;
+use crate::split::{select, unique_records, Split};
+
+/// How samples are assigned to folds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldStrategy {
+    /// Every fold has (nearly) the same class proportions as the whole dataset
+    Stratified,
+    /// Every record (patient) falls entirely inside one fold
+    GroupByPatient,
+}
+
+/// Build k stratified folds
+///
+/// Each class is shuffled and dealt round-robin across the folds, so fold
+/// sizes differ by at most one and class counts per fold differ by at most one.
+///
+/// # Returns
+/// * One `Split` per fold: the fold itself is the test set, the other k-1 folds the training set
+pub fn stratified_k_fold(classes: &[usize], k: usize, seed: u64) -> Vec<Split> {
+    if k < 2 {
+        panic!("Cross-validation needs at least 2 folds");
+    }
+    let mut rng = Rng::new(seed);
+    let num_classes = classes.iter().max().map_or(0, |&c| c + 1);
+    let mut fold_of = vec![0; classes.len()];
+    let mut next_fold = 0;
+
+    for class in 0..num_classes {
+        let mut members: Vec<usize> = (0..classes.len()).filter(|&i| classes[i] == class).collect();
+        rng.shuffle(&mut members);
+        for index in members {
+            fold_of[index] = next_fold;
+            next_fold = (next_fold + 1) % k;
+        }
+    }
+
+    folds_from_assignment(&fold_of, k)
+}
+
+/// Build k folds with every record in exactly one fold
+///
+/// Records are shuffled and then placed largest-first into the fold with the
+/// fewest samples so far, which keeps fold sizes as even as record sizes allow.
+pub fn group_k_fold(record_ids: &[String], k: usize, seed: u64) -> Vec<Split> {
+    if k < 2 {
+        panic!("Cross-validation needs at least 2 folds");
+    }
+    let mut records = unique_records(record_ids);
+    Rng::new(seed).shuffle(&mut records);
+
+    let count = |record: &String| record_ids.iter().filter(|id| *id == record).count();
+    // Stable sort keeps the shuffled order between records of equal size
+    records.sort_by_key(|record| std::cmp::Reverse(count(record)));
+
+    let mut fold_sizes = vec![0; k];
+    let mut fold_of_record = Vec::with_capacity(records.len());
+    for record in &records {
+        let fold = (0..k).min_by_key(|&f| fold_sizes[f]).unwrap_or(0);
+        fold_sizes[fold] += count(record);
+        fold_of_record.push((record.clone(), fold));
+    }
+
+    let fold_of: Vec<usize> = record_ids.iter()
+        .map(|id| fold_of_record.iter().find(|(record, _)| record == id).map_or(0, |(_, fold)| *fold))
+        .collect();
+
+    folds_from_assignment(&fold_of, k)
+}
+
+fn folds_from_assignment(fold_of: &[usize], k: usize) -> Vec<Split> {
+    (0..k)
+        .map(|fold| {
+            let (test, train): (Vec<usize>, Vec<usize>) = (0..fold_of.len()).partition(|&i| fold_of[i] == fold);
+            Split { train, test }
+        })
+        .collect()
+}
+
+/// Build folds with the given strategy; `record_ids` is only used for `GroupByPatient`
+pub fn k_fold(strategy: FoldStrategy, classes: &[usize], record_ids: &[String], k: usize, seed: u64) -> Vec<Split> {
+    match strategy {
+        FoldStrategy::Stratified => stratified_k_fold(classes, k, seed),
+        FoldStrategy::GroupByPatient => group_k_fold(record_ids, k, seed),
+    }
+}
+
+/// Mean and spread of one metric across folds
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSummary {
+    pub name: String,
+    /// Value from each fold, in fold order
+    pub values: Vec<f64>,
+    pub mean: f64,
+    /// Sample standard deviation (n - 1); 0 with a single fold
+    pub std_dev: f64,
+}
+
+/// Aggregate per-fold metrics by name, keeping the order in which names first appear
+pub fn summarize(fold_metrics: &[Vec<(String, f64)>]) -> Vec<MetricSummary> {
+    let mut names: Vec<String> = Vec::new();
+    for metrics in fold_metrics {
+        for (name, _) in metrics {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    names.into_iter()
+        .map(|name| {
+            let values: Vec<f64> = fold_metrics.iter()
+                .filter_map(|metrics| metrics.iter().find(|(n, _)| *n == name).map(|(_, v)| *v))
+                .collect();
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let std_dev = if values.len() > 1 {
+                (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+            } else {
+                0.0
+            };
+            MetricSummary { name, values, mean, std_dev }
+        })
+        .collect()
+}
+
+/// Run `train_and_evaluate` once per fold and aggregate the metrics it returns
+///
+/// The closure receives the fold number and its split, and is expected to
+/// train a fresh model on `split.train` and evaluate it on `split.test`.
+pub fn cross_validate<F>(folds: &[Split], mut train_and_evaluate: F) -> Vec<MetricSummary>
+where
+    F: FnMut(usize, &Split) -> Vec<(String, f64)>,
+{
+    let fold_metrics: Vec<Vec<(String, f64)>> = folds.iter()
+        .enumerate()
+        .map(|(fold, split)| train_and_evaluate(fold, split))
+        .collect();
+    summarize(&fold_metrics)
+}
+
+/// Accuracy and macro-averaged sensitivity, precision and F1
+/// Classes that never occur (neither predicted nor true) are left out of the macro averages
+pub fn classification_metrics(predicted: &[usize], true_classes: &[usize], num_classes: usize) -> Vec<(String, f64)> {
+    let mut sensitivities = Vec::new();
+    let mut precisions = Vec::new();
+    let mut f1_scores = Vec::new();
+
+    for class in 0..num_classes {
+        let mut tp = 0.0;
+        let mut fp = 0.0;
+        let mut fn_ = 0.0;
+        for (&p, &t) in predicted.iter().zip(true_classes.iter()) {
+            match (p == class, t == class) {
+                (true, true) => tp += 1.0,
+                (true, false) => fp += 1.0,
+                (false, true) => fn_ += 1.0,
+                (false, false) => {}
+            }
+        }
+        if tp + fp + fn_ == 0.0 {
+            continue;
+        }
+
+        let sensitivity = if tp + fn_ > 0.0 { tp / (tp + fn_) } else { 0.0 };
+        let precision = if tp + fp > 0.0 { tp / (tp + fp) } else { 0.0 };
+        let f1 = if sensitivity + precision > 0.0 {
+            2.0 * sensitivity * precision / (sensitivity + precision)
+        } else {
+            0.0
+        };
+        sensitivities.push(sensitivity);
+        precisions.push(precision);
+        f1_scores.push(f1);
+    }
+
+    let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    vec![
+        ("accuracy".to_string(), calculate_multiclass_accuracy(predicted, true_classes)),
+        ("macro_sensitivity".to_string(), mean(&sensitivities)),
+        ("macro_precision".to_string(), mean(&precisions)),
+        ("macro_f1".to_string(), mean(&f1_scores)),
+    ]
+}
+
+/// Configuration shared by every fold's model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MlpConfig {
+    pub num_hidden: usize,
+    pub num_classes: usize,
+    pub epochs: usize,
+    pub learning_rate: f64,
+    /// Seed for the initial weights; every fold starts from the same draw
+    pub seed: u64,
+}
+
+/// Train a fresh softmax MLP on one fold and evaluate it on the held-out part
+///
+/// # Arguments
+/// * `config` - Network and training settings
+/// * `segments` - Every segment, one flattened segment per row
+/// * `classes` - Class index of every segment
+/// * `split` - Which segments to train and test on
+///
+/// # Returns
+/// * Test loss plus `classification_metrics` on the test segments
+pub fn train_mlp_fold(config: &MlpConfig, segments: &Matrix, classes: &[usize], split: &Split) -> Vec<(String, f64)> {
+    let one_hot = |indices: &[usize]| -> Matrix {
+        let mut targets = Matrix::zeros(indices.len(), config.num_classes);
+        for (row, &i) in indices.iter().enumerate() {
+            targets[(row, classes[i])] = 1.0;
+        }
+        targets
+    };
+
+    let train_segments = segments.select_rows(&split.train);
+    let train_targets = one_hot(&split.train);
+    let test_segments = segments.select_rows(&split.test);
+    let test_classes = select(classes, &split.test);
+    let test_targets = one_hot(&split.test);
+
+    let mut model = Sequential::mlp_seeded(segments.cols, &[config.num_hidden], config.num_classes, config.seed);
+    let sample_weights = vec![1.0; train_segments.rows];
+    for _ in 0..config.epochs {
+        model.train_step(&train_segments, &train_targets, &sample_weights, Loss::CategoricalCrossEntropy, config.learning_rate);
+    }
+
+    let probabilities = model.forward(&test_segments);
+    let predicted: Vec<usize> = probabilities.iter_rows().map(argmax).collect();
+
+    let mut metrics = vec![("loss".to_string(), categorical_cross_entropy(&probabilities, &test_targets))];
+    metrics.extend(classification_metrics(&predicted, &test_classes, config.num_classes));
+    metrics
+}
@@ -58,6 +58,41 @@ pub fn load_all_data(folder_path: &str) -> Result<Vec<Vec<f64>>, Box<dyn std::er
     Ok(all_data)
 }
 
+/// A single recording (one patient in MIT-BIH), identified by its file name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// File stem, e.g. "100" for 100.csv
+    pub id: String,
+    pub data: Vec<Vec<f64>>,
+}
+
+/// Load every CSV file in a folder as a separate record
+/// Unlike `load_all_data` the files are kept apart, sorted by record ID so the order is reproducible
+pub fn load_records(folder_path: &str) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    let mut records = Vec::new();
+    
+    for entry in fs::read_dir(folder_path)? {
+        let entry = entry?;
+        let path = entry.path();
+        
+        if path.is_file() && path.extension().is_some_and(|extension| extension == "csv") {
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.to_string(),
+                None => continue,
+            };
+            if let Some(path_str) = path.to_str() {
+                match load_csv(path_str) {
+                    Ok(data) => records.push(Record { id, data }),
+                    Err(e) => eprintln!("Error loading file {:?}: {}", path, e),
+                }
+            }
+        }
+    }
+    
+    records.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(records)
+}
+
 /// Scale data by normalizing columns 2 and 3 (indices 1 and 2)
 /// Returns a new vector with scaled data
 pub fn data_scaling(data: &[Vec<f64>]) -> Vec<Vec<f64>> {
@@ -0,0 +1,116 @@
+use crate::float::Float;
+use crate::layers::Param;
+use crate::matrix_math::Matrix;
+use crate::optim::{Optimizer, OptimizerState};
+use crate::sequential::Sequential;
+use std::fmt;
+
+/// What a `DivergenceGuard` does once training has produced a NaN or infinity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Stop, leaving the model as it was when the problem was found
+    Abort,
+    /// Restore the weights and optimizer state (and schedule) from the start of the epoch
+    Rollback,
+}
+
+/// The first non-finite value found in a training step
+#[derive(Debug, Clone, PartialEq)]
+pub enum NonFinite {
+    Loss(f64),
+    /// Parameter name (as in `Sequential::parameter_names`) and the offending gradient element
+    Gradient(String, f64),
+    /// A parameter that became non-finite through the update
+    Parameter(String, f64),
+}
+
+impl NonFinite {
+    /// First non-finite gradient among `params`, named by `names`
+    pub fn in_gradients<T: Float>(names: &[String], params: &[&Param<T>]) -> Option<NonFinite> {
+        first_non_finite(names, params.iter().map(|p| &p.grad)).map(|(name, value)| NonFinite::Gradient(name, value))
+    }
+
+    /// First non-finite parameter value among `params`, named by `names`
+    pub fn in_values<T: Float>(names: &[String], params: &[&Param<T>]) -> Option<NonFinite> {
+        first_non_finite(names, params.iter().map(|p| &p.value)).map(|(name, value)| NonFinite::Parameter(name, value))
+    }
+}
+
+fn first_non_finite<'a, T: Float + 'a>(names: &[String], matrices: impl Iterator<Item = &'a Matrix<T>>) -> Option<(String, f64)> {
+    names.iter().zip(matrices).find_map(|(name, matrix)| {
+        matrix.data.iter().find(|x| !x.is_finite()).map(|x| (name.clone(), x.to_f64()))
+    })
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonFinite::Loss(value) => write!(f, "loss is {}", value),
+            NonFinite::Gradient(name, value) => write!(f, "gradient of {} is {}", name, value),
+            NonFinite::Parameter(name, value) => write!(f, "{} became {} after the update", name, value),
+        }
+    }
+}
+
+/// Training hit a NaN or infinity and was stopped by a `DivergenceGuard`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub epoch: usize,
+    /// Index of the batch within the epoch
+    pub batch: usize,
+    pub source: NonFinite,
+    /// Whether the model and optimizer were restored to the start of the epoch
+    pub rolled_back: bool,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Training diverged in epoch {}, batch {}: {}", self.epoch, self.batch, self.source)?;
+        if self.rolled_back {
+            write!(f, "; rolled back to the start of the epoch")
+        } else {
+            write!(f, "; aborted")
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// Checks every step of `Sequential::train_epoch_guarded` for NaN and infinity
+///
+/// The loss and gradients are checked before the update, so an optimizer never
+/// sees a non-finite gradient, and the parameters after it. A checkpoint of the
+/// parameters and optimizer state (including a `Scheduled` optimizer's schedule
+/// position) is taken at the start of every guarded epoch.
+pub struct DivergenceGuard<T: Float = f64> {
+    pub recovery: Recovery,
+    parameters: Vec<Matrix<T>>,
+    optimizer_state: Option<OptimizerState>,
+}
+
+impl<T: Float> DivergenceGuard<T> {
+    pub fn new(recovery: Recovery) -> Self {
+        DivergenceGuard { recovery, parameters: Vec::new(), optimizer_state: None }
+    }
+
+    /// Remember the current parameters and optimizer state as the last good ones
+    pub fn checkpoint(&mut self, model: &Sequential<T>, optimizer: &dyn Optimizer<T>) {
+        self.parameters = model.parameters().iter().map(|p| p.value.clone()).collect();
+        self.optimizer_state = Some(optimizer.state());
+    }
+
+    /// Apply the recovery policy to a problem found in `epoch`, `batch`
+    pub fn recover(&self, model: &mut Sequential<T>, optimizer: &mut dyn Optimizer<T>, epoch: usize, batch: usize, source: NonFinite) -> Divergence {
+        let rolled_back = self.recovery == Recovery::Rollback;
+        if rolled_back {
+            let state = self.optimizer_state.as_ref()
+                .unwrap_or_else(|| panic!("DivergenceGuard cannot roll back without a checkpoint"));
+            for (param, value) in model.parameters_mut().into_iter().zip(&self.parameters) {
+                param.value = value.clone();
+            }
+            optimizer.load_state(state)
+                .unwrap_or_else(|e| panic!("DivergenceGuard checkpoint does not fit the optimizer: {}", e));
+        }
+        Divergence { epoch, batch, source, rolled_back }
+    }
+}
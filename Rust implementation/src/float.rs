@@ -0,0 +1,228 @@
+use crate::simd::{self, SimdLevel};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Element type of matrices and networks: implemented for `f32` and `f64`
+///
+/// Besides the usual arithmetic, the trait carries the slice kernels the
+/// matrix code is built on. For `f64` these go through the hand-written
+/// `simd` kernels; `f32` uses the portable loops below, which the compiler
+/// vectorises at twice the `f64` lane count.
+pub trait Float:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + fmt::Debug
+    + fmt::Display
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// Machine epsilon: the gap between 1 and the next representable value
+    const EPSILON: Self;
+    const NEG_INFINITY: Self;
+
+    /// Nearest representable value (rounds when narrowing to f32)
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn from_usize(value: usize) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn clamp(self, min: Self, max: Self) -> Self;
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+
+    /// Sum of `a[i] * b[i]` over the common length
+    fn dot(_level: SimdLevel, a: &[Self], b: &[Self]) -> Self {
+        // Eight independent partial sums, so the loop vectorises despite strict FP ordering
+        let mut partial = [Self::ZERO; 8];
+        let (a_chunks, b_chunks) = (a.chunks_exact(8), b.chunks_exact(8));
+        let tail: Self = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(&x, &y)| x * y).sum();
+        for (x, y) in a_chunks.zip(b_chunks) {
+            for ((p, &x), &y) in partial.iter_mut().zip(x).zip(y) {
+                *p += x * y;
+            }
+        }
+        partial.into_iter().sum::<Self>() + tail
+    }
+
+    /// `out[i] = a[i] + b[i]`
+    fn add_into(_level: SimdLevel, a: &[Self], b: &[Self], out: &mut [Self]) {
+        for ((o, &x), &y) in out.iter_mut().zip(a).zip(b) {
+            *o = x + y;
+        }
+    }
+
+    /// `out[i] = a[i] * b[i]`
+    fn mul_into(_level: SimdLevel, a: &[Self], b: &[Self], out: &mut [Self]) {
+        for ((o, &x), &y) in out.iter_mut().zip(a).zip(b) {
+            *o = x * y;
+        }
+    }
+
+    /// `out[i] = a[i] * scalar`
+    fn scale_into(_level: SimdLevel, a: &[Self], scalar: Self, out: &mut [Self]) {
+        for (o, &x) in out.iter_mut().zip(a) {
+            *o = x * scalar;
+        }
+    }
+
+    /// `y[i] += alpha * x[i]`
+    fn axpy(_level: SimdLevel, alpha: Self, x: &[Self], y: &mut [Self]) {
+        for (y_i, &x_i) in y.iter_mut().zip(x) {
+            *y_i += alpha * x_i;
+        }
+    }
+
+    /// `out[i] = max(0, a[i])`, passing NaN through
+    fn relu_into(_level: SimdLevel, a: &[Self], out: &mut [Self]) {
+        for (o, &x) in out.iter_mut().zip(a) {
+            *o = if x < Self::ZERO { Self::ZERO } else { x };
+        }
+    }
+
+    /// `out[i] = 1 / (1 + exp(-a[i]))`
+    fn sigmoid_into(_level: SimdLevel, a: &[Self], out: &mut [Self]) {
+        for (o, &x) in out.iter_mut().zip(a) {
+            *o = Self::ONE / (Self::ONE + (-x).exp());
+        }
+    }
+
+    /// GEMM register tile, see `simd::kernel_4x4`
+    fn kernel_4x4(_level: SimdLevel, a: &[Self], b: &[Self], c: &mut [Self], ldc: usize, mr: usize, nr: usize) {
+        let mut acc = [[Self::ZERO; 4]; 4];
+        for (a_column, b_row) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+            let a_column: &[Self; 4] = a_column.try_into().unwrap();
+            let b_row: &[Self; 4] = b_row.try_into().unwrap();
+            for (acc_row, &a_i) in acc.iter_mut().zip(a_column) {
+                for (acc_ij, &b_j) in acc_row.iter_mut().zip(b_row) {
+                    *acc_ij += a_i * b_j;
+                }
+            }
+        }
+        for (i, acc_row) in acc.iter().enumerate().take(mr) {
+            for (c_ij, &value) in c[i * ldc..i * ldc + nr].iter_mut().zip(acc_row) {
+                *c_ij += value;
+            }
+        }
+    }
+
+    /// Two adjacent GEMM register tiles, see `simd::kernel_4x8`
+    #[allow(clippy::too_many_arguments)]
+    fn kernel_4x8(level: SimdLevel, a: &[Self], b_left: &[Self], b_right: &[Self], c: &mut [Self], ldc: usize, mr: usize, nr: usize) {
+        Self::kernel_4x4(level, a, b_left, c, ldc, mr, nr.min(4));
+        if nr > 4 {
+            Self::kernel_4x4(level, a, b_right, &mut c[4..], ldc, mr, nr - 4);
+        }
+    }
+}
+
+macro_rules! float_methods {
+    ($t:ty) => {
+        const ZERO: Self = 0.0;
+        const ONE: Self = 1.0;
+        const EPSILON: Self = <$t>::EPSILON;
+        const NEG_INFINITY: Self = <$t>::NEG_INFINITY;
+
+        fn from_f64(value: f64) -> Self {
+            value as $t
+        }
+        fn to_f64(self) -> f64 {
+            self as f64
+        }
+        fn exp(self) -> Self {
+            <$t>::exp(self)
+        }
+        fn ln(self) -> Self {
+            <$t>::ln(self)
+        }
+        fn sqrt(self) -> Self {
+            <$t>::sqrt(self)
+        }
+        fn abs(self) -> Self {
+            <$t>::abs(self)
+        }
+        fn powi(self, n: i32) -> Self {
+            <$t>::powi(self, n)
+        }
+        fn max(self, other: Self) -> Self {
+            <$t>::max(self, other)
+        }
+        fn min(self, other: Self) -> Self {
+            <$t>::min(self, other)
+        }
+        fn clamp(self, min: Self, max: Self) -> Self {
+            <$t>::clamp(self, min, max)
+        }
+        fn is_finite(self) -> bool {
+            <$t>::is_finite(self)
+        }
+        fn is_nan(self) -> bool {
+            <$t>::is_nan(self)
+        }
+    };
+}
+
+impl Float for f32 {
+    float_methods!(f32);
+}
+
+impl Float for f64 {
+    float_methods!(f64);
+
+    fn dot(level: SimdLevel, a: &[f64], b: &[f64]) -> f64 {
+        simd::dot(level, a, b)
+    }
+    fn add_into(level: SimdLevel, a: &[f64], b: &[f64], out: &mut [f64]) {
+        simd::add(level, a, b, out)
+    }
+    fn mul_into(level: SimdLevel, a: &[f64], b: &[f64], out: &mut [f64]) {
+        simd::mul(level, a, b, out)
+    }
+    fn scale_into(level: SimdLevel, a: &[f64], scalar: f64, out: &mut [f64]) {
+        simd::scale(level, a, scalar, out)
+    }
+    fn axpy(level: SimdLevel, alpha: f64, x: &[f64], y: &mut [f64]) {
+        simd::axpy(level, alpha, x, y)
+    }
+    fn relu_into(level: SimdLevel, a: &[f64], out: &mut [f64]) {
+        simd::relu(level, a, out)
+    }
+    fn sigmoid_into(level: SimdLevel, a: &[f64], out: &mut [f64]) {
+        simd::sigmoid(level, a, out)
+    }
+    fn kernel_4x4(level: SimdLevel, a: &[f64], b: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+        simd::kernel_4x4(level, a, b, c, ldc, mr, nr)
+    }
+    fn kernel_4x8(level: SimdLevel, a: &[f64], b_left: &[f64], b_right: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+        simd::kernel_4x8(level, a, b_left, b_right, c, ldc, mr, nr)
+    }
+}
+
+/// Convert every element to another float type, e.g. f64 weights to f32 for deployment
+pub fn cast_slice<T: Float, U: Float>(values: &[T]) -> Vec<U> {
+    values.iter().map(|&x| U::from_f64(x.to_f64())).collect()
+}
@@ -0,0 +1,156 @@
+use crate::float::Float;
+use crate::matrix_math::{Matrix, MatrixView};
+use crate::parallel::for_each_chunk_mut;
+use crate::simd::{self, SimdLevel};
+
+// Blocking follows the usual GotoBLAS / BLIS layout:
+//
+//   jc: NC columns of B and C         (packed B panel lives in L3)
+//   pc: KC deep slice of A and B      (one KC x NR sliver of B lives in L1)
+//   ic: MC rows of A and C            (packed A block lives in L2)
+//   jr, ir: MR x NR register tile updated by the micro-kernel
+//
+// Packing copies each block into the exact order the micro-kernel reads it,
+// so the innermost loop walks both operands with unit stride.
+
+/// Rows of the register tile
+const MR: usize = 4;
+/// Columns of the register tile
+/// A 4 x 4 f64 tile takes 8 of the 16 SSE2 registers, leaving room for the
+/// operands. With AVX2 two neighbouring slivers are handled as one 4 x 8 tile.
+const NR: usize = 4;
+// The SIMD tiles in `simd` are written for exactly this shape
+const _: () = assert!(MR == 4 && NR == 4);
+/// Rows of A per packed block (multiple of MR)
+const MC: usize = 64;
+/// Depth of a packed block
+const KC: usize = 256;
+/// Columns of B per packed panel (multiple of NR)
+const NC: usize = 1024;
+
+/// Below this many multiply-adds packing costs more than it saves
+const SMALL_GEMM: usize = 32 * 32 * 32;
+/// Shallower products (e.g. the rank-1 750 x 1 by 1 x 64 weight gradient)
+/// are dominated by writing C, which packing cannot speed up
+const MIN_DEPTH: usize = 16;
+
+/// General matrix multiply-accumulate: C += A B
+///
+/// Small products, and those too thin or too shallow to fill a register tile
+/// (such as the single-sample 1 x 750 by 750 x 64 forward pass), use a plain
+/// i-k-j loop; everything else goes through the packed, cache-blocked kernel.
+///
+/// Large products are split by rows of C across `parallel::num_threads()`
+/// threads. The kernel is chosen from the full shape before splitting, so
+/// every element is summed in the same order whatever the thread count.
+pub fn gemm<T: Float>(a: MatrixView<T>, b: MatrixView<T>, c: &mut Matrix<T>) {
+    if a.cols != b.rows || c.rows != a.rows || c.cols != b.cols {
+        panic!(
+            "Matrix dimensions do not match for multiplication (got {}x{} times {}x{} into {}x{})",
+            a.rows, a.cols, b.rows, b.cols, c.rows, c.cols
+        );
+    }
+    let (m, k, n) = (a.rows, a.cols, b.cols);
+    if m == 0 || n == 0 || k == 0 {
+        return;
+    }
+
+    let blocked = !(m < MR || n < NR || k < MIN_DEPTH || m * n * k < SMALL_GEMM);
+    let level = simd::active_level();
+
+    // Split on whole register tiles of rows so no thread gets a ragged tile in the middle
+    for_each_chunk_mut(&mut c.data, MR * n, m * n * k, |offset, c_rows| {
+        let first_row = offset / n;
+        let a_rows = a.slice_rows(first_row..first_row + c_rows.len() / n);
+        if blocked {
+            gemm_blocked(level, a_rows, b, c_rows);
+        } else {
+            gemm_ikj(level, a_rows, b, c_rows);
+        }
+    });
+}
+
+/// Unpacked i-k-j loop: the inner loop runs along contiguous rows of B and C
+fn gemm_ikj<T: Float>(level: SimdLevel, a: MatrixView<T>, b: MatrixView<T>, c: &mut [T]) {
+    for (a_row, c_row) in a.iter_rows().zip(c.chunks_exact_mut(b.cols)) {
+        for (&a_ip, b_row) in a_row.iter().zip(b.iter_rows()) {
+            T::axpy(level, a_ip, b_row, c_row);
+        }
+    }
+}
+
+/// Packed, cache-blocked kernel; `c` holds the a.rows x b.cols output rows
+fn gemm_blocked<T: Float>(level: SimdLevel, a: MatrixView<T>, b: MatrixView<T>, c: &mut [T]) {
+    let (m, k, n) = (a.rows, a.cols, b.cols);
+    // Buffers only as large as the biggest block actually packed
+    let mut packed_a = vec![T::ZERO; MC.min(m.next_multiple_of(MR)) * KC.min(k)];
+    let mut packed_b = vec![T::ZERO; KC.min(k) * NC.min(n.next_multiple_of(NR))];
+
+    for jc in (0..n).step_by(NC) {
+        let nc = NC.min(n - jc);
+        for pc in (0..k).step_by(KC) {
+            let kc = KC.min(k - pc);
+            pack_b(b, pc, kc, jc, nc, &mut packed_b);
+
+            for ic in (0..m).step_by(MC) {
+                let mc = MC.min(m - ic);
+                pack_a(a, ic, mc, pc, kc, &mut packed_a);
+
+                let mut jr = 0;
+                while jr < nc {
+                    // Two slivers at a time when the wide AVX2 tile is available
+                    let slivers = if level == SimdLevel::Avx2Fma && nc - jr > NR { 2 } else { 1 };
+                    let nr = (slivers * NR).min(nc - jr);
+                    let b_sliver = &packed_b[jr * kc..(jr + NR) * kc];
+
+                    for ir in (0..mc).step_by(MR) {
+                        let mr = MR.min(mc - ir);
+                        let a_sliver = &packed_a[ir * kc..(ir + MR) * kc];
+                        let c_tile = &mut c[(ic + ir) * n + jc + jr..];
+                        if slivers == 2 {
+                            let b_next = &packed_b[(jr + NR) * kc..(jr + 2 * NR) * kc];
+                            T::kernel_4x8(level, a_sliver, b_sliver, b_next, c_tile, n, mr, nr);
+                        } else {
+                            T::kernel_4x4(level, a_sliver, b_sliver, c_tile, n, mr, nr);
+                        }
+                    }
+                    jr += slivers * NR;
+                }
+            }
+        }
+    }
+}
+
+/// Pack rows ic..ic+mc, columns pc..pc+kc of A into MR-row slivers,
+/// column by column, zero-padding the last sliver
+fn pack_a<T: Float>(a: MatrixView<T>, ic: usize, mc: usize, pc: usize, kc: usize, packed: &mut [T]) {
+    for (sliver, ir) in (0..mc).step_by(MR).enumerate() {
+        let dest = &mut packed[sliver * MR * kc..(sliver + 1) * MR * kc];
+        for i in 0..MR {
+            if ir + i < mc {
+                let source = &a.row(ic + ir + i)[pc..pc + kc];
+                for (column, &value) in dest.chunks_exact_mut(MR).zip(source) {
+                    column[i] = value;
+                }
+            } else {
+                for column in dest.chunks_exact_mut(MR) {
+                    column[i] = T::ZERO;
+                }
+            }
+        }
+    }
+}
+
+/// Pack rows pc..pc+kc, columns jc..jc+nc of B into NR-column slivers,
+/// row by row, zero-padding the last sliver
+fn pack_b<T: Float>(b: MatrixView<T>, pc: usize, kc: usize, jc: usize, nc: usize, packed: &mut [T]) {
+    for (sliver, jr) in (0..nc).step_by(NR).enumerate() {
+        let width = NR.min(nc - jr);
+        let dest = &mut packed[sliver * NR * kc..(sliver + 1) * NR * kc];
+        for (p, row) in dest.chunks_exact_mut(NR).enumerate() {
+            let source = &b.row(pc + p)[jc + jr..jc + jr + width];
+            row[..width].copy_from_slice(source);
+            row[width..].fill(T::ZERO);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::layers::Layer;
+use crate::loss::Loss;
+use crate::matrix_math::*;
+use crate::rng::Rng;
+use crate::sequential::Sequential;
+use std::fmt;
+
+/// Default finite-difference step: small enough for accuracy, large enough to avoid cancellation in f64
+pub const DEFAULT_STEP: f64 = 1e-5;
+
+/// Comparison of analytic and numerical gradients for one parameter tensor
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamCheck {
+    /// Where the parameter lives, e.g. "layer 0 Dense weights"
+    pub name: String,
+    /// Largest relative error over the parameter's elements
+    pub max_relative_error: f64,
+    /// Index into the parameter's data of the element with that error
+    pub worst_element: usize,
+    pub analytic: f64,
+    pub numerical: f64,
+}
+
+/// Report of a gradient check, one entry per parameter tensor
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GradientCheck {
+    pub params: Vec<ParamCheck>,
+}
+
+impl GradientCheck {
+    /// Largest relative error over every parameter (0 if there are none)
+    pub fn max_relative_error(&self) -> f64 {
+        self.params.iter().map(|p| p.max_relative_error).fold(0.0, f64::max)
+    }
+
+    /// Parameter with the largest relative error
+    pub fn worst(&self) -> Option<&ParamCheck> {
+        self.params.iter().max_by(|a, b| a.max_relative_error.total_cmp(&b.max_relative_error))
+    }
+
+    /// Whether every relative error is at most `tolerance`
+    pub fn passes(&self, tolerance: f64) -> bool {
+        self.params.iter().all(|p| p.max_relative_error <= tolerance)
+    }
+}
+
+impl fmt::Display for GradientCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for p in &self.params {
+            writeln!(
+                f,
+                "{}: max relative error {:.3e} at element {} (analytic {:.6e}, numerical {:.6e})",
+                p.name, p.max_relative_error, p.worst_element, p.analytic, p.numerical
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// |a - n| / max(|a|, |n|), with a floor on the denominator so two
+/// vanishing gradients (e.g. behind a dead ReLU) do not count as a mismatch
+pub fn relative_error(analytic: f64, numerical: f64) -> f64 {
+    (analytic - numerical).abs() / analytic.abs().max(numerical.abs()).max(1e-8)
+}
+
+/// Compare analytic gradients with central differences of `loss`
+///
+/// # Arguments
+/// * `names` - One name per parameter, used in the report
+/// * `values` - Parameter values at which to check
+/// * `analytic` - Gradient of the loss with respect to each parameter, same shapes as `values`
+/// * `loss` - Loss as a function of all the parameters
+/// * `step` - Finite-difference step h: numerical = (L(x + h) - L(x - h)) / 2h
+pub fn check_gradients(
+    names: &[String],
+    values: &[Matrix],
+    analytic: &[Matrix],
+    mut loss: impl FnMut(&[Matrix]) -> f64,
+    step: f64,
+) -> GradientCheck {
+    if names.len() != values.len() || analytic.len() != values.len() {
+        panic!("Gradient check needs one name and one gradient per parameter (got {} names and {} gradients for {} parameters)", names.len(), analytic.len(), values.len());
+    }
+
+    let mut perturbed = values.to_vec();
+    let mut params = Vec::new();
+    for (p, (name, gradient)) in names.iter().zip(analytic).enumerate() {
+        if gradient.shape() != values[p].shape() {
+            panic!("Gradient of {} is {}x{} but the parameter is {}x{}", name, gradient.rows, gradient.cols, values[p].rows, values[p].cols);
+        }
+
+        let mut check = ParamCheck { name: name.clone(), max_relative_error: 0.0, worst_element: 0, analytic: 0.0, numerical: 0.0 };
+        for (i, &analytic) in gradient.data.iter().enumerate() {
+            let original = values[p].data[i];
+            perturbed[p].data[i] = original + step;
+            let plus = loss(&perturbed);
+            perturbed[p].data[i] = original - step;
+            let minus = loss(&perturbed);
+            perturbed[p].data[i] = original;
+
+            let numerical = (plus - minus) / (2.0 * step);
+            let error = relative_error(analytic, numerical);
+            // A NaN error is always reported as the worst
+            if i == 0 || error.is_nan() || error > check.max_relative_error {
+                check = ParamCheck { max_relative_error: error, worst_element: i, analytic, numerical, ..check };
+            }
+        }
+        params.push(check);
+    }
+
+    GradientCheck { params }
+}
+
+/// Check every parameter gradient of a model under `loss` plus its regularisation penalty
+///
+/// Dropout resamples its mask on every forward pass, so call `model.eval()` first.
+/// Parameter values are restored afterwards; the gradients are left as
+/// computed by `compute_gradients` at the unperturbed values.
+pub fn check_sequential(model: &mut Sequential, inputs: &Matrix, targets: &Matrix, sample_weights: &[f64], loss: Loss, step: f64) -> GradientCheck {
+    model.compute_gradients(inputs, targets, sample_weights, loss);
+
+    let names = model.parameter_names();
+    let values: Vec<Matrix> = model.parameters().iter().map(|p| p.value.clone()).collect();
+    let analytic: Vec<Matrix> = model.gradients().into_iter().cloned().collect();
+
+    let report = check_gradients(&names, &values, &analytic, |perturbed| {
+        set_parameters(model, perturbed);
+        loss.evaluate(&model.forward(inputs), targets, sample_weights).0 + model.penalty()
+    }, step);
+
+    set_parameters(model, &values);
+    report
+}
+
+fn set_parameters(model: &mut Sequential, values: &[Matrix]) {
+    for (param, value) in model.parameters_mut().into_iter().zip(values) {
+        param.value.data.copy_from_slice(&value.data);
+    }
+}
+
+/// Check a single layer, including the gradient it passes back to its input
+///
+/// The loss is a fixed random projection of the output, sum(output * R),
+/// so every output element contributes with a different weight.
+pub fn check_layer(layer: &mut dyn Layer, input: &Matrix, step: f64) -> GradientCheck {
+    let output = layer.forward(input);
+    let mut rng = Rng::new(0x6772_6164);
+    let projection = Matrix::from_vec(output.rows, output.cols, (0..output.data.len()).map(|_| rng.uniform(-1.0, 1.0)).collect());
+    let grad_input = layer.backward(&projection);
+
+    let mut names = vec![format!("{} input", layer.name())];
+    names.extend(layer.parameters().iter().map(|p| format!("{} {}", layer.name(), p.name)));
+    let mut values = vec![input.clone()];
+    values.extend(layer.parameters().iter().map(|p| p.value.clone()));
+    let mut analytic = vec![grad_input];
+    analytic.extend(layer.gradients().into_iter().cloned());
+
+    let report = check_gradients(&names, &values, &analytic, |perturbed| {
+        for (param, value) in layer.parameters_mut().into_iter().zip(&perturbed[1..]) {
+            param.value.data.copy_from_slice(&value.data);
+        }
+        dot_product(&layer.forward(&perturbed[0]).data, &projection.data)
+    }, step);
+
+    for (param, value) in layer.parameters_mut().into_iter().zip(&values[1..]) {
+        param.value.data.copy_from_slice(&value.data);
+    }
+    report
+}
@@ -0,0 +1,134 @@
+use crate::rng::Rng;
+
+/// Number of samples in each class
+pub fn class_counts(classes: &[usize], num_classes: usize) -> Vec<usize> {
+    let mut counts = vec![0; num_classes];
+    for &class in classes {
+        counts[class] += 1;
+    }
+    counts
+}
+
+/// Per-class loss weights inversely proportional to class frequency
+///
+/// Uses `n / (num_classes * n_c)`, so a perfectly balanced dataset gets a
+/// weight of 1 for every class. Classes without any samples get weight 0.
+pub fn inverse_frequency_weights(classes: &[usize], num_classes: usize) -> Vec<f64> {
+    let n = classes.len() as f64;
+    class_counts(classes, num_classes)
+        .iter()
+        .map(|&count| if count > 0 { n / (num_classes as f64 * count as f64) } else { 0.0 })
+        .collect()
+}
+
+/// Indices of the samples of every class, by class
+fn class_members(classes: &[usize]) -> Vec<Vec<usize>> {
+    let num_classes = classes.iter().max().map_or(0, |&c| c + 1);
+    let mut members = vec![Vec::new(); num_classes];
+    for (index, &class) in classes.iter().enumerate() {
+        members[class].push(index);
+    }
+    members
+}
+
+/// Random oversampling: repeat minority samples until every class matches the largest one
+///
+/// # Returns
+/// * Shuffled sample indices (use with `split::select`); every original sample appears at least once
+pub fn random_oversample(classes: &[usize], seed: u64) -> Vec<usize> {
+    let mut rng = Rng::new(seed);
+    let members = class_members(classes);
+    let target = members.iter().map(|m| m.len()).max().unwrap_or(0);
+
+    let mut indices = Vec::with_capacity(target * members.len());
+    for class_members in members.iter().filter(|m| !m.is_empty()) {
+        indices.extend(class_members);
+        for _ in class_members.len()..target {
+            indices.push(class_members[rng.below(class_members.len())]);
+        }
+    }
+    rng.shuffle(&mut indices);
+    indices
+}
+
+/// Random undersampling: keep a random subset of each class the size of the smallest non-empty class
+///
+/// # Returns
+/// * Shuffled sample indices (use with `split::select`), without repeats
+pub fn random_undersample(classes: &[usize], seed: u64) -> Vec<usize> {
+    let mut rng = Rng::new(seed);
+    let members = class_members(classes);
+    let target = members.iter().map(|m| m.len()).filter(|&n| n > 0).min().unwrap_or(0);
+
+    let mut indices = Vec::with_capacity(target * members.len());
+    for mut class_members in members.into_iter().filter(|m| !m.is_empty()) {
+        rng.shuffle(&mut class_members);
+        indices.extend(&class_members[..target]);
+    }
+    rng.shuffle(&mut indices);
+    indices
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// SMOTE: synthesise new minority samples until every class matches the largest one
+///
+/// Each synthetic sample lies on the line between a random minority sample and
+/// one of its `k_neighbors` nearest neighbours (Euclidean) within the same class.
+/// A class with a single sample can only be copied.
+///
+/// # Arguments
+/// * `features` - One feature vector per sample (e.g. a flattened segment)
+/// * `classes` - Class index of every sample
+/// * `k_neighbors` - Number of same-class neighbours to interpolate towards (5 in the original paper)
+/// * `seed` - Seed for sample and neighbour selection
+///
+/// # Returns
+/// * Only the synthetic feature vectors and their classes; append them to the originals
+pub fn smote(features: &[Vec<f64>], classes: &[usize], k_neighbors: usize, seed: u64) -> (Vec<Vec<f64>>, Vec<usize>) {
+    if features.len() != classes.len() {
+        panic!("Features and classes must have the same length for SMOTE");
+    }
+    let mut rng = Rng::new(seed);
+    let members = class_members(classes);
+    let target = members.iter().map(|m| m.len()).max().unwrap_or(0);
+
+    let mut synthetic_features = Vec::new();
+    let mut synthetic_classes = Vec::new();
+
+    for (class, class_members) in members.iter().enumerate() {
+        if class_members.is_empty() || class_members.len() >= target {
+            continue;
+        }
+
+        // Nearest same-class neighbours of every member, computed once per class
+        let neighbours: Vec<Vec<usize>> = class_members.iter()
+            .map(|&i| {
+                let mut others: Vec<(f64, usize)> = class_members.iter()
+                    .filter(|&&j| j != i)
+                    .map(|&j| (squared_distance(&features[i], &features[j]), j))
+                    .collect();
+                others.sort_by(|a, b| a.0.total_cmp(&b.0));
+                others.into_iter().take(k_neighbors.max(1)).map(|(_, j)| j).collect()
+            })
+            .collect();
+
+        for _ in class_members.len()..target {
+            let pick = rng.below(class_members.len());
+            let base = &features[class_members[pick]];
+            let sample = if neighbours[pick].is_empty() {
+                base.clone()
+            } else {
+                let neighbour = &features[neighbours[pick][rng.below(neighbours[pick].len())]];
+                let gap = rng.next_f64();
+                base.iter().zip(neighbour.iter()).map(|(b, n)| b + gap * (n - b)).collect()
+            };
+            synthetic_features.push(sample);
+            synthetic_classes.push(class);
+        }
+    }
+
+    (synthetic_features, synthetic_classes)
+}
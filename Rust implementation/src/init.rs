@@ -0,0 +1,80 @@
+use crate::float::Float;
+use crate::matrix_math::Matrix;
+use crate::rng::Rng;
+
+/// How to draw the initial (fan_in x fan_out) weights of a layer
+///
+/// Every variant draws from a `Rng`, so a network built from the same seed
+/// is identical from run to run. Random weights matter: when every weight
+/// starts equal, all hidden units get the same gradient and never diverge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Initializer {
+    /// Every weight set to the same value (does not break symmetry)
+    Constant(f64),
+    /// Uniform in [low, high)
+    Uniform { low: f64, high: f64 },
+    Normal { mean: f64, std_dev: f64 },
+    /// Glorot & Bengio: uniform in ±sqrt(6 / (fan_in + fan_out)), for sigmoid/softmax layers
+    XavierUniform,
+    /// Glorot & Bengio: normal with variance 2 / (fan_in + fan_out)
+    XavierNormal,
+    /// He et al.: uniform in ±sqrt(6 / fan_in), for ReLU layers
+    HeUniform,
+    /// He et al.: normal with variance 2 / fan_in
+    HeNormal,
+    /// Random matrix with orthonormal rows or columns (whichever are fewer), times `gain`
+    Orthogonal { gain: f64 },
+}
+
+impl Initializer {
+    /// Weights for a layer with `fan_in` inputs and `fan_out` outputs
+    pub fn weights<T: Float>(&self, fan_in: usize, fan_out: usize, rng: &mut Rng) -> Matrix<T> {
+        let count = fan_in * fan_out;
+        let (fan_in_f, fan_sum) = (fan_in.max(1) as f64, (fan_in + fan_out).max(1) as f64);
+        let values: Vec<f64> = match *self {
+            Initializer::Constant(value) => vec![value; count],
+            Initializer::Uniform { low, high } => (0..count).map(|_| rng.uniform(low, high)).collect(),
+            Initializer::Normal { mean, std_dev } => (0..count).map(|_| rng.normal(mean, std_dev)).collect(),
+            Initializer::XavierUniform => uniform(count, (6.0 / fan_sum).sqrt(), rng),
+            Initializer::XavierNormal => (0..count).map(|_| rng.normal(0.0, (2.0 / fan_sum).sqrt())).collect(),
+            Initializer::HeUniform => uniform(count, (6.0 / fan_in_f).sqrt(), rng),
+            Initializer::HeNormal => (0..count).map(|_| rng.normal(0.0, (2.0 / fan_in_f).sqrt())).collect(),
+            Initializer::Orthogonal { gain } => orthogonal(fan_in, fan_out, gain, rng),
+        };
+        Matrix::from_vec(fan_in, fan_out, values.into_iter().map(T::from_f64).collect())
+    }
+}
+
+/// `count` values uniform in [-limit, limit)
+fn uniform(count: usize, limit: f64, rng: &mut Rng) -> Vec<f64> {
+    (0..count).map(|_| rng.uniform(-limit, limit)).collect()
+}
+
+/// Row-major rows x cols matrix whose columns (if rows >= cols) or rows are orthonormal, scaled by `gain`
+fn orthogonal(rows: usize, cols: usize, gain: f64, rng: &mut Rng) -> Vec<f64> {
+    // Orthonormalise k random normal vectors of length n with modified Gram-Schmidt
+    let (n, k) = (rows.max(cols), rows.min(cols));
+    let mut basis: Vec<Vec<f64>> = Vec::with_capacity(k);
+    for _ in 0..k {
+        let mut v: Vec<f64> = (0..n).map(|_| rng.normal(0.0, 1.0)).collect();
+        for q in &basis {
+            let projection: f64 = v.iter().zip(q).map(|(a, b)| a * b).sum();
+            for (x, &q_i) in v.iter_mut().zip(q) {
+                *x -= projection * q_i;
+            }
+        }
+        let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        v.iter_mut().for_each(|x| *x /= norm);
+        basis.push(v);
+    }
+
+    let mut values = vec![0.0; rows * cols];
+    for (j, q) in basis.iter().enumerate() {
+        for (i, &q_i) in q.iter().enumerate() {
+            // Tall matrices take the vectors as columns, wide ones as rows
+            let index = if rows >= cols { i * cols + j } else { j * cols + i };
+            values[index] = gain * q_i;
+        }
+    }
+    values
+}
@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// AAMI EC57 heartbeat classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AamiClass {
+    /// Normal and bundle branch block beats
+    N,
+    /// Supraventricular ectopic beats
+    S,
+    /// Ventricular ectopic beats
+    V,
+    /// Fusion of ventricular and normal beats
+    F,
+    /// Paced and unclassifiable beats
+    Q,
+}
+
+impl AamiClass {
+    /// All classes, in target index order
+    pub const ALL: [AamiClass; 5] = [AamiClass::N, AamiClass::S, AamiClass::V, AamiClass::F, AamiClass::Q];
+
+    /// Position of this class in a model's output / one-hot target
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<AamiClass> {
+        AamiClass::ALL.get(index).copied()
+    }
+
+    /// One-hot encoded training target
+    pub fn one_hot(&self) -> Vec<f64> {
+        let mut target = vec![0.0; AamiClass::ALL.len()];
+        target[self.index()] = 1.0;
+        target
+    }
+}
+
+/// Map a MIT-BIH beat annotation symbol to its AAMI class
+/// Returns None for non-beat annotations (rhythm changes, noise, artifacts, ...)
+pub fn aami_class(symbol: char) -> Option<AamiClass> {
+    match symbol {
+        'N' | 'L' | 'R' | 'e' | 'j' => Some(AamiClass::N),
+        'A' | 'a' | 'J' | 'S' => Some(AamiClass::S),
+        'V' | 'E' => Some(AamiClass::V),
+        'F' => Some(AamiClass::F),
+        '/' | 'f' | 'Q' => Some(AamiClass::Q),
+        _ => None,
+    }
+}
+
+/// MIT-BIH rhythm annotations (the aux string of a '+' annotation)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RhythmLabel {
+    AtrialBigeminy,
+    AtrialFibrillation,
+    AtrialFlutter,
+    VentricularBigeminy,
+    SecondDegreeBlock,
+    IdioventricularRhythm,
+    Normal,
+    NodalRhythm,
+    Paced,
+    PreExcitation,
+    SinusBradycardia,
+    SupraventricularTachyarrhythmia,
+    VentricularTrigeminy,
+    VentricularFlutter,
+    VentricularTachycardia,
+}
+
+impl RhythmLabel {
+    /// All rhythms, in target index order
+    pub const ALL: [RhythmLabel; 15] = [
+        RhythmLabel::AtrialBigeminy,
+        RhythmLabel::AtrialFibrillation,
+        RhythmLabel::AtrialFlutter,
+        RhythmLabel::VentricularBigeminy,
+        RhythmLabel::SecondDegreeBlock,
+        RhythmLabel::IdioventricularRhythm,
+        RhythmLabel::Normal,
+        RhythmLabel::NodalRhythm,
+        RhythmLabel::Paced,
+        RhythmLabel::PreExcitation,
+        RhythmLabel::SinusBradycardia,
+        RhythmLabel::SupraventricularTachyarrhythmia,
+        RhythmLabel::VentricularTrigeminy,
+        RhythmLabel::VentricularFlutter,
+        RhythmLabel::VentricularTachycardia,
+    ];
+
+    /// Aux string used for this rhythm in MIT-BIH annotation files
+    pub fn aux(&self) -> &'static str {
+        match self {
+            RhythmLabel::AtrialBigeminy => "(AB",
+            RhythmLabel::AtrialFibrillation => "(AFIB",
+            RhythmLabel::AtrialFlutter => "(AFL",
+            RhythmLabel::VentricularBigeminy => "(B",
+            RhythmLabel::SecondDegreeBlock => "(BII",
+            RhythmLabel::IdioventricularRhythm => "(IVR",
+            RhythmLabel::Normal => "(N",
+            RhythmLabel::NodalRhythm => "(NOD",
+            RhythmLabel::Paced => "(P",
+            RhythmLabel::PreExcitation => "(PREX",
+            RhythmLabel::SinusBradycardia => "(SBR",
+            RhythmLabel::SupraventricularTachyarrhythmia => "(SVTA",
+            RhythmLabel::VentricularTrigeminy => "(T",
+            RhythmLabel::VentricularFlutter => "(VFL",
+            RhythmLabel::VentricularTachycardia => "(VT",
+        }
+    }
+
+    /// Position of this rhythm in a model's output / one-hot target
+    pub fn index(&self) -> usize {
+        RhythmLabel::ALL.iter().position(|r| r == self).unwrap_or(0)
+    }
+
+    pub fn from_index(index: usize) -> Option<RhythmLabel> {
+        RhythmLabel::ALL.get(index).copied()
+    }
+}
+
+/// Parse a rhythm aux string such as `(AFIB` or `(N`
+/// Trailing NUL padding and whitespace (as found in exported annotation files) are ignored
+pub fn parse_rhythm(aux: &str) -> Option<RhythmLabel> {
+    let aux = aux.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    RhythmLabel::ALL.iter().find(|r| r.aux() == aux).copied()
+}
+
+/// One line of a MIT-BIH annotation file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub sample: usize,
+    pub symbol: char,
+    pub aux: Option<String>,
+}
+
+/// Load annotations exported in the PhysioNet `rdann` text layout
+///
+/// Each line after the header is `time sample type sub chan num [aux]`,
+/// e.g. `0:00.050  18  +  0  0  0  (N`.
+pub fn load_annotations(file_path: &str) -> Result<Vec<Annotation>, Box<dyn std::error::Error>> {
+    let file = File::open(file_path)?;
+    let reader = BufReader::new(file);
+    let mut annotations = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        // Skip header (first line)
+        if index == 0 || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 {
+            eprintln!("Error parsing line {}: expected at least 3 fields", index + 1);
+            continue;
+        }
+
+        let sample = match fields[1].parse::<usize>() {
+            Ok(sample) => sample,
+            Err(e) => {
+                eprintln!("Error parsing line {}: {}", index + 1, e);
+                continue;
+            }
+        };
+        let symbol = fields[2].chars().next().unwrap_or('?');
+        let aux = fields.get(6).map(|s| s.trim_matches('\0').to_string());
+
+        annotations.push(Annotation { sample, symbol, aux });
+    }
+
+    Ok(annotations)
+}
+
+/// AAMI class of every beat annotation, skipping non-beat annotations
+pub fn beat_labels(annotations: &[Annotation]) -> Vec<(usize, AamiClass)> {
+    annotations.iter()
+        .filter_map(|a| aami_class(a.symbol).map(|class| (a.sample, class)))
+        .collect()
+}
+
+/// Rhythm changes as `(sample, rhythm)`, in file order
+pub fn rhythm_changes(annotations: &[Annotation]) -> Vec<(usize, RhythmLabel)> {
+    annotations.iter()
+        .filter_map(|a| a.aux.as_deref().and_then(parse_rhythm).map(|r| (a.sample, r)))
+        .collect()
+}
+
+/// Rhythm in effect at a sample, if any rhythm has been annotated before it
+pub fn rhythm_at(changes: &[(usize, RhythmLabel)], sample: usize) -> Option<RhythmLabel> {
+    changes.iter()
+        .take_while(|(start, _)| *start <= sample)
+        .last()
+        .map(|(_, rhythm)| *rhythm)
+}
+
+/// Beat class of each fixed-length segment, matching `data_segmentation`
+///
+/// A segment takes the class of its most clinically significant beat
+/// (V, then F, then S, then Q, then N), so one ectopic beat is enough to
+/// mark it. Segments without any beat annotation are None.
+pub fn segment_classes(annotations: &[Annotation], num_samples: usize, segment_length: usize) -> Vec<Option<AamiClass>> {
+    if segment_length == 0 {
+        return Vec::new();
+    }
+
+    let priority = |class: AamiClass| match class {
+        AamiClass::V => 4,
+        AamiClass::F => 3,
+        AamiClass::S => 2,
+        AamiClass::Q => 1,
+        AamiClass::N => 0,
+    };
+
+    let mut classes = vec![None; num_samples / segment_length];
+    for (sample, class) in beat_labels(annotations) {
+        if let Some(slot) = classes.get_mut(sample / segment_length) {
+            let replace = match slot {
+                Some(current) => priority(class) > priority(*current),
+                None => true,
+            };
+            if replace {
+                *slot = Some(class);
+            }
+        }
+    }
+
+    classes
+}
@@ -0,0 +1,283 @@
+use crate::brains::{apply_relu, apply_relu_derivative, apply_sigmoid, sigmoid_derivative, softmax};
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::matrix_math::*;
+use crate::rng::Rng;
+
+/// A trainable tensor and the gradient of the loss with respect to it
+///
+/// Biases are stored as 1 x n matrices so every parameter has the same type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param<T = f64> {
+    /// Short name within its layer, e.g. "weights" or "bias"
+    pub name: &'static str,
+    pub value: Matrix<T>,
+    /// Gradient from the most recent `backward`, same shape as `value`
+    pub grad: Matrix<T>,
+}
+
+impl<T: Float> Param<T> {
+    /// Parameter with a zero gradient
+    pub fn new(name: &'static str, value: Matrix<T>) -> Self {
+        let grad = Matrix::zeros(value.rows, value.cols);
+        Param { name, value, grad }
+    }
+}
+
+/// One stage of a network operating on a batch with one sample per row
+///
+/// `forward` keeps whatever `backward` needs, so calls must alternate:
+/// `backward` always refers to the batch of the latest `forward`.
+pub trait Layer<T: Float = f64> {
+    /// Short type name used when reporting on the layer
+    fn name(&self) -> &'static str;
+
+    /// Output for a batch of inputs
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T>;
+
+    /// Given dL/d(output) for the last `forward` batch, store dL/d(parameter)
+    /// in every parameter's `grad` and return dL/d(input)
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T>;
+
+    /// Trainable parameters; empty for activations
+    fn parameters(&self) -> Vec<&Param<T>> {
+        Vec::new()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Param<T>> {
+        Vec::new()
+    }
+
+    /// Gradients of `parameters`, in the same order
+    fn gradients(&self) -> Vec<&Matrix<T>> {
+        self.parameters().into_iter().map(|p| &p.grad).collect()
+    }
+
+    /// Switch between training and evaluation behaviour; only `Dropout` differs
+    fn set_training(&mut self, _training: bool) {}
+}
+
+/// Value cached by `forward`, or a panic explaining the misuse
+pub(crate) fn cached<'a, T>(cache: &'a Option<Matrix<T>>, layer: &str) -> &'a Matrix<T> {
+    cache.as_ref().unwrap_or_else(|| panic!("{} backward called before forward", layer))
+}
+
+/// Fully connected layer: output = input * weights + bias
+#[derive(Debug, Clone)]
+pub struct Dense<T = f64> {
+    /// inputs x outputs
+    pub weights: Param<T>,
+    /// 1 x outputs
+    pub bias: Param<T>,
+    input: Option<Matrix<T>>,
+}
+
+impl<T: Float> Dense<T> {
+    /// Layer with weights drawn by `initializer` and a zero bias
+    pub fn with_initializer(num_inputs: usize, num_outputs: usize, initializer: Initializer, rng: &mut Rng) -> Self {
+        Dense::from_parameters(initializer.weights(num_inputs, num_outputs, rng), Matrix::zeros(1, num_outputs))
+    }
+
+    /// Layer with the given (inputs x outputs) weights and (1 x outputs) bias
+    pub fn from_parameters(weights: Matrix<T>, bias: Matrix<T>) -> Self {
+        if bias.rows != 1 || bias.cols != weights.cols {
+            panic!("Dense bias must be 1x{} to match {}x{} weights, got {}x{}", weights.cols, weights.rows, weights.cols, bias.rows, bias.cols);
+        }
+        Dense { weights: Param::new("weights", weights), bias: Param::new("bias", bias), input: None }
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.weights.value.rows
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.weights.value.cols
+    }
+}
+
+impl<T: Float> Layer<T> for Dense<T> {
+    fn name(&self) -> &'static str {
+        "Dense"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let mut output = matrix_multiply(input, &self.weights.value);
+        add_row_vector_inplace(&mut output, &self.bias.value.data);
+        self.input = Some(input.clone());
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let input = cached(&self.input, "Dense");
+        self.weights.grad = matrix_multiply(&matrix_transpose(input), grad_output);
+        self.bias.grad = Matrix::from_vec(1, grad_output.cols, column_sums(grad_output));
+        matrix_multiply(grad_output, &matrix_transpose(&self.weights.value))
+    }
+
+    fn parameters(&self) -> Vec<&Param<T>> {
+        vec![&self.weights, &self.bias]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Param<T>> {
+        vec![&mut self.weights, &mut self.bias]
+    }
+}
+
+/// Element-wise ReLU
+#[derive(Debug, Clone, Default)]
+pub struct ReLU<T = f64> {
+    output: Option<Matrix<T>>,
+}
+
+impl<T: Float> ReLU<T> {
+    pub fn new() -> Self {
+        ReLU { output: None }
+    }
+}
+
+impl<T: Float> Layer<T> for ReLU<T> {
+    fn name(&self) -> &'static str {
+        "ReLU"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = Matrix::from_vec(input.rows, input.cols, apply_relu(&input.data));
+        self.output = Some(output.clone());
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        // The output is positive exactly where the input was
+        let output = cached(&self.output, "ReLU");
+        let mask = Matrix::from_vec(output.rows, output.cols, apply_relu_derivative(&output.data));
+        elementwise_multiply(grad_output, &mask)
+    }
+}
+
+/// Element-wise logistic sigmoid, the binary output layer
+#[derive(Debug, Clone, Default)]
+pub struct Sigmoid<T = f64> {
+    output: Option<Matrix<T>>,
+}
+
+impl<T: Float> Sigmoid<T> {
+    pub fn new() -> Self {
+        Sigmoid { output: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Sigmoid<T> {
+    fn name(&self) -> &'static str {
+        "Sigmoid"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = Matrix::from_vec(input.rows, input.cols, apply_sigmoid(&input.data));
+        self.output = Some(output.clone());
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let output = cached(&self.output, "Sigmoid");
+        let slope: Vec<T> = output.data.iter().map(|&y| sigmoid_derivative(y)).collect();
+        elementwise_multiply(grad_output, &Matrix::from_vec(output.rows, output.cols, slope))
+    }
+}
+
+/// Row-wise softmax, the multi-class output layer
+#[derive(Debug, Clone, Default)]
+pub struct Softmax<T = f64> {
+    output: Option<Matrix<T>>,
+}
+
+impl<T: Float> Softmax<T> {
+    pub fn new() -> Self {
+        Softmax { output: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Softmax<T> {
+    fn name(&self) -> &'static str {
+        "Softmax"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let mut output = input.clone();
+        for row in output.iter_rows_mut() {
+            let p = softmax(row);
+            row.copy_from_slice(&p);
+        }
+        self.output = Some(output.clone());
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        // Jacobian-vector product of each row: y * (g - <g, y>)
+        let output = cached(&self.output, "Softmax");
+        let mut grad_input = Matrix::zeros(output.rows, output.cols);
+        let rows = output.iter_rows().zip(grad_output.iter_rows());
+        for (grad_row, (y, g)) in grad_input.iter_rows_mut().zip(rows) {
+            let inner = dot_product(g, y);
+            for ((gi, &y_j), &g_j) in grad_row.iter_mut().zip(y).zip(g) {
+                *gi = y_j * (g_j - inner);
+            }
+        }
+        grad_input
+    }
+}
+
+/// Inverted dropout: in training mode each input is zeroed with probability `rate`
+/// and the survivors are scaled by 1 / (1 - rate), so evaluation mode is the identity
+///
+/// A new layer starts in training mode; call `Sequential::eval` before predicting.
+#[derive(Debug, Clone)]
+pub struct Dropout<T = f64> {
+    pub rate: f64,
+    training: bool,
+    rng: Rng,
+    /// 0 or 1 / (1 - rate) for each input of the last training batch
+    mask: Option<Matrix<T>>,
+}
+
+impl<T: Float> Dropout<T> {
+    /// Dropout whose masks are drawn from an `Rng` seeded with `seed`
+    pub fn new(rate: f64, seed: u64) -> Self {
+        if !(0.0..1.0).contains(&rate) {
+            panic!("Dropout rate must be in [0, 1), got {}", rate);
+        }
+        Dropout { rate, training: true, rng: Rng::new(seed), mask: None }
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+}
+
+impl<T: Float> Layer<T> for Dropout<T> {
+    fn name(&self) -> &'static str {
+        "Dropout"
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        if !self.training || self.rate == 0.0 {
+            self.mask = Some(Matrix::filled(input.rows, input.cols, T::ONE));
+            return input.clone();
+        }
+        let keep = T::from_f64(1.0 / (1.0 - self.rate));
+        let mask_data = (0..input.data.len())
+            .map(|_| if self.rng.chance(self.rate) { T::ZERO } else { keep })
+            .collect();
+        let mask = Matrix::from_vec(input.rows, input.cols, mask_data);
+        let output = elementwise_multiply(input, &mask);
+        self.mask = Some(mask);
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        elementwise_multiply(grad_output, cached(&self.mask, "Dropout"))
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+}
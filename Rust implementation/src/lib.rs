@@ -1,3 +1,28 @@
 pub mod matrix_math;
+pub mod gemm;
+pub mod parallel;
+pub mod simd;
+pub mod float;
 pub mod data;
-pub mod brains;
\ No newline at end of file
+pub mod brains;
+pub mod init;
+pub mod layers;
+pub mod conv;
+pub mod loss;
+pub mod regularization;
+pub mod autodiff;
+pub mod gradient_check;
+pub mod sequential;
+pub mod batching;
+pub mod optim;
+pub mod clipping;
+pub mod divergence;
+pub mod schedule;
+pub mod rng;
+pub mod synthetic;
+pub mod noise_stress;
+pub mod augment;
+pub mod labels;
+pub mod split;
+pub mod cross_validation;
+pub mod imbalance;
\ No newline at end of file
@@ -0,0 +1,119 @@
+use crate::brains::{epsilon, softmax_cross_entropy_gradient};
+use crate::float::Float;
+use crate::matrix_math::Matrix;
+
+/// Loss on the final output of a network, averaged over samples with a weight per sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loss {
+    /// Binary cross-entropy on sigmoid outputs; one column, targets 0 or 1
+    BinaryCrossEntropy,
+    /// Categorical cross-entropy on softmax outputs; targets are one-hot (or soft) rows
+    CategoricalCrossEntropy,
+    /// Mean over samples of the summed squared error of each row
+    MeanSquaredError,
+}
+
+impl Loss {
+    /// Loss of every sample (row) of `predictions`
+    fn per_sample<T: Float>(&self, predictions: &[T], targets: &[T]) -> T {
+        let mut total = T::ZERO;
+        for (&y, &t) in predictions.iter().zip(targets) {
+            total += match self {
+                Loss::BinaryCrossEntropy => {
+                    let y = y.clamp(epsilon(), T::ONE - epsilon());
+                    -(t * y.ln() + (T::ONE - t) * (T::ONE - y).ln())
+                }
+                Loss::CategoricalCrossEntropy if t != T::ZERO => -t * y.max(epsilon()).ln(),
+                Loss::CategoricalCrossEntropy => T::ZERO,
+                Loss::MeanSquaredError => (y - t) * (y - t),
+            };
+        }
+        total
+    }
+
+    /// dL/dy for one element, before sample weighting
+    fn derivative<T: Float>(&self, y: T, t: T) -> T {
+        match self {
+            Loss::BinaryCrossEntropy => {
+                let y = y.clamp(epsilon(), T::ONE - epsilon());
+                -t / y + (T::ONE - t) / (T::ONE - y)
+            }
+            Loss::CategoricalCrossEntropy => -t / y.max(epsilon()),
+            Loss::MeanSquaredError => T::from_f64(2.0) * (y - t),
+        }
+    }
+
+    /// Weighted mean loss over the batch and its gradient with respect to `predictions`
+    ///
+    /// # Arguments
+    /// * `predictions` - Network outputs, one sample per row
+    /// * `targets` - Same shape as `predictions`
+    /// * `sample_weights` - One weight per row (e.g. class weights looked up by label)
+    ///
+    /// # Returns
+    /// * (sum of w_i * loss_i / sum of w_i, its gradient with the same shape as `predictions`)
+    pub fn evaluate<T: Float>(&self, predictions: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T]) -> (T, Matrix<T>) {
+        if predictions.shape() != targets.shape() || sample_weights.len() != predictions.rows {
+            panic!(
+                "Loss needs predictions and targets of the same shape and one weight per row (got {}x{}, {}x{} and {} weights)",
+                predictions.rows, predictions.cols, targets.rows, targets.cols, sample_weights.len()
+            );
+        }
+
+        let total_weight: T = sample_weights.iter().copied().sum();
+        let mut loss = T::ZERO;
+        let mut gradient = Matrix::zeros(predictions.rows, predictions.cols);
+        let rows = predictions.iter_rows().zip(targets.iter_rows()).zip(sample_weights);
+        for (grad_row, ((y, t), &weight)) in gradient.iter_rows_mut().zip(rows) {
+            loss += weight * self.per_sample(y, t);
+            let scale = weight / total_weight;
+            for ((g, &y), &t) in grad_row.iter_mut().zip(y).zip(t) {
+                *g = scale * self.derivative(y, t);
+            }
+        }
+
+        (loss / total_weight, gradient)
+    }
+}
+
+/// Binary cross-entropy of sigmoid outputs, with the gradient taken with respect to the
+/// sigmoid inputs (logits) rather than its outputs
+///
+/// See `softmax_cross_entropy`; going through `Sigmoid::backward` multiplies the clamped
+/// derivative by y(1 - y), which is exactly zero once the sigmoid rounds to 0 or 1, so a
+/// confidently wrong sample stops contributing any gradient.
+///
+/// # Returns
+/// * (the same loss as `Loss::BinaryCrossEntropy.evaluate`, dL/d(logits))
+pub fn sigmoid_cross_entropy<T: Float>(probabilities: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T]) -> (T, Matrix<T>) {
+    fused(Loss::BinaryCrossEntropy, probabilities, targets, sample_weights)
+}
+
+/// Categorical cross-entropy of softmax outputs, with the gradient taken with respect to the
+/// softmax inputs (logits) rather than its outputs
+///
+/// A cross-entropy derivative clamps its probabilities to keep -t / p finite, and pushing that
+/// through the output activation's backward pass loses the gradient wherever the activation
+/// has saturated (see also `sigmoid_cross_entropy`). The fused gradient w_i * (p - t) / sum of w_i
+/// needs neither: here the softmax Jacobian would be multiplied by -t / max(p, epsilon), which
+/// drops the gradient of any target class whose probability is below epsilon.
+///
+/// # Returns
+/// * (the same loss as `Loss::CategoricalCrossEntropy.evaluate`, dL/d(logits))
+pub fn softmax_cross_entropy<T: Float>(probabilities: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T]) -> (T, Matrix<T>) {
+    fused(Loss::CategoricalCrossEntropy, probabilities, targets, sample_weights)
+}
+
+/// `loss` of the outputs with the gradient w_i * (p - t) / sum of w_i with respect to the logits
+fn fused<T: Float>(loss: Loss, probabilities: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T]) -> (T, Matrix<T>) {
+    let (value, mut gradient) = loss.evaluate(probabilities, targets, sample_weights);
+    let total_weight: T = sample_weights.iter().copied().sum();
+    let rows = probabilities.iter_rows().zip(targets.iter_rows()).zip(sample_weights);
+    for (grad_row, ((p, t), &weight)) in gradient.iter_rows_mut().zip(rows) {
+        let scale = weight / total_weight;
+        for (g, d) in grad_row.iter_mut().zip(softmax_cross_entropy_gradient(p, t)) {
+            *g = scale * d;
+        }
+    }
+    (value, gradient)
+}
@@ -1,158 +1,534 @@
-pub fn matrix_multiply(a: &Vec<Vec<f64>>, b: &Vec<Vec<f64>>) -> Vec<Vec<f64>> {
-    let rows_a = a.len();
-    let cols_a = a[0].len();
-    let rows_b = b.len();
-    let cols_b = b[0].len();
+use crate::float::{cast_slice, Float};
+use crate::gemm::gemm;
+use crate::parallel;
+use crate::simd;
+use std::fmt;
+use std::ops::{Index, IndexMut, Range};
+
+/// Dense row-major matrix
+///
+/// Element (i, j) is stored at `data[i * cols + j]`, so rows are contiguous
+/// and a whole matrix is a single allocation. The element type defaults to
+/// f64; `Matrix<f32>` halves the memory and doubles the SIMD width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T = f64> {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<T>,
+}
+
+/// Borrowed, read-only view of a block of consecutive rows of a matrix
+///
+/// Views are cheap to copy and never own their data; a single feature vector
+/// can be viewed as a 1 x n matrix with `MatrixView::from_row` without copying it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatrixView<'a, T = f64> {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: &'a [T],
+}
 
-    if cols_a != rows_b {
-        panic!("Matrix dimensions do not match for multiplication");
+impl<T: Float> Matrix<T> {
+    /// Matrix of zeros
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix::filled(rows, cols, T::ZERO)
     }
 
-    let mut result = vec![vec![0.0; cols_b]; rows_a];
+    /// Matrix with every element set to `value`
+    pub fn filled(rows: usize, cols: usize, value: T) -> Self {
+        Matrix { rows, cols, data: vec![value; rows * cols] }
+    }
 
-    for i in 0..rows_a {
-        for j in 0..cols_b {
-            for k in 0..cols_a {
-                result[i][j] += a[i][k] * b[k][j];
-            }
+    /// Wrap row-major data
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<T>) -> Self {
+        or_panic(Matrix::try_from_vec(rows, cols, data))
+    }
+
+    pub fn try_from_vec(rows: usize, cols: usize, data: Vec<T>) -> Result<Self, ShapeError> {
+        if data.len() != rows * cols {
+            return Err(ShapeError::new(
+                "Data length does not match matrix dimensions",
+                Shape::Matrix(rows, cols),
+                Shape::Vector(data.len()),
+            ));
         }
+        Ok(Matrix { rows, cols, data })
     }
 
-    result
-}
+    /// Copy nested rows into a contiguous matrix; all rows must have the same length
+    /// An empty slice gives a 0 x 0 matrix
+    pub fn from_rows(rows: &[Vec<T>]) -> Self {
+        or_panic(Matrix::try_from_rows(rows))
+    }
 
-pub fn matrix_transpose(matrix: &Vec<Vec<f64>>) -> Vec<Vec<f64>> {
-    let rows = matrix.len();
-    let cols = matrix[0].len();
-    let mut result = vec![vec![0.0; rows]; cols];
+    pub fn try_from_rows(rows: &[Vec<T>]) -> Result<Self, ShapeError> {
+        let cols = rows.first().map_or(0, |row| row.len());
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(ShapeError::new(
+                "All rows must have the same length",
+                Shape::Vector(cols),
+                Shape::Vector(row.len()),
+            ));
+        }
+        Ok(Matrix { rows: rows.len(), cols, data: rows.concat() })
+    }
 
-    for i in 0..rows {
-        for j in 0..cols {
-            result[j][i] = matrix[i][j];
+    /// Copy back into the nested `Vec<Vec<T>>` form
+    pub fn to_rows(&self) -> Vec<Vec<T>> {
+        self.view().to_rows()
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// True if the matrix has no elements
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// View of the whole matrix
+    pub fn view(&self) -> MatrixView<'_, T> {
+        MatrixView { rows: self.rows, cols: self.cols, data: &self.data }
+    }
+
+    /// View of rows `range.start..range.end`
+    pub fn slice_rows(&self, range: Range<usize>) -> MatrixView<'_, T> {
+        self.view().slice_rows(range)
+    }
+
+    pub fn row(&self, i: usize) -> &[T] {
+        self.view().row(i)
+    }
+
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        if i >= self.rows {
+            panic!("Row {} out of bounds for {}x{} matrix", i, self.rows, self.cols);
         }
+        &mut self.data[i * self.cols..(i + 1) * self.cols]
     }
 
-    result
+    /// Elements of column `j`, top to bottom
+    pub fn col(&self, j: usize) -> impl Iterator<Item = &T> + '_ {
+        self.view().col(j)
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.view().iter_rows()
+    }
+
+    pub fn iter_rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> + '_ {
+        // chunks_exact_mut(0) would panic; a matrix without columns has no elements to hand out
+        self.data.chunks_exact_mut(self.cols.max(1))
+    }
+
+    pub fn iter_cols(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.cols).map(move |j| self.col(j))
+    }
+
+    /// New matrix made of the given rows, in the given order (repeats allowed)
+    pub fn select_rows(&self, indices: &[usize]) -> Matrix<T> {
+        let mut data = Vec::with_capacity(indices.len() * self.cols);
+        for &i in indices {
+            data.extend_from_slice(self.row(i));
+        }
+        Matrix { rows: indices.len(), cols: self.cols, data }
+    }
+
+    /// Copy with every element converted to another float type, e.g. trained f64 weights to f32
+    pub fn cast<U: Float>(&self) -> Matrix<U> {
+        Matrix { rows: self.rows, cols: self.cols, data: cast_slice(&self.data) }
+    }
 }
 
-pub fn matrix_add(a: &Vec<Vec<f64>>, b: &Vec<Vec<f64>>) -> Vec<Vec<f64>> {
-    let rows = a.len();
-    let cols = a[0].len();
+impl<'a, T: Float> MatrixView<'a, T> {
+    /// View a single vector as a 1 x n matrix
+    pub fn from_row(row: &'a [T]) -> Self {
+        MatrixView { rows: 1, cols: row.len(), data: row }
+    }
+
+    /// View a single vector as an n x 1 matrix
+    pub fn from_col(col: &'a [T]) -> Self {
+        MatrixView { rows: col.len(), cols: 1, data: col }
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
 
-    if rows != b.len() || cols != b[0].len() {
-        panic!("Matrix dimensions do not match for addition");
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
     }
 
-    let mut result = vec![vec![0.0; cols]; rows];
+    /// Sub-view of rows `range.start..range.end`
+    pub fn slice_rows(&self, range: Range<usize>) -> MatrixView<'a, T> {
+        if range.start > range.end || range.end > self.rows {
+            panic!("Row range {:?} out of bounds for {}x{} matrix", range, self.rows, self.cols);
+        }
+        MatrixView {
+            rows: range.end - range.start,
+            cols: self.cols,
+            data: &self.data[range.start * self.cols..range.end * self.cols],
+        }
+    }
 
-    for i in 0..rows {
-        for j in 0..cols {
-            result[i][j] = a[i][j] + b[i][j];
+    pub fn row(&self, i: usize) -> &'a [T] {
+        if i >= self.rows {
+            panic!("Row {} out of bounds for {}x{} matrix", i, self.rows, self.cols);
         }
+        &self.data[i * self.cols..(i + 1) * self.cols]
     }
 
-    result
+    pub fn col(&self, j: usize) -> impl Iterator<Item = &'a T> + 'a {
+        if j >= self.cols {
+            panic!("Column {} out of bounds for {}x{} matrix", j, self.rows, self.cols);
+        }
+        self.data.iter().skip(j).step_by(self.cols)
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &'a [T]> + 'a {
+        let view = *self;
+        (0..self.rows).map(move |i| view.row(i))
+    }
+
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix { rows: self.rows, cols: self.cols, data: self.data.to_vec() }
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<T>> {
+        self.iter_rows().map(|row| row.to_vec()).collect()
+    }
 }
 
-pub fn dot_product(a: &Vec<f64>, b: &Vec<f64>) -> f64 {
-    if a.len() != b.len() {
-        panic!("Vectors must be of the same length for dot product");
+impl<'a, T: Float> From<&'a Matrix<T>> for MatrixView<'a, T> {
+    fn from(matrix: &'a Matrix<T>) -> Self {
+        matrix.view()
     }
+}
 
-    let mut result = 0.0;
+impl<T: Float> From<Vec<Vec<T>>> for Matrix<T> {
+    fn from(rows: Vec<Vec<T>>) -> Self {
+        Matrix::from_rows(&rows)
+    }
+}
 
-    for i in 0..a.len() {
-        result += a[i] * b[i];
+impl<T: Float> From<Matrix<T>> for Vec<Vec<T>> {
+    fn from(matrix: Matrix<T>) -> Self {
+        matrix.to_rows()
     }
+}
 
-    result
+fn check_index(rows: usize, cols: usize, (i, j): (usize, usize)) -> usize {
+    if i >= rows || j >= cols {
+        panic!("Index ({}, {}) out of bounds for {}x{} matrix", i, j, rows, cols);
+    }
+    i * cols + j
+}
+
+impl<T: Float> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, index: (usize, usize)) -> &T {
+        &self.data[check_index(self.rows, self.cols, index)]
+    }
 }
 
-pub fn scalar_multiply(matrix: &Vec<Vec<f64>>, scalar: f64) -> Vec<Vec<f64>> {
-    let rows = matrix.len();
-    let cols = matrix[0].len();
-    let mut result = vec![vec![0.0; cols]; rows];
+impl<T: Float> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut T {
+        let position = check_index(self.rows, self.cols, index);
+        &mut self.data[position]
+    }
+}
+
+impl<T: Float> Index<(usize, usize)> for MatrixView<'_, T> {
+    type Output = T;
 
-    for i in 0..rows {
-        for j in 0..cols {
-            result[i][j] = matrix[i][j] * scalar;
+    fn index(&self, index: (usize, usize)) -> &T {
+        &self.data[check_index(self.rows, self.cols, index)]
+    }
+}
+
+/// Shape of an operand, as reported in a `ShapeError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Vector(usize),
+    Matrix(usize, usize),
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Vector(len) => write!(f, "length {}", len),
+            Shape::Matrix(rows, cols) => write!(f, "{}x{}", rows, cols),
         }
     }
+}
+
+/// Operands of incompatible shapes were passed to a `matrix_math` operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeError {
+    pub message: &'static str,
+    pub left: Shape,
+    pub right: Shape,
+}
+
+impl ShapeError {
+    fn new(message: &'static str, left: Shape, right: Shape) -> Self {
+        ShapeError { message, left, right }
+    }
 
-    result
+    fn matrices<T>(message: &'static str, a: MatrixView<T>, b: MatrixView<T>) -> Self {
+        ShapeError::new(message, Shape::Matrix(a.rows, a.cols), Shape::Matrix(b.rows, b.cols))
+    }
+
+    fn vectors<T>(message: &'static str, a: &[T], b: &[T]) -> Self {
+        ShapeError::new(message, Shape::Vector(a.len()), Shape::Vector(b.len()))
+    }
 }
 
-pub fn elementwise_multiply(a: &Vec<Vec<f64>>, b: &Vec<Vec<f64>>) -> Vec<Vec<f64>> {
-    let rows = a.len();
-    let cols = a[0].len();
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (got {} and {})", self.message, self.left, self.right)
+    }
+}
 
-    if rows != b.len() || cols != b[0].len() {
-        panic!("Matrix dimensions do not match for element-wise multiplication");
+impl std::error::Error for ShapeError {}
+
+/// The panicking functions below are thin wrappers around their `try_` variants
+fn or_panic<R>(result: Result<R, ShapeError>) -> R {
+    result.unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn matrix_multiply<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Matrix<T> {
+    or_panic(try_matrix_multiply(a, b))
+}
+
+pub fn try_matrix_multiply<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Result<Matrix<T>, ShapeError> {
+    let (a, b) = (a.into(), b.into());
+
+    if a.cols != b.rows {
+        return Err(ShapeError::matrices("Matrix dimensions do not match for multiplication", a, b));
     }
 
-    let mut result = vec![vec![0.0; cols]; rows];
+    let mut result = Matrix::zeros(a.rows, b.cols);
+    gemm(a, b, &mut result);
+    Ok(result)
+}
+
+/// Reference i-j-k triple loop, kept for testing and benchmarking `gemm`
+pub fn matrix_multiply_naive<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Matrix<T> {
+    let (a, b) = (a.into(), b.into());
 
-    for i in 0..rows {
-        for j in 0..cols {
-            result[i][j] = a[i][j] * b[i][j];
+    if a.cols != b.rows {
+        panic!("{}", ShapeError::matrices("Matrix dimensions do not match for multiplication", a, b));
+    }
+
+    let mut result = Matrix::zeros(a.rows, b.cols);
+
+    for i in 0..a.rows {
+        for j in 0..b.cols {
+            for k in 0..a.cols {
+                result[(i, j)] += a[(i, k)] * b[(k, j)];
+            }
         }
     }
 
     result
 }
 
-pub fn vector_add(a: &Vec<f64>, b: &Vec<f64>) -> Vec<f64> {
+pub fn matrix_transpose<'a, T: Float>(matrix: impl Into<MatrixView<'a, T>>) -> Matrix<T> {
+    let matrix = matrix.into();
+    let mut result = Matrix::zeros(matrix.cols, matrix.rows);
+    let work = matrix.data.len();
+
+    // Each thread fills a band of output rows (input columns)
+    parallel::for_each_chunk_mut(&mut result.data, matrix.rows, work, |offset, band| {
+        let first_col = offset / matrix.rows.max(1);
+        for (j, out_row) in band.chunks_exact_mut(matrix.rows.max(1)).enumerate() {
+            for (out, value) in out_row.iter_mut().zip(matrix.col(first_col + j)) {
+                *out = *value;
+            }
+        }
+    });
+
+    result
+}
+
+pub fn matrix_add<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Matrix<T> {
+    or_panic(try_matrix_add(a, b))
+}
+
+pub fn try_matrix_add<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Result<Matrix<T>, ShapeError> {
+    let (a, b) = (a.into(), b.into());
+
+    if a.shape() != b.shape() {
+        return Err(ShapeError::matrices("Matrix dimensions do not match for addition", a, b));
+    }
+
+    let level = simd::active_level();
+    let data = parallel::zip_map_chunks(a.data, b.data, |a, b, out| T::add_into(level, a, b, out));
+    Ok(Matrix { rows: a.rows, cols: a.cols, data })
+}
+
+pub fn dot_product<T: Float>(a: &[T], b: &[T]) -> T {
+    or_panic(try_dot_product(a, b))
+}
+
+pub fn try_dot_product<T: Float>(a: &[T], b: &[T]) -> Result<T, ShapeError> {
     if a.len() != b.len() {
-        panic!("Vectors must be of the same length for addition");
+        return Err(ShapeError::vectors("Vectors must be of the same length for dot product", a, b));
     }
-    a.iter().zip(b.iter()).map(|(x, y)| x + y).collect()
+
+    let level = simd::active_level();
+    Ok(parallel::sum_blocks(a.len(), |range| T::dot(level, &a[range.clone()], &b[range])))
+}
+
+pub fn scalar_multiply<'a, T: Float>(matrix: impl Into<MatrixView<'a, T>>, scalar: T) -> Matrix<T> {
+    let matrix = matrix.into();
+    let level = simd::active_level();
+    let data = parallel::map_chunks(matrix.data, |input, out| T::scale_into(level, input, scalar, out));
+    Matrix { rows: matrix.rows, cols: matrix.cols, data }
+}
+
+pub fn elementwise_multiply<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Matrix<T> {
+    or_panic(try_elementwise_multiply(a, b))
+}
+
+pub fn try_elementwise_multiply<'a, 'b, T: Float>(a: impl Into<MatrixView<'a, T>>, b: impl Into<MatrixView<'b, T>>) -> Result<Matrix<T>, ShapeError> {
+    let (a, b) = (a.into(), b.into());
+
+    if a.shape() != b.shape() {
+        return Err(ShapeError::matrices("Matrix dimensions do not match for element-wise multiplication", a, b));
+    }
+
+    let level = simd::active_level();
+    let data = parallel::zip_map_chunks(a.data, b.data, |a, b, out| T::mul_into(level, a, b, out));
+    Ok(Matrix { rows: a.rows, cols: a.cols, data })
+}
+
+pub fn vector_add<T: Float>(a: &[T], b: &[T]) -> Vec<T> {
+    or_panic(try_vector_add(a, b))
+}
+
+pub fn try_vector_add<T: Float>(a: &[T], b: &[T]) -> Result<Vec<T>, ShapeError> {
+    if a.len() != b.len() {
+        return Err(ShapeError::vectors("Vectors must be of the same length for addition", a, b));
+    }
+    let level = simd::active_level();
+    Ok(parallel::zip_map_chunks(a, b, |a, b, out| T::add_into(level, a, b, out)))
 }
 
 /// Matrix addition in-place - modifies first matrix
-pub fn matrix_add_inplace(a: &mut Vec<Vec<f64>>, b: &Vec<Vec<f64>>) {
-    if a.len() != b.len() || a[0].len() != b[0].len() {
-        panic!("Matrices must be of the same dimensions for addition");
+pub fn matrix_add_inplace<'b, T: Float>(a: &mut Matrix<T>, b: impl Into<MatrixView<'b, T>>) {
+    or_panic(try_matrix_add_inplace(a, b))
+}
+
+/// Matrix addition in-place; `a` is left unchanged on error
+pub fn try_matrix_add_inplace<'b, T: Float>(a: &mut Matrix<T>, b: impl Into<MatrixView<'b, T>>) -> Result<(), ShapeError> {
+    let b = b.into();
+    if a.shape() != b.shape() {
+        return Err(ShapeError::matrices("Matrix dimensions do not match for addition", a.view(), b));
     }
-    for i in 0..a.len() {
-        for j in 0..a[0].len() {
-            a[i][j] += b[i][j];
-        }
+    let level = simd::active_level();
+    parallel::zip_apply_chunks(&mut a.data, b.data, |a, b| T::axpy(level, T::ONE, b, a));
+    Ok(())
+}
+
+/// Add `row` to every row of `matrix` in place (e.g. a bias over a batch)
+pub fn add_row_vector_inplace<T: Float>(matrix: &mut Matrix<T>, row: &[T]) {
+    or_panic(try_add_row_vector_inplace(matrix, row))
+}
+
+/// Row-broadcast addition in-place; `matrix` is left unchanged on error
+pub fn try_add_row_vector_inplace<T: Float>(matrix: &mut Matrix<T>, row: &[T]) -> Result<(), ShapeError> {
+    if matrix.cols != row.len() {
+        return Err(ShapeError::new(
+            "Row vector length does not match matrix columns",
+            Shape::Matrix(matrix.rows, matrix.cols),
+            Shape::Vector(row.len()),
+        ));
     }
+    let level = simd::active_level();
+    let work = matrix.data.len();
+    parallel::for_each_chunk_mut(&mut matrix.data, row.len().max(1), work, |_, rows| {
+        for matrix_row in rows.chunks_exact_mut(row.len().max(1)) {
+            T::axpy(level, T::ONE, row, matrix_row);
+        }
+    });
+    Ok(())
 }
 
 /// Vector addition in-place - modifies first vector
-pub fn vector_add_inplace(a: &mut Vec<f64>, b: &Vec<f64>) {
+pub fn vector_add_inplace<T: Float>(a: &mut [T], b: &[T]) {
+    or_panic(try_vector_add_inplace(a, b))
+}
+
+/// Vector addition in-place; `a` is left unchanged on error
+pub fn try_vector_add_inplace<T: Float>(a: &mut [T], b: &[T]) -> Result<(), ShapeError> {
     if a.len() != b.len() {
-        panic!("Vectors must be of the same length for addition");
-    }
-    for i in 0..a.len() {
-        a[i] += b[i];
+        return Err(ShapeError::vectors("Vectors must be of the same length for addition", a, b));
     }
+    let level = simd::active_level();
+    parallel::zip_apply_chunks(a, b, |a, b| T::axpy(level, T::ONE, b, a));
+    Ok(())
+}
+
+pub fn vector_scalar_divide<T: Float>(vector: &[T], scalar: T) -> Vec<T> {
+    parallel::map(vector, |x| x / scalar)
 }
 
-pub fn vector_scalar_divide(vector: &Vec<f64>, scalar: f64) -> Vec<f64> {
-    vector.iter().map(|x| x / scalar).collect()
+pub fn matrix_scalar_divide<'a, T: Float>(matrix: impl Into<MatrixView<'a, T>>, scalar: T) -> Matrix<T> {
+    let matrix = matrix.into();
+    let data = parallel::map(matrix.data, |x| x / scalar);
+    Matrix { rows: matrix.rows, cols: matrix.cols, data }
 }
 
-pub fn matrix_scalar_divide(matrix: &Vec<Vec<f64>>, scalar: f64) -> Vec<Vec<f64>> {
-    matrix.iter()
-        .map(|row| row.iter().map(|x| x / scalar).collect())
-        .collect()
+pub fn vector_elementwise_multiply<T: Float>(a: &[T], b: &[T]) -> Vec<T> {
+    or_panic(try_vector_elementwise_multiply(a, b))
 }
 
-pub fn vector_elementwise_multiply(a: &Vec<f64>, b: &Vec<f64>) -> Vec<f64> {
+pub fn try_vector_elementwise_multiply<T: Float>(a: &[T], b: &[T]) -> Result<Vec<T>, ShapeError> {
     if a.len() != b.len() {
-        panic!("Vectors must be of the same length for element-wise multiplication");
+        return Err(ShapeError::vectors("Vectors must be of the same length for element-wise multiplication", a, b));
+    }
+    let level = simd::active_level();
+    Ok(parallel::zip_map_chunks(a, b, |a, b, out| T::mul_into(level, a, b, out)))
+}
+
+/// Sum of all elements
+pub fn matrix_sum<'a, T: Float>(matrix: impl Into<MatrixView<'a, T>>) -> T {
+    let data = matrix.into().data;
+    parallel::sum_blocks(data.len(), |range| data[range].iter().copied().sum())
+}
+
+/// Sum of each column (e.g. a bias gradient summed over a batch of rows)
+pub fn column_sums<'a, T: Float>(matrix: impl Into<MatrixView<'a, T>>) -> Vec<T> {
+    let matrix = matrix.into();
+    // Blocks of whole rows, summed independently and then added in order
+    let rows_per_block = (4096 / matrix.cols.max(1)).max(1);
+    let partials = parallel::map_blocks(matrix.rows, rows_per_block, matrix.data.len(), |rows| {
+        let mut sums = vec![T::ZERO; matrix.cols];
+        for row in matrix.slice_rows(rows).iter_rows() {
+            for (sum, value) in sums.iter_mut().zip(row) {
+                *sum += *value;
+            }
+        }
+        sums
+    });
+
+    let mut sums = vec![T::ZERO; matrix.cols];
+    for partial in &partials {
+        vector_add_inplace(&mut sums, partial);
     }
-    a.iter().zip(b.iter()).map(|(x, y)| x * y).collect()
+    sums
 }
 
 /// Initialize gradient matrix with same dimensions as input, filled with zeros
-pub fn initialize_gradient_matrix_like(matrix: &Vec<Vec<f64>>) -> Vec<Vec<f64>> {
-    vec![vec![0.0; matrix[0].len()]; matrix.len()]
+pub fn initialize_gradient_matrix_like<T: Float>(matrix: &Matrix<T>) -> Matrix<T> {
+    Matrix::zeros(matrix.rows, matrix.cols)
 }
 
 /// Initialize gradient vector with same length as input, filled with zeros
-pub fn initialize_gradient_vector_like(vector: &Vec<f64>) -> Vec<f64> {
-    vec![0.0; vector.len()]
+pub fn initialize_gradient_vector_like<T: Float>(vector: &[T]) -> Vec<T> {
+    vec![T::ZERO; vector.len()]
 }
\ No newline at end of file
@@ -0,0 +1,278 @@
+use crate::data::load_csv;
+use crate::rng::Rng;
+use std::f64::consts::PI;
+
+/// Type of noise, named after the MIT-BIH Noise Stress Test records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Baseline wander ("bw")
+    BaselineWander,
+    /// Muscle (EMG) artifact ("ma")
+    MuscleArtifact,
+    /// Electrode motion artifact ("em")
+    ElectrodeMotion,
+}
+
+impl NoiseKind {
+    /// Name of the corresponding NST noise record
+    pub fn record_name(&self) -> &'static str {
+        match self {
+            NoiseKind::BaselineWander => "bw",
+            NoiseKind::MuscleArtifact => "ma",
+            NoiseKind::ElectrodeMotion => "em",
+        }
+    }
+}
+
+/// Where noise samples come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoiseSource {
+    /// A recorded noise signal (e.g. loaded with `load_noise_record`), reused cyclically
+    Recorded { kind: NoiseKind, samples: Vec<f64> },
+    /// Noise synthesised on the fly by `synthetic_noise`
+    Synthetic(NoiseKind),
+}
+
+impl NoiseSource {
+    pub fn kind(&self) -> NoiseKind {
+        match self {
+            NoiseSource::Recorded { kind, .. } => *kind,
+            NoiseSource::Synthetic(kind) => *kind,
+        }
+    }
+
+    /// Draw `len` noise samples, starting at a random offset for recorded noise
+    pub fn realise(&self, len: usize, sample_rate: f64, rng: &mut Rng) -> Vec<f64> {
+        match self {
+            NoiseSource::Recorded { samples, .. } => {
+                if samples.is_empty() {
+                    return vec![0.0; len];
+                }
+                let offset = rng.below(samples.len());
+                (0..len).map(|i| samples[(offset + i) % samples.len()]).collect()
+            }
+            NoiseSource::Synthetic(kind) => synthetic_noise(*kind, len, sample_rate, rng),
+        }
+    }
+}
+
+/// Load one column of a noise recording stored as CSV (same format as `load_csv`)
+pub fn load_noise_record(file_path: &str, column: usize) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let rows = load_csv(file_path)?;
+    rows.iter()
+        .map(|row| {
+            row.get(column)
+                .copied()
+                .ok_or_else(|| format!("Column {} missing in {}", column, file_path).into())
+        })
+        .collect()
+}
+
+/// Generate synthetic noise of the given kind
+///
+/// * Baseline wander: a few random sinusoids between 0.05 and 0.5 Hz
+/// * Muscle artifact: white noise through a 5 Hz first-order high-pass
+/// * Electrode motion: a leaky random walk plus sporadic decaying step artifacts
+///
+/// The result is not normalised; `mix_at_snr` sets the final level.
+pub fn synthetic_noise(kind: NoiseKind, len: usize, sample_rate: f64, rng: &mut Rng) -> Vec<f64> {
+    let dt = 1.0 / sample_rate;
+
+    match kind {
+        NoiseKind::BaselineWander => {
+            let components: Vec<(f64, f64, f64)> = (0..4)
+                .map(|_| (rng.uniform(0.05, 0.5), rng.uniform(0.5, 1.0), rng.uniform(0.0, 2.0 * PI)))
+                .collect();
+            (0..len)
+                .map(|i| {
+                    let t = i as f64 * dt;
+                    components.iter()
+                        .map(|(f, a, p)| a * (2.0 * PI * f * t + p).sin())
+                        .sum()
+                })
+                .collect()
+        }
+        NoiseKind::MuscleArtifact => {
+            let rc = 1.0 / (2.0 * PI * 5.0);
+            let alpha = rc / (rc + dt);
+            let mut previous_input = 0.0;
+            let mut previous_output = 0.0;
+            (0..len)
+                .map(|_| {
+                    let input = rng.normal(0.0, 1.0);
+                    previous_output = alpha * (previous_output + input - previous_input);
+                    previous_input = input;
+                    previous_output
+                })
+                .collect()
+        }
+        NoiseKind::ElectrodeMotion => {
+            let leak = (-dt / 2.0).exp();
+            let decay = (-dt / 0.3).exp();
+            let artifact_rate = 0.2; // artifacts per second
+            let mut walk = 0.0;
+            let mut artifact = 0.0;
+            (0..len)
+                .map(|_| {
+                    walk = leak * walk + rng.normal(0.0, dt.sqrt());
+                    artifact *= decay;
+                    if rng.chance(artifact_rate * dt) {
+                        artifact += rng.normal(0.0, 2.0);
+                    }
+                    walk + artifact
+                })
+                .collect()
+        }
+    }
+}
+
+/// Mean power of a signal about its mean (its variance)
+pub fn signal_power(signal: &[f64]) -> f64 {
+    if signal.is_empty() {
+        return 0.0;
+    }
+    let mean = signal.iter().sum::<f64>() / signal.len() as f64;
+    signal.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / signal.len() as f64
+}
+
+/// Add noise to a clean signal at the given signal-to-noise ratio
+///
+/// # Arguments
+/// * `clean` - Clean signal
+/// * `noise` - Noise signal, at least as long as `clean`
+/// * `snr_db` - Target SNR in dB, measured as power of `clean` over power of the added noise.
+///   Both powers are taken over the whole record, also when a `mask` is given, so the noisy
+///   spans see the same noise level as they would without the mask (as in the NST records)
+/// * `mask` - Optional per-sample switch, one per sample of `clean`; noise is only added where it is `true`
+///
+/// # Returns
+/// * The noisy signal
+pub fn mix_at_snr(clean: &[f64], noise: &[f64], snr_db: f64, mask: Option<&[bool]>) -> Vec<f64> {
+    if noise.len() < clean.len() {
+        panic!("Noise signal is shorter than the clean signal");
+    }
+    if let Some(mask) = mask {
+        assert_eq!(mask.len(), clean.len(), "Noise mask needs one entry per sample of the clean signal");
+    }
+
+    let noise = &noise[..clean.len()];
+    let noise_power = signal_power(noise);
+    let gain = if noise_power > 0.0 {
+        (signal_power(clean) / (noise_power * 10f64.powf(snr_db / 10.0))).sqrt()
+    } else {
+        0.0
+    };
+
+    clean.iter()
+        .zip(noise.iter())
+        .enumerate()
+        .map(|(i, (s, n))| {
+            let on = mask.is_none_or(|m| m[i]);
+            if on { s + gain * n } else { *s }
+        })
+        .collect()
+}
+
+/// Noise on/off schedule used by the MIT-BIH NST records:
+/// clean for the first 5 minutes, then alternating 2 minutes noisy and 2 minutes clean
+pub fn nst_schedule(len: usize, sample_rate: f64) -> Vec<bool> {
+    let lead_in = (300.0 * sample_rate) as usize;
+    let block = ((120.0 * sample_rate) as usize).max(1);
+    (0..len)
+        .map(|i| i >= lead_in && ((i - lead_in) / block).is_multiple_of(2))
+        .collect()
+}
+
+/// Configuration for `noise_stress_dataset`
+#[derive(Debug, Clone)]
+pub struct StressConfig {
+    pub sample_rate: f64,
+    /// SNR levels in dB; NST uses 24, 18, 12, 6, 0 and -6
+    pub snr_levels: Vec<f64>,
+    pub sources: Vec<NoiseSource>,
+    /// Use the NST on/off schedule instead of adding noise everywhere
+    pub use_nst_schedule: bool,
+    pub seed: u64,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        StressConfig {
+            sample_rate: 360.0,
+            snr_levels: vec![24.0, 18.0, 12.0, 6.0, 0.0, -6.0],
+            sources: vec![
+                NoiseSource::Synthetic(NoiseKind::BaselineWander),
+                NoiseSource::Synthetic(NoiseKind::MuscleArtifact),
+                NoiseSource::Synthetic(NoiseKind::ElectrodeMotion),
+            ],
+            use_nst_schedule: false,
+            seed: 42,
+        }
+    }
+}
+
+/// One noisy copy of a record
+#[derive(Debug, Clone)]
+pub struct StressedRecord {
+    pub noise: NoiseKind,
+    pub snr_db: f64,
+    /// Rows in the input layout: column 0 is copied unchanged, every other column is noisy
+    pub rows: Vec<Vec<f64>>,
+    /// True for samples where noise was added
+    pub noisy: Vec<bool>,
+}
+
+/// Build noisy versions of a record for every (noise source, SNR) pair
+///
+/// Rows use the `load_csv` layout of `[index, lead_1, lead_2, ...]`. Each lead
+/// gets an independent stretch of noise and the SNR is set per lead. Sample
+/// order is unchanged, so labels of the clean record (e.g. segment labels)
+/// apply to every noisy copy as they are.
+pub fn noise_stress_dataset(rows: &[Vec<f64>], config: &StressConfig) -> Vec<StressedRecord> {
+    let mut rng = Rng::new(config.seed);
+    let len = rows.len();
+    let num_leads = rows.first().map_or(0, |row| row.len().saturating_sub(1));
+    let clean: Vec<Vec<f64>> = (0..num_leads)
+        .map(|lead| rows.iter().map(|row| row[lead + 1]).collect())
+        .collect();
+
+    let noisy = if config.use_nst_schedule {
+        nst_schedule(len, config.sample_rate)
+    } else {
+        vec![true; len]
+    };
+
+    let mut records = Vec::new();
+    for source in &config.sources {
+        // The same noise realisation is used at every SNR so levels are directly comparable
+        let noise: Vec<Vec<f64>> = (0..num_leads)
+            .map(|_| source.realise(len, config.sample_rate, &mut rng))
+            .collect();
+
+        for &snr_db in &config.snr_levels {
+            let leads: Vec<Vec<f64>> = clean.iter()
+                .zip(noise.iter())
+                .map(|(signal, n)| mix_at_snr(signal, n, snr_db, Some(&noisy)))
+                .collect();
+
+            let noisy_rows = rows.iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let mut out = Vec::with_capacity(row.len());
+                    out.push(row[0]);
+                    out.extend(leads.iter().map(|lead| lead[i]));
+                    out
+                })
+                .collect();
+
+            records.push(StressedRecord {
+                noise: source.kind(),
+                snr_db,
+                rows: noisy_rows,
+                noisy: noisy.clone(),
+            });
+        }
+    }
+
+    records
+}
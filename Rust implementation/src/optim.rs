@@ -0,0 +1,496 @@
+use crate::float::Float;
+use crate::layers::Param;
+use crate::matrix_math::Matrix;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+
+/// Updates parameters from their gradients, keeping whatever per-parameter state it needs
+///
+/// `step` must be given the same parameters in the same order every time
+/// (e.g. `Sequential::parameters_mut`); state is created on the first step.
+pub trait Optimizer<T: Float = f64> {
+    /// Short type name, also used to check saved state belongs to this optimizer
+    fn name(&self) -> &'static str;
+
+    fn learning_rate(&self) -> f64;
+
+    /// Change the step size, e.g. from a learning-rate schedule
+    fn set_learning_rate(&mut self, learning_rate: f64);
+
+    /// Apply one update to every parameter using its `grad`
+    fn step(&mut self, params: &mut [&mut Param<T>]);
+
+    /// Snapshot of everything needed to continue training exactly where it stopped
+    fn state(&self) -> OptimizerState;
+
+    /// Restore a snapshot taken with `state`
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>>;
+}
+
+/// Serialisable optimizer state: step count, learning rate and one buffer per parameter per slot
+///
+/// Values are stored as f64 whatever the parameter type; f32 state round-trips exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizerState {
+    pub name: String,
+    pub learning_rate: f64,
+    /// Number of `step` calls so far
+    pub step: u64,
+    /// (slot name, buffer of every parameter), e.g. ("m", [...]) and ("v", [...]) for Adam
+    pub buffers: Vec<(String, Vec<Matrix>)>,
+    /// Position of the learning-rate schedule, when the optimizer is `Scheduled`
+    pub schedule: Option<ScheduleState>,
+}
+
+/// Position of a learning-rate schedule as named values, e.g. ("step", 12.0) for `OneCycle`
+///
+/// Only the progress is saved; the schedule's settings come from the code that builds it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleState {
+    pub name: String,
+    pub values: Vec<(String, f64)>,
+}
+
+impl ScheduleState {
+    /// Value saved under `key`
+    pub fn value(&self, key: &str) -> Result<f64, Box<dyn Error>> {
+        self.values.iter()
+            .find(|(name, _)| name == key)
+            .map(|&(_, value)| value)
+            .ok_or_else(|| format!("{} schedule state has no '{}'", self.name, key).into())
+    }
+}
+
+impl OptimizerState {
+    /// Plain-text form, one header line per value (the schedule's on one line) and one line of numbers per buffer
+    ///
+    /// Numbers are written with Rust's shortest round-trip formatting, so
+    /// `from_text(&state.to_text())` gives back exactly the same state.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "optimizer {}", self.name).unwrap();
+        writeln!(text, "learning_rate {}", self.learning_rate).unwrap();
+        writeln!(text, "step {}", self.step).unwrap();
+        if let Some(schedule) = &self.schedule {
+            write!(text, "schedule {}", schedule.name).unwrap();
+            for (key, value) in &schedule.values {
+                write!(text, " {} {}", key, value).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+        for (slot, matrices) in &self.buffers {
+            for (i, matrix) in matrices.iter().enumerate() {
+                writeln!(text, "buffer {} {} {} {}", slot, i, matrix.rows, matrix.cols).unwrap();
+                let values: Vec<String> = matrix.data.iter().map(|x| x.to_string()).collect();
+                writeln!(text, "{}", values.join(" ")).unwrap();
+            }
+        }
+        text
+    }
+
+    /// Parse the output of `to_text`
+    pub fn from_text(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).peekable();
+        let name = next_field(&mut lines, "optimizer")?;
+        let learning_rate = next_field(&mut lines, "learning_rate")?.parse()?;
+        let step = next_field(&mut lines, "step")?.parse()?;
+
+        let mut schedule = None;
+        if lines.peek().is_some_and(|(_, line)| line.split_whitespace().next() == Some("schedule")) {
+            let (index, line) = lines.next().unwrap();
+            let parts: Vec<&str> = line.split_whitespace().skip(1).collect();
+            let pairs = parts.get(1..).unwrap_or_default().chunks_exact(2);
+            if parts.is_empty() || !pairs.remainder().is_empty() {
+                return Err(format!("line {}: expected 'schedule <name> [<key> <value>]...', got '{}'", index + 1, line).into());
+            }
+            let values = pairs
+                .map(|pair| Ok((pair[0].to_string(), pair[1].parse()?)))
+                .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+            schedule = Some(ScheduleState { name: parts[0].to_string(), values });
+        }
+
+        let mut buffers: Vec<(String, Vec<Matrix>)> = Vec::new();
+        while let Some((index, line)) = lines.next() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 5 || parts[0] != "buffer" {
+                return Err(format!("line {}: expected 'buffer <slot> <index> <rows> <cols>', got '{}'", index + 1, line).into());
+            }
+            let (slot, position, rows, cols): (&str, usize, usize, usize) = (parts[1], parts[2].parse()?, parts[3].parse()?, parts[4].parse()?);
+            let values = match lines.next() {
+                Some((_, line)) => line.split_whitespace().map(|x| x.parse::<f64>()).collect::<Result<Vec<_>, _>>()?,
+                None => Vec::new(),
+            };
+            let matrix = Matrix::try_from_vec(rows, cols, values)?;
+
+            match buffers.last_mut() {
+                Some((last, matrices)) if last == slot => matrices.push(matrix),
+                _ => buffers.push((slot.to_string(), vec![matrix])),
+            }
+            if buffers.last().map_or(0, |(_, matrices)| matrices.len()) != position + 1 {
+                return Err(format!("line {}: buffer {} {} is out of order", index + 1, slot, position).into());
+            }
+        }
+
+        Ok(OptimizerState { name, learning_rate, step, buffers, schedule })
+    }
+
+    pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(file_path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn load(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_text(&fs::read_to_string(file_path)?)
+    }
+}
+
+/// Value of the next `key value` line
+fn next_field<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>, key: &str) -> Result<String, Box<dyn Error>> {
+    match lines.next() {
+        Some((_, line)) if line.split_whitespace().next() == Some(key) => Ok(line[key.len()..].trim().to_string()),
+        Some((index, line)) => Err(format!("line {}: expected '{}', got '{}'", index + 1, key, line).into()),
+        None => Err(format!("missing '{}'", key).into()),
+    }
+}
+
+/// Per-parameter buffers shared by all the optimizers: `buffers[slot][param]`
+#[derive(Debug, Clone)]
+struct Slots<T> {
+    names: &'static [&'static str],
+    buffers: Vec<Vec<Matrix<T>>>,
+    step: u64,
+}
+
+impl<T: Float> Slots<T> {
+    fn new(names: &'static [&'static str]) -> Self {
+        Slots { names, buffers: vec![Vec::new(); names.len()], step: 0 }
+    }
+
+    /// Create zero buffers on the first step and check later steps see the same parameters
+    fn prepare(&mut self, params: &[&mut Param<T>]) {
+        let ready = self.buffers.iter().all(|buffers| buffers.len() == params.len());
+        if self.step == 0 && !ready {
+            for buffers in self.buffers.iter_mut() {
+                *buffers = params.iter().map(|p| Matrix::zeros(p.value.rows, p.value.cols)).collect();
+            }
+        }
+        for buffers in &self.buffers {
+            let shapes_match = buffers.len() == params.len() && buffers.iter().zip(params).all(|(b, p)| b.shape() == p.value.shape());
+            if !shapes_match {
+                panic!("Optimizer state was created for different parameters ({} buffers for {} parameters)", buffers.len(), params.len());
+            }
+        }
+        self.step += 1;
+    }
+
+    fn state(&self, name: &str, learning_rate: f64) -> OptimizerState {
+        let buffers = self.names.iter().zip(&self.buffers)
+            .map(|(slot, matrices)| (slot.to_string(), matrices.iter().map(|m| m.cast()).collect()))
+            .collect();
+        OptimizerState { name: name.to_string(), learning_rate, step: self.step, buffers, schedule: None }
+    }
+
+    /// Restore buffers from `state`; returns its learning rate
+    fn load(&mut self, name: &str, state: &OptimizerState) -> Result<f64, Box<dyn Error>> {
+        if state.name != name {
+            return Err(format!("Cannot load {} state into a {} optimizer", state.name, name).into());
+        }
+        let slots: Vec<&str> = state.buffers.iter().map(|(slot, _)| slot.as_str()).collect();
+        if state.step > 0 && slots != self.names {
+            return Err(format!("{} state needs buffers {:?}, got {:?}", name, self.names, slots).into());
+        }
+
+        self.buffers = vec![Vec::new(); self.names.len()];
+        for (buffers, (_, matrices)) in self.buffers.iter_mut().zip(&state.buffers) {
+            *buffers = matrices.iter().map(|m| m.cast()).collect();
+        }
+        self.step = state.step;
+        Ok(state.learning_rate)
+    }
+}
+
+/// Element-wise update of one parameter and its state buffers
+fn update<T: Float, const N: usize>(param: &mut Param<T>, buffers: [&mut Matrix<T>; N], mut f: impl FnMut(&mut T, T, [&mut T; N])) {
+    let mut buffers = buffers.map(|b| b.data.iter_mut());
+    for (w, &g) in param.value.data.iter_mut().zip(&param.grad.data) {
+        let state = buffers.each_mut().map(|b| b.next().unwrap());
+        f(w, g, state);
+    }
+}
+
+/// Stochastic gradient descent with optional (Nesterov) momentum
+///
+/// With momentum μ: v = μ v + g, then w -= lr * v, or w -= lr * (g + μ v) for Nesterov.
+/// With zero momentum this is plain gradient descent, the same update as `Sequential::train_step`.
+#[derive(Debug, Clone)]
+pub struct Sgd<T = f64> {
+    pub learning_rate: f64,
+    pub momentum: f64,
+    pub nesterov: bool,
+    slots: Slots<T>,
+}
+
+impl<T: Float> Sgd<T> {
+    pub fn new(learning_rate: f64) -> Self {
+        Sgd::with_momentum(learning_rate, 0.0, false)
+    }
+
+    pub fn with_momentum(learning_rate: f64, momentum: f64, nesterov: bool) -> Self {
+        Sgd { learning_rate, momentum, nesterov, slots: Slots::new(&["velocity"]) }
+    }
+}
+
+impl<T: Float> Optimizer<T> for Sgd<T> {
+    fn name(&self) -> &'static str {
+        "SGD"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        self.slots.prepare(params);
+        let (lr, mu, nesterov) = (T::from_f64(self.learning_rate), T::from_f64(self.momentum), self.nesterov);
+        for (param, velocity) in params.iter_mut().zip(self.slots.buffers[0].iter_mut()) {
+            update(param, [velocity], |w, g, [v]| {
+                if mu == T::ZERO {
+                    *w -= lr * g;
+                    return;
+                }
+                *v = mu * *v + g;
+                *w -= lr * if nesterov { g + mu * *v } else { *v };
+            });
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.slots.state(Optimizer::<T>::name(self), self.learning_rate)
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        self.learning_rate = self.slots.load(Optimizer::<T>::name(self), state)?;
+        Ok(())
+    }
+}
+
+/// Adam update of one parameter; `decay` > 0 adds AdamW's decoupled weight decay
+#[allow(clippy::too_many_arguments)]
+fn adam_step<T: Float>(param: &mut Param<T>, m: &mut Matrix<T>, v: &mut Matrix<T>, step: u64, learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64, decay: f64) {
+    // Bias corrections for the zero-initialised moments
+    let correction1 = T::from_f64(1.0 - beta1.powi(step as i32));
+    let correction2 = T::from_f64(1.0 - beta2.powi(step as i32));
+    let (lr, b1, b2, eps) = (T::from_f64(learning_rate), T::from_f64(beta1), T::from_f64(beta2), T::from_f64(epsilon));
+    let shrink = T::from_f64(1.0 - learning_rate * decay);
+    update(param, [m, v], |w, g, [m, v]| {
+        *m = b1 * *m + (T::ONE - b1) * g;
+        *v = b2 * *v + (T::ONE - b2) * g * g;
+        let m_hat = *m / correction1;
+        let v_hat = *v / correction2;
+        *w = *w * shrink - lr * m_hat / (v_hat.sqrt() + eps);
+    });
+}
+
+/// Adam (Kingma & Ba): per-element steps from bias-corrected first and second moment estimates
+#[derive(Debug, Clone)]
+pub struct Adam<T = f64> {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    slots: Slots<T>,
+}
+
+impl<T: Float> Adam<T> {
+    /// Adam with the usual betas (0.9, 0.999) and epsilon 1e-8
+    pub fn new(learning_rate: f64) -> Self {
+        Adam { learning_rate, beta1: 0.9, beta2: 0.999, epsilon: 1e-8, slots: Slots::new(&["m", "v"]) }
+    }
+}
+
+impl<T: Float> Optimizer<T> for Adam<T> {
+    fn name(&self) -> &'static str {
+        "Adam"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        self.slots.prepare(params);
+        let [m, v] = &mut self.slots.buffers[..] else { unreachable!() };
+        for ((param, m), v) in params.iter_mut().zip(m.iter_mut()).zip(v.iter_mut()) {
+            adam_step(param, m, v, self.slots.step, self.learning_rate, self.beta1, self.beta2, self.epsilon, 0.0);
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.slots.state(Optimizer::<T>::name(self), self.learning_rate)
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        self.learning_rate = self.slots.load(Optimizer::<T>::name(self), state)?;
+        Ok(())
+    }
+}
+
+/// AdamW (Loshchilov & Hutter): Adam with weight decay applied to the weights directly,
+/// w -= lr * weight_decay * w, instead of being added to the gradient
+#[derive(Debug, Clone)]
+pub struct AdamW<T = f64> {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    pub weight_decay: f64,
+    slots: Slots<T>,
+}
+
+impl<T: Float> AdamW<T> {
+    /// AdamW with the usual betas (0.9, 0.999) and epsilon 1e-8
+    pub fn new(learning_rate: f64, weight_decay: f64) -> Self {
+        AdamW { learning_rate, beta1: 0.9, beta2: 0.999, epsilon: 1e-8, weight_decay, slots: Slots::new(&["m", "v"]) }
+    }
+}
+
+impl<T: Float> Optimizer<T> for AdamW<T> {
+    fn name(&self) -> &'static str {
+        "AdamW"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        self.slots.prepare(params);
+        let [m, v] = &mut self.slots.buffers[..] else { unreachable!() };
+        for ((param, m), v) in params.iter_mut().zip(m.iter_mut()).zip(v.iter_mut()) {
+            adam_step(param, m, v, self.slots.step, self.learning_rate, self.beta1, self.beta2, self.epsilon, self.weight_decay);
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.slots.state(Optimizer::<T>::name(self), self.learning_rate)
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        self.learning_rate = self.slots.load(Optimizer::<T>::name(self), state)?;
+        Ok(())
+    }
+}
+
+/// RMSprop: steps scaled by a running RMS of recent gradients
+/// s = alpha s + (1 - alpha) g², w -= lr g / (sqrt(s) + epsilon)
+#[derive(Debug, Clone)]
+pub struct RmsProp<T = f64> {
+    pub learning_rate: f64,
+    pub alpha: f64,
+    pub epsilon: f64,
+    slots: Slots<T>,
+}
+
+impl<T: Float> RmsProp<T> {
+    /// RMSprop with smoothing 0.99 and epsilon 1e-8
+    pub fn new(learning_rate: f64) -> Self {
+        RmsProp { learning_rate, alpha: 0.99, epsilon: 1e-8, slots: Slots::new(&["square_average"]) }
+    }
+}
+
+impl<T: Float> Optimizer<T> for RmsProp<T> {
+    fn name(&self) -> &'static str {
+        "RMSprop"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        self.slots.prepare(params);
+        let (lr, alpha, eps) = (T::from_f64(self.learning_rate), T::from_f64(self.alpha), T::from_f64(self.epsilon));
+        for (param, square_average) in params.iter_mut().zip(self.slots.buffers[0].iter_mut()) {
+            update(param, [square_average], |w, g, [s]| {
+                *s = alpha * *s + (T::ONE - alpha) * g * g;
+                *w -= lr * g / (s.sqrt() + eps);
+            });
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.slots.state(Optimizer::<T>::name(self), self.learning_rate)
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        self.learning_rate = self.slots.load(Optimizer::<T>::name(self), state)?;
+        Ok(())
+    }
+}
+
+/// Adagrad: steps scaled by the root of the sum of all past squared gradients
+/// s += g², w -= lr g / (sqrt(s) + epsilon)
+#[derive(Debug, Clone)]
+pub struct Adagrad<T = f64> {
+    pub learning_rate: f64,
+    pub epsilon: f64,
+    slots: Slots<T>,
+}
+
+impl<T: Float> Adagrad<T> {
+    /// Adagrad with epsilon 1e-10
+    pub fn new(learning_rate: f64) -> Self {
+        Adagrad { learning_rate, epsilon: 1e-10, slots: Slots::new(&["sum_of_squares"]) }
+    }
+}
+
+impl<T: Float> Optimizer<T> for Adagrad<T> {
+    fn name(&self) -> &'static str {
+        "Adagrad"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        self.slots.prepare(params);
+        let (lr, eps) = (T::from_f64(self.learning_rate), T::from_f64(self.epsilon));
+        for (param, sum_of_squares) in params.iter_mut().zip(self.slots.buffers[0].iter_mut()) {
+            update(param, [sum_of_squares], |w, g, [s]| {
+                *s += g * g;
+                *w -= lr * g / (s.sqrt() + eps);
+            });
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.slots.state(Optimizer::<T>::name(self), self.learning_rate)
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        self.learning_rate = self.slots.load(Optimizer::<T>::name(self), state)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::iter::Sum;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+
+// Kernels run on a global pool of worker threads, started on first use and grown
+// to `num_threads() - 1` workers; the calling thread always does a share of the
+// work itself. A call hands the workers closures that borrow its operands and
+// waits for all of them before returning, like `std::thread::scope` but without
+// creating threads. The global settings below decide how many threads a call may
+// use and how much work it takes to use more than one.
+
+/// 0 means "use every available core"
+static NUM_THREADS: AtomicUsize = AtomicUsize::new(0);
+static MIN_PARALLEL_WORK: AtomicUsize = AtomicUsize::new(DEFAULT_MIN_PARALLEL_WORK);
+
+/// Default amount of work (elements touched or multiply-adds) below which kernels stay on one thread
+/// Handing a chunk to a worker and waiting for it costs roughly as much as this many cheap floating-point operations
+pub const DEFAULT_MIN_PARALLEL_WORK: usize = 1 << 16;
+
+/// Partial results of reductions are formed over blocks of this many elements,
+/// so the summation order (and the result) does not depend on the thread count
+const REDUCE_BLOCK: usize = 4096;
+
+/// Set the number of threads matrix kernels may use; 0 restores the default of one per core
+pub fn set_num_threads(threads: usize) {
+    NUM_THREADS.store(threads, Ordering::Relaxed);
+}
+
+/// Number of threads matrix kernels may use
+pub fn num_threads() -> usize {
+    match NUM_THREADS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Set the amount of work below which kernels run single-threaded
+pub fn set_min_parallel_work(work: usize) {
+    MIN_PARALLEL_WORK.store(work.max(1), Ordering::Relaxed);
+}
+
+pub fn min_parallel_work() -> usize {
+    MIN_PARALLEL_WORK.load(Ordering::Relaxed)
+}
+
+/// Threads worth using for `work` units: one per `min_parallel_work`, capped at `num_threads`
+fn threads_for(work: usize) -> usize {
+    let by_work = work / min_parallel_work();
+    // Querying the core count reads cgroup files on Linux; small calls skip it.
+    // A kernel called from inside a worker runs there: waiting for other workers
+    // from a worker could leave every one of them waiting.
+    if by_work <= 1 || IS_WORKER.with(Cell::get) {
+        return 1;
+    }
+    num_threads().min(by_work)
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A kernel's share of a call, free to borrow from the caller
+type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+thread_local! {
+    static IS_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+struct Pool {
+    queue: Mutex<VecDeque<Job>>,
+    job_ready: Condvar,
+    /// Number of workers started so far; the pool never shrinks
+    workers: Mutex<usize>,
+}
+
+fn pool() -> &'static Pool {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(|| Pool { queue: Mutex::new(VecDeque::new()), job_ready: Condvar::new(), workers: Mutex::new(0) })
+}
+
+impl Pool {
+    /// Start workers until there are at least `count`
+    fn reserve(&'static self, count: usize) {
+        let mut workers = self.workers.lock().unwrap();
+        while *workers < count {
+            thread::Builder::new()
+                .name(format!("ecgnn-worker-{}", *workers))
+                .spawn(move || self.work())
+                .unwrap_or_else(|e| panic!("Could not start a matrix kernel worker thread: {}", e));
+            *workers += 1;
+        }
+    }
+
+    fn submit(&self, job: Job) {
+        self.queue.lock().unwrap().push_back(job);
+        self.job_ready.notify_one();
+    }
+
+    fn work(&self) {
+        IS_WORKER.with(|is_worker| is_worker.set(true));
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    match queue.pop_front() {
+                        Some(job) => break job,
+                        None => queue = self.job_ready.wait(queue).unwrap(),
+                    }
+                }
+            };
+            // Jobs catch their own panics, so a worker lives as long as the process
+            job();
+        }
+    }
+}
+
+/// Counts the outstanding tasks of one call and keeps the first panic among them
+struct Latch {
+    state: Mutex<(usize, Option<Box<dyn Any + Send>>)>,
+    done: Condvar,
+}
+
+impl Latch {
+    fn finish(&self, panic: Option<Box<dyn Any + Send>>) {
+        let mut state = self.state.lock().unwrap();
+        state.0 -= 1;
+        if state.1.is_none() {
+            state.1 = panic;
+        }
+        if state.0 == 0 {
+            self.done.notify_all();
+        }
+    }
+
+    /// Block until every task has finished; returns the first panic, if any
+    fn wait(&self) -> Option<Box<dyn Any + Send>> {
+        let mut state = self.state.lock().unwrap();
+        while state.0 > 0 {
+            state = self.done.wait(state).unwrap();
+        }
+        state.1.take()
+    }
+}
+
+/// Run the first task on the calling thread and the rest on the pool, returning once all have finished
+///
+/// A panic in any task is re-raised here after the others are done.
+fn run_tasks(mut tasks: Vec<Task<'_>>) {
+    if tasks.is_empty() {
+        return;
+    }
+    let first = tasks.remove(0);
+    let pool = pool();
+    pool.reserve(tasks.len());
+    let latch = Arc::new(Latch { state: Mutex::new((tasks.len(), None)), done: Condvar::new() });
+    for task in tasks {
+        let latch = Arc::clone(&latch);
+        let job: Task<'_> = Box::new(move || latch.finish(panic::catch_unwind(AssertUnwindSafe(task)).err()));
+        // SAFETY: only the lifetime is changed. The latch is waited on below before this
+        // function returns, also when `first` panics, so everything the task borrows
+        // outlives its run; the task itself is consumed before `finish` is called.
+        let job = unsafe { std::mem::transmute::<Task<'_>, Job>(job) };
+        pool.submit(job);
+    }
+    let first_panic = panic::catch_unwind(AssertUnwindSafe(first)).err();
+    let task_panic = latch.wait();
+    if let Some(payload) = first_panic.or(task_panic) {
+        panic::resume_unwind(payload);
+    }
+}
+
+/// Run `f(offset, chunk)` over consecutive chunks of `data`, in parallel when there is enough work
+///
+/// # Arguments
+/// * `data` - Output buffer to split
+/// * `granularity` - Chunk lengths (except the last) are multiples of this, e.g. a row length
+/// * `work` - Estimated cost of the whole call, compared against `min_parallel_work`
+/// * `f` - Called with the offset of each chunk in `data` and the chunk itself
+pub fn for_each_chunk_mut<T, F>(data: &mut [T], granularity: usize, work: usize, f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    let granularity = granularity.max(1);
+    let units = data.len().div_ceil(granularity);
+    let threads = threads_for(work).min(units);
+    if threads <= 1 {
+        f(0, data);
+        return;
+    }
+
+    let chunk_len = units.div_ceil(threads) * granularity;
+    let f = &f;
+    // The calling thread takes the first chunk instead of idling
+    run_tasks(data.chunks_mut(chunk_len).enumerate().map(|(i, chunk)| Box::new(move || f(i * chunk_len, chunk)) as Task<'_>).collect());
+}
+
+/// Apply `block(range)` to consecutive blocks of `block_size` items and return the results in order
+///
+/// Block boundaries depend only on `len` and `block_size`, never on the thread count.
+pub fn map_blocks<T, F>(len: usize, block_size: usize, work: usize, block: F) -> Vec<T>
+where
+    T: Send + Default + Clone,
+    F: Fn(Range<usize>) -> T + Sync,
+{
+    let block_size = block_size.max(1);
+    let mut results = vec![T::default(); len.div_ceil(block_size)];
+    for_each_chunk_mut(&mut results, 1, work, |offset, chunk| {
+        for (i, result) in chunk.iter_mut().enumerate() {
+            let start = (offset + i) * block_size;
+            *result = block(start..(start + block_size).min(len));
+        }
+    });
+    results
+}
+
+/// Elementwise `f(x)` into a new vector
+pub fn map<T, F>(input: &[T], f: F) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync,
+    F: Fn(T) -> T + Sync,
+{
+    let mut output = vec![T::default(); input.len()];
+    for_each_chunk_mut(&mut output, 1, input.len(), |offset, chunk| {
+        for (out, &x) in chunk.iter_mut().zip(&input[offset..]) {
+            *out = f(x);
+        }
+    });
+    output
+}
+
+/// Elementwise `f(x, y)` of two equally long slices into a new vector
+pub fn zip_map<T, F>(a: &[T], b: &[T], f: F) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync,
+    F: Fn(T, T) -> T + Sync,
+{
+    let len = a.len().min(b.len());
+    let mut output = vec![T::default(); len];
+    for_each_chunk_mut(&mut output, 1, len, |offset, chunk| {
+        for ((out, &x), &y) in chunk.iter_mut().zip(&a[offset..]).zip(&b[offset..]) {
+            *out = f(x, y);
+        }
+    });
+    output
+}
+
+/// Elementwise in-place update `f(&mut x, y)`
+pub fn zip_apply<T, F>(a: &mut [T], b: &[T], f: F)
+where
+    T: Copy + Send + Sync,
+    F: Fn(&mut T, T) + Sync,
+{
+    let work = a.len();
+    for_each_chunk_mut(a, 1, work, |offset, chunk| {
+        for (x, &y) in chunk.iter_mut().zip(&b[offset..]) {
+            f(x, y);
+        }
+    });
+}
+
+/// Like `map`, but `kernel(input_chunk, output_chunk)` handles a whole chunk at once (e.g. a SIMD kernel)
+pub fn map_chunks<T, F>(input: &[T], kernel: F) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync,
+    F: Fn(&[T], &mut [T]) + Sync,
+{
+    let mut output = vec![T::default(); input.len()];
+    for_each_chunk_mut(&mut output, 1, input.len(), |offset, chunk| {
+        kernel(&input[offset..offset + chunk.len()], chunk);
+    });
+    output
+}
+
+/// Like `zip_map`, but `kernel(a_chunk, b_chunk, output_chunk)` handles a whole chunk at once
+pub fn zip_map_chunks<T, F>(a: &[T], b: &[T], kernel: F) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync,
+    F: Fn(&[T], &[T], &mut [T]) + Sync,
+{
+    let len = a.len().min(b.len());
+    let mut output = vec![T::default(); len];
+    for_each_chunk_mut(&mut output, 1, len, |offset, chunk| {
+        let range = offset..offset + chunk.len();
+        kernel(&a[range.clone()], &b[range], chunk);
+    });
+    output
+}
+
+/// Like `zip_apply`, but `kernel(a_chunk, b_chunk)` updates a whole chunk of `a` at once
+pub fn zip_apply_chunks<T, F>(a: &mut [T], b: &[T], kernel: F)
+where
+    T: Send + Sync,
+    F: Fn(&mut [T], &[T]) + Sync,
+{
+    let work = a.len();
+    for_each_chunk_mut(a, 1, work, |offset, chunk| {
+        let len = chunk.len();
+        kernel(chunk, &b[offset..offset + len]);
+    });
+}
+
+/// Sum of `block_sum(range)` over fixed blocks covering `0..len`, added up in block order
+pub fn sum_blocks<T, F>(len: usize, block_sum: F) -> T
+where
+    T: Copy + Default + Send + Sum,
+    F: Fn(Range<usize>) -> T + Sync,
+{
+    map_blocks(len, REDUCE_BLOCK, len, block_sum).into_iter().sum()
+}
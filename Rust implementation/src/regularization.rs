@@ -0,0 +1,69 @@
+use crate::float::Float;
+use crate::layers::Param;
+
+/// Weight penalties and constraints applied by `Sequential` during training
+///
+/// Only parameters named "weights" are regularised; biases are left alone.
+/// All three are off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Regularization {
+    /// Coefficient of the L1 penalty l1 * sum(|w|), which pushes weights to exactly zero
+    pub l1: f64,
+    /// Coefficient of the L2 penalty (l2 / 2) * sum(w²), whose gradient l2 * w is classic weight decay
+    pub l2: f64,
+    /// Largest allowed L2 norm of the incoming weights of any unit (a column of a weight matrix),
+    /// enforced after every update
+    pub max_norm: Option<f64>,
+}
+
+fn is_regularised<T>(param: &Param<T>) -> bool {
+    param.name == "weights"
+}
+
+impl Regularization {
+    /// Value added to the loss
+    pub fn penalty<T: Float>(&self, params: &[&Param<T>]) -> T {
+        if self.l1 == 0.0 && self.l2 == 0.0 {
+            return T::ZERO;
+        }
+        let (l1, half_l2) = (T::from_f64(self.l1), T::from_f64(self.l2 / 2.0));
+        params.iter().filter(|p| is_regularised(p))
+            .flat_map(|p| p.value.data.iter())
+            .map(|&w| l1 * w.abs() + half_l2 * w * w)
+            .sum()
+    }
+
+    /// Add the gradient of `penalty` to every regularised parameter's `grad`
+    ///
+    /// The L1 subgradient at exactly zero is taken as zero.
+    pub fn add_gradients<T: Float>(&self, params: &mut [&mut Param<T>]) {
+        if self.l1 == 0.0 && self.l2 == 0.0 {
+            return;
+        }
+        let (l1, l2) = (T::from_f64(self.l1), T::from_f64(self.l2));
+        for param in params.iter_mut().filter(|p| is_regularised(p)) {
+            for (g, &w) in param.grad.data.iter_mut().zip(&param.value.data) {
+                let sign = if w > T::ZERO { T::ONE } else if w < T::ZERO { -T::ONE } else { T::ZERO };
+                *g += l1 * sign + l2 * w;
+            }
+        }
+    }
+
+    /// Rescale every column of every regularised parameter whose norm exceeds `max_norm`
+    pub fn apply_max_norm<T: Float>(&self, params: &mut [&mut Param<T>]) {
+        let Some(max_norm) = self.max_norm else { return };
+        let max_norm = T::from_f64(max_norm);
+        for param in params.iter_mut().filter(|p| is_regularised(p)) {
+            let weights = &mut param.value;
+            for j in 0..weights.cols {
+                let norm = weights.col(j).map(|&w| w * w).sum::<T>().sqrt();
+                if norm > max_norm {
+                    let scale = max_norm / norm;
+                    for i in 0..weights.rows {
+                        weights[(i, j)] *= scale;
+                    }
+                }
+            }
+        }
+    }
+}
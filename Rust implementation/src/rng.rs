@@ -0,0 +1,86 @@
+/// Small seeded pseudo-random number generator (xoshiro256**)
+///
+/// The crate has no external dependencies, so everything that needs
+/// randomness (synthetic data, shuffling, augmentation) draws from this
+/// generator. The same seed always produces the same sequence.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Create a generator from a 64-bit seed
+    /// The seed is expanded with SplitMix64 so that nearby seeds give unrelated streams
+    pub fn new(seed: u64) -> Self {
+        let mut sm = seed;
+        let mut state = [0u64; 4];
+        for slot in state.iter_mut() {
+            *slot = splitmix64(&mut sm);
+        }
+        Rng { state }
+    }
+
+    /// Next raw 64-bit value
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+
+        result
+    }
+
+    /// Uniform float in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Uniform float in [low, high)
+    pub fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// Normally distributed float (Box-Muller transform)
+    pub fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+        // 1 - u keeps the argument of ln strictly positive
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        mean + std_dev * z
+    }
+
+    /// Uniform integer in [0, n)
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        // Lemire's multiply-shift; the bias is negligible for the sizes used here
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Returns true with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Shuffle a slice in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,476 @@
+use crate::float::Float;
+use crate::layers::Param;
+use crate::optim::{Optimizer, OptimizerState, ScheduleState};
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// When a schedule moves forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    /// After every optimizer step (mini-batch)
+    Step,
+    /// At the end of every epoch
+    Epoch,
+}
+
+/// Learning rate as a function of training progress
+pub trait Scheduler {
+    /// Short type name used when logging the schedule
+    fn name(&self) -> &'static str;
+
+    /// Whether `advance` is meant to be called per step or per epoch
+    fn interval(&self) -> Interval;
+
+    /// Learning rate for the current step or epoch
+    fn learning_rate(&self) -> f64;
+
+    /// Move to the next step or epoch
+    ///
+    /// `metric` is the latest validation metric; only `ReduceOnPlateau` uses it.
+    fn advance(&mut self, metric: Option<f64>);
+
+    /// Progress through the schedule, saved with the optimizer state by `Scheduled`
+    fn state(&self) -> ScheduleState;
+
+    /// Continue from a position saved by `state`
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>>;
+}
+
+fn schedule_state(name: &str, values: &[(&str, f64)]) -> ScheduleState {
+    ScheduleState { name: name.to_string(), values: values.iter().map(|&(key, value)| (key.to_string(), value)).collect() }
+}
+
+/// Counter saved under `key`, after checking the state belongs to a `name` schedule
+fn load_count(state: &ScheduleState, name: &str, key: &str) -> Result<usize, Box<dyn Error>> {
+    if state.name != name {
+        return Err(format!("Cannot load {} schedule state into a {} schedule", state.name, name).into());
+    }
+    Ok(state.value(key)? as usize)
+}
+
+/// Multiply the learning rate by `gamma` every `step_size` epochs
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepDecay {
+    pub initial: f64,
+    pub step_size: usize,
+    pub gamma: f64,
+    epoch: usize,
+}
+
+impl StepDecay {
+    pub fn new(initial: f64, step_size: usize, gamma: f64) -> Self {
+        if step_size == 0 {
+            panic!("StepDecay step size must be at least 1");
+        }
+        StepDecay { initial, step_size, gamma, epoch: 0 }
+    }
+}
+
+impl Scheduler for StepDecay {
+    fn name(&self) -> &'static str {
+        "StepDecay"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Epoch
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.initial * self.gamma.powi((self.epoch / self.step_size) as i32)
+    }
+
+    fn advance(&mut self, _metric: Option<f64>) {
+        self.epoch += 1;
+    }
+
+    fn state(&self) -> ScheduleState {
+        schedule_state(self.name(), &[("epoch", self.epoch as f64)])
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.epoch = load_count(state, self.name(), "epoch")?;
+        Ok(())
+    }
+}
+
+/// Multiply the learning rate by `gamma` every epoch
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exponential {
+    pub initial: f64,
+    pub gamma: f64,
+    epoch: usize,
+}
+
+impl Exponential {
+    pub fn new(initial: f64, gamma: f64) -> Self {
+        Exponential { initial, gamma, epoch: 0 }
+    }
+}
+
+impl Scheduler for Exponential {
+    fn name(&self) -> &'static str {
+        "Exponential"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Epoch
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.initial * self.gamma.powi(self.epoch as i32)
+    }
+
+    fn advance(&mut self, _metric: Option<f64>) {
+        self.epoch += 1;
+    }
+
+    fn state(&self) -> ScheduleState {
+        schedule_state(self.name(), &[("epoch", self.epoch as f64)])
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.epoch = load_count(state, self.name(), "epoch")?;
+        Ok(())
+    }
+}
+
+/// Cosine annealing with warm restarts (SGDR, Loshchilov & Hutter), per step
+///
+/// The rate follows half a cosine from `max_lr` down to `min_lr` over a cycle of
+/// `first_cycle` steps, then jumps back to `max_lr`; each cycle is `cycle_multiplier` times
+/// longer than the one before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CosineWarmRestarts {
+    pub max_lr: f64,
+    pub min_lr: f64,
+    pub first_cycle: usize,
+    pub cycle_multiplier: usize,
+    /// Position within the current cycle, and that cycle's length
+    position: usize,
+    cycle: usize,
+}
+
+impl CosineWarmRestarts {
+    pub fn new(max_lr: f64, min_lr: f64, first_cycle: usize, cycle_multiplier: usize) -> Self {
+        if first_cycle == 0 || cycle_multiplier == 0 {
+            panic!("CosineWarmRestarts needs a first cycle and multiplier of at least 1");
+        }
+        CosineWarmRestarts { max_lr, min_lr, first_cycle, cycle_multiplier, position: 0, cycle: first_cycle }
+    }
+}
+
+impl Scheduler for CosineWarmRestarts {
+    fn name(&self) -> &'static str {
+        "CosineWarmRestarts"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Step
+    }
+
+    fn learning_rate(&self) -> f64 {
+        let progress = self.position as f64 / self.cycle as f64;
+        self.min_lr + (self.max_lr - self.min_lr) * (1.0 + (PI * progress).cos()) / 2.0
+    }
+
+    fn advance(&mut self, _metric: Option<f64>) {
+        self.position += 1;
+        if self.position == self.cycle {
+            self.position = 0;
+            self.cycle *= self.cycle_multiplier;
+        }
+    }
+
+    fn state(&self) -> ScheduleState {
+        schedule_state(self.name(), &[("position", self.position as f64), ("cycle", self.cycle as f64)])
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.position = load_count(state, self.name(), "position")?;
+        self.cycle = load_count(state, self.name(), "cycle")?;
+        Ok(())
+    }
+}
+
+/// Linear ramp from `start_factor * target` to `target` over `warmup_steps` steps, then constant
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearWarmup {
+    pub target: f64,
+    pub warmup_steps: usize,
+    pub start_factor: f64,
+    step: usize,
+}
+
+impl LinearWarmup {
+    pub fn new(target: f64, warmup_steps: usize, start_factor: f64) -> Self {
+        LinearWarmup { target, warmup_steps, start_factor, step: 0 }
+    }
+}
+
+impl Scheduler for LinearWarmup {
+    fn name(&self) -> &'static str {
+        "LinearWarmup"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Step
+    }
+
+    fn learning_rate(&self) -> f64 {
+        if self.step >= self.warmup_steps {
+            return self.target;
+        }
+        let progress = self.step as f64 / self.warmup_steps as f64;
+        self.target * (self.start_factor + (1.0 - self.start_factor) * progress)
+    }
+
+    fn advance(&mut self, _metric: Option<f64>) {
+        self.step += 1;
+    }
+
+    fn state(&self) -> ScheduleState {
+        schedule_state(self.name(), &[("step", self.step as f64)])
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.step = load_count(state, self.name(), "step")?;
+        Ok(())
+    }
+}
+
+/// One-cycle policy (Smith & Topin), per step
+///
+/// Cosine rise from `max_lr / div_factor` to `max_lr` over the first `warmup_fraction`
+/// of `total_steps`, then cosine decay to `max_lr / (div_factor * final_div_factor)`.
+/// The rate stays at that minimum after `total_steps`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OneCycle {
+    pub max_lr: f64,
+    pub total_steps: usize,
+    pub warmup_fraction: f64,
+    pub div_factor: f64,
+    pub final_div_factor: f64,
+    step: usize,
+}
+
+impl OneCycle {
+    /// One cycle with 30% warm-up, starting at max_lr / 25 and ending at max_lr / 250000
+    pub fn new(max_lr: f64, total_steps: usize) -> Self {
+        OneCycle { max_lr, total_steps, warmup_fraction: 0.3, div_factor: 25.0, final_div_factor: 1e4, step: 0 }
+    }
+}
+
+/// Half-cosine from `start` (progress 0) to `end` (progress 1)
+fn cosine_between(start: f64, end: f64, progress: f64) -> f64 {
+    end + (start - end) * (1.0 + (PI * progress.clamp(0.0, 1.0)).cos()) / 2.0
+}
+
+impl Scheduler for OneCycle {
+    fn name(&self) -> &'static str {
+        "OneCycle"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Step
+    }
+
+    fn learning_rate(&self) -> f64 {
+        let initial = self.max_lr / self.div_factor;
+        let last = self.total_steps.saturating_sub(1).max(1) as f64;
+        let peak = (self.warmup_fraction * last).max(1.0);
+        let step = self.step as f64;
+        if step < peak {
+            cosine_between(initial, self.max_lr, step / peak)
+        } else {
+            cosine_between(self.max_lr, initial / self.final_div_factor, (step - peak) / (last - peak).max(1.0))
+        }
+    }
+
+    fn advance(&mut self, _metric: Option<f64>) {
+        self.step += 1;
+    }
+
+    fn state(&self) -> ScheduleState {
+        schedule_state(self.name(), &[("step", self.step as f64)])
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.step = load_count(state, self.name(), "step")?;
+        Ok(())
+    }
+}
+
+/// Whether a smaller or a larger validation metric is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// e.g. validation loss
+    Min,
+    /// e.g. validation accuracy or F1
+    Max,
+}
+
+/// Multiply the learning rate by `factor` when the validation metric stops improving
+///
+/// After `patience` epochs without a relative improvement of at least `threshold`
+/// the rate is reduced (never below `min_lr`), then `cooldown` epochs pass before
+/// stagnation is counted again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReduceOnPlateau {
+    pub mode: Mode,
+    pub factor: f64,
+    pub patience: usize,
+    pub threshold: f64,
+    pub cooldown: usize,
+    pub min_lr: f64,
+    learning_rate: f64,
+    best: Option<f64>,
+    bad_epochs: usize,
+    cooldown_left: usize,
+}
+
+impl ReduceOnPlateau {
+    /// Halve the rate after 5 epochs without a 0.01% improvement, no cooldown
+    pub fn new(initial: f64, mode: Mode) -> Self {
+        ReduceOnPlateau {
+            mode,
+            factor: 0.5,
+            patience: 5,
+            threshold: 1e-4,
+            cooldown: 0,
+            min_lr: 0.0,
+            learning_rate: initial,
+            best: None,
+            bad_epochs: 0,
+            cooldown_left: 0,
+        }
+    }
+
+    fn improves(&self, metric: f64, best: f64) -> bool {
+        match self.mode {
+            Mode::Min => metric < best - self.threshold * best.abs(),
+            Mode::Max => metric > best + self.threshold * best.abs(),
+        }
+    }
+}
+
+impl Scheduler for ReduceOnPlateau {
+    fn name(&self) -> &'static str {
+        "ReduceOnPlateau"
+    }
+
+    fn interval(&self) -> Interval {
+        Interval::Epoch
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn advance(&mut self, metric: Option<f64>) {
+        let metric = metric.unwrap_or_else(|| panic!("ReduceOnPlateau needs a validation metric every epoch"));
+        match self.best {
+            Some(best) if !self.improves(metric, best) => self.bad_epochs += 1,
+            _ => {
+                self.best = Some(metric);
+                self.bad_epochs = 0;
+            }
+        }
+
+        if self.cooldown_left > 0 {
+            self.cooldown_left -= 1;
+            self.bad_epochs = 0;
+        } else if self.bad_epochs > self.patience {
+            self.learning_rate = (self.learning_rate * self.factor).max(self.min_lr);
+            self.cooldown_left = self.cooldown;
+            self.bad_epochs = 0;
+        }
+    }
+
+    /// `best` is left out until the first metric has been seen
+    fn state(&self) -> ScheduleState {
+        let mut state = schedule_state(self.name(), &[
+            ("learning_rate", self.learning_rate),
+            ("bad_epochs", self.bad_epochs as f64),
+            ("cooldown_left", self.cooldown_left as f64),
+        ]);
+        if let Some(best) = self.best {
+            state.values.push(("best".to_string(), best));
+        }
+        state
+    }
+
+    fn load_state(&mut self, state: &ScheduleState) -> Result<(), Box<dyn Error>> {
+        self.bad_epochs = load_count(state, self.name(), "bad_epochs")?;
+        self.cooldown_left = load_count(state, self.name(), "cooldown_left")?;
+        self.learning_rate = state.value("learning_rate")?;
+        self.best = state.value("best").ok();
+        Ok(())
+    }
+}
+
+/// An optimizer whose learning rate comes from a schedule
+///
+/// Before every step the optimizer's rate is set from the scheduler and recorded
+/// in `history`; per-step schedules then advance. Per-epoch schedules advance in
+/// `end_epoch`, which the training loop calls with the validation metric.
+/// `state` saves the schedule's position along with the optimizer's state, so training
+/// resumes at the same point of the schedule; `history` is not saved.
+pub struct Scheduled<O, S> {
+    pub optimizer: O,
+    pub scheduler: S,
+    history: Vec<f64>,
+}
+
+impl<O, S: Scheduler> Scheduled<O, S> {
+    pub fn new(optimizer: O, scheduler: S) -> Self {
+        Scheduled { optimizer, scheduler, history: Vec::new() }
+    }
+
+    /// Advance a per-epoch schedule; does nothing for per-step schedules
+    pub fn end_epoch(&mut self, metric: Option<f64>) {
+        if self.scheduler.interval() == Interval::Epoch {
+            self.scheduler.advance(metric);
+        }
+    }
+
+    /// Learning rate used by every step so far
+    pub fn history(&self) -> &[f64] {
+        &self.history
+    }
+}
+
+impl<T: Float, O: Optimizer<T>, S: Scheduler> Optimizer<T> for Scheduled<O, S> {
+    fn name(&self) -> &'static str {
+        self.optimizer.name()
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.scheduler.learning_rate()
+    }
+
+    /// Overridden by the schedule at the next step
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.optimizer.set_learning_rate(learning_rate);
+    }
+
+    fn step(&mut self, params: &mut [&mut Param<T>]) {
+        let learning_rate = self.scheduler.learning_rate();
+        self.optimizer.set_learning_rate(learning_rate);
+        self.history.push(learning_rate);
+        self.optimizer.step(params);
+        if self.scheduler.interval() == Interval::Step {
+            self.scheduler.advance(None);
+        }
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState { schedule: Some(self.scheduler.state()), ..self.optimizer.state() }
+    }
+
+    fn load_state(&mut self, state: &OptimizerState) -> Result<(), Box<dyn Error>> {
+        let schedule = state.schedule.as_ref()
+            .ok_or_else(|| format!("{} state has no schedule to load into {}", state.name, self.scheduler.name()))?;
+        self.scheduler.load_state(schedule)?;
+        self.optimizer.load_state(state)
+    }
+}
@@ -0,0 +1,324 @@
+use crate::batching::BatchConfig;
+use crate::clipping::GradientClip;
+use crate::divergence::{Divergence, DivergenceGuard, NonFinite};
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::layers::{Dense, Layer, Param, ReLU, Sigmoid, Softmax};
+use crate::loss::{sigmoid_cross_entropy, softmax_cross_entropy, Loss};
+use crate::matrix_math::Matrix;
+use crate::optim::Optimizer;
+use crate::regularization::Regularization;
+use crate::rng::Rng;
+use crate::split::select;
+use std::convert::Infallible;
+
+/// A network made of layers applied one after another
+///
+/// The one-hidden-layer network of `brains` is `Sequential::mlp_seeded(features, &[hidden], outputs, seed)`;
+/// deeper networks just list more hidden sizes, or push arbitrary layers.
+pub struct Sequential<T: Float = f64> {
+    pub layers: Vec<Box<dyn Layer<T>>>,
+    /// Weight penalties and constraints used by the training methods; none by default
+    pub regularization: Regularization,
+    /// Clipping applied to the gradients before every update; none by default
+    pub gradient_clip: Option<GradientClip>,
+}
+
+impl<T: Float> Sequential<T> {
+    pub fn new(layers: Vec<Box<dyn Layer<T>>>) -> Self {
+        Sequential { layers, regularization: Regularization::default(), gradient_clip: None }
+    }
+
+    pub fn with_regularization(mut self, regularization: Regularization) -> Self {
+        self.regularization = regularization;
+        self
+    }
+
+    pub fn with_gradient_clip(mut self, gradient_clip: GradientClip) -> Self {
+        self.gradient_clip = Some(gradient_clip);
+        self
+    }
+
+    /// Multi-layer perceptron: Dense + ReLU for every hidden size, then a Dense output layer
+    /// followed by Sigmoid for a single output or Softmax for several
+    ///
+    /// He-normal hidden layers (suited to ReLU) and a Xavier-uniform output layer,
+    /// reproducible from `seed`
+    pub fn mlp_seeded(num_features: usize, hidden_sizes: &[usize], num_outputs: usize, seed: u64) -> Self {
+        let mut initializers = vec![Initializer::HeNormal; hidden_sizes.len()];
+        initializers.push(Initializer::XavierUniform);
+        Self::mlp_with_initializers(num_features, hidden_sizes, num_outputs, &initializers, &mut Rng::new(seed))
+    }
+
+    /// `mlp_seeded` with one initializer per Dense layer (hidden layers first, then the output layer)
+    /// Biases start at zero
+    pub fn mlp_with_initializers(num_features: usize, hidden_sizes: &[usize], num_outputs: usize, initializers: &[Initializer], rng: &mut Rng) -> Self {
+        if initializers.len() != hidden_sizes.len() + 1 {
+            panic!("An MLP with {} hidden layers needs {} initializers, got {}", hidden_sizes.len(), hidden_sizes.len() + 1, initializers.len());
+        }
+        let sizes = Self::layer_sizes(num_features, hidden_sizes, num_outputs);
+        let dense = sizes.windows(2).zip(initializers)
+            .map(|(pair, &initializer)| Dense::with_initializer(pair[0], pair[1], initializer, rng))
+            .collect();
+        Self::mlp_from_dense(dense)
+    }
+
+    fn layer_sizes(num_features: usize, hidden_sizes: &[usize], num_outputs: usize) -> Vec<usize> {
+        let mut sizes = vec![num_features];
+        sizes.extend_from_slice(hidden_sizes);
+        sizes.push(num_outputs);
+        sizes
+    }
+
+    /// ReLU between consecutive Dense layers, then the output activation
+    fn mlp_from_dense(dense: Vec<Dense<T>>) -> Self {
+        let num_outputs = dense.last().map_or(0, |layer| layer.num_outputs());
+        let num_dense = dense.len();
+        let mut model = Sequential::new(Vec::new());
+        for (i, layer) in dense.into_iter().enumerate() {
+            model.push(layer);
+            if i + 1 < num_dense {
+                model.push(ReLU::new());
+            }
+        }
+        if num_outputs == 1 {
+            model.push(Sigmoid::new());
+        } else {
+            model.push(Softmax::new());
+        }
+        model
+    }
+
+    pub fn push(&mut self, layer: impl Layer<T> + 'static) {
+        self.layers.push(Box::new(layer));
+    }
+
+    /// Output of the whole network for a batch, one sample per row
+    pub fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let mut activations = input.clone();
+        for layer in self.layers.iter_mut() {
+            activations = layer.forward(&activations);
+        }
+        activations
+    }
+
+    /// Put every layer in training mode (dropout active)
+    pub fn train(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.set_training(true);
+        }
+    }
+
+    /// Put every layer in evaluation mode (dropout off), for validation and prediction
+    pub fn eval(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.set_training(false);
+        }
+    }
+
+    /// Backpropagate dL/d(output) of the last `forward` through every layer
+    /// Fills every parameter's gradient and returns dL/d(input)
+    pub fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let layer_count = self.layers.len();
+        self.backward_through(layer_count, grad_output)
+    }
+
+    /// `backward` through the first `layer_count` layers only, starting from dL/d(output of the last of them)
+    fn backward_through(&mut self, layer_count: usize, grad_output: &Matrix<T>) -> Matrix<T> {
+        let mut grad = grad_output.clone();
+        for layer in self.layers[..layer_count].iter_mut().rev() {
+            grad = layer.backward(&grad);
+        }
+        grad
+    }
+
+    /// Every trainable parameter, layer by layer
+    pub fn parameters(&self) -> Vec<&Param<T>> {
+        self.layers.iter().flat_map(|layer| layer.parameters()).collect()
+    }
+
+    pub fn parameters_mut(&mut self) -> Vec<&mut Param<T>> {
+        self.layers.iter_mut().flat_map(|layer| layer.parameters_mut()).collect()
+    }
+
+    /// Gradients of `parameters`, in the same order
+    pub fn gradients(&self) -> Vec<&Matrix<T>> {
+        self.parameters().into_iter().map(|p| &p.grad).collect()
+    }
+
+    /// Name of every parameter, in the order of `parameters`, e.g. "layer 0 Dense weights"
+    pub fn parameter_names(&self) -> Vec<String> {
+        self.layers.iter().enumerate()
+            .flat_map(|(i, layer)| layer.parameters().into_iter().map(move |param| format!("layer {} {} {}", i, layer.name(), param.name)))
+            .collect()
+    }
+
+    /// Total number of trainable values
+    pub fn num_parameters(&self) -> usize {
+        self.parameters().iter().map(|p| p.value.data.len()).sum()
+    }
+
+    /// Current regularisation penalty, the part of the training loss not due to the data
+    pub fn penalty(&self) -> T {
+        self.regularization.penalty(&self.parameters())
+    }
+
+    /// Forward pass, loss and backward pass on one batch; returns the loss and leaves the gradients in place
+    ///
+    /// Both the returned loss and the gradients include the regularisation penalty.
+    /// A final `Sigmoid` trained with binary cross-entropy, or `Softmax` with categorical
+    /// cross-entropy, is skipped in the backward pass: the gradient starts from the fused
+    /// p - t at its input (see `loss::sigmoid_cross_entropy`), which survives saturation.
+    pub fn compute_gradients(&mut self, inputs: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T], loss: Loss) -> T {
+        let predictions = self.forward(inputs);
+        let fused = match (loss, self.layers.last().map(|layer| layer.name())) {
+            (Loss::BinaryCrossEntropy, Some("Sigmoid")) => Some(sigmoid_cross_entropy(&predictions, targets, sample_weights)),
+            (Loss::CategoricalCrossEntropy, Some("Softmax")) => Some(softmax_cross_entropy(&predictions, targets, sample_weights)),
+            _ => None,
+        };
+        let value = if let Some((value, grad_logits)) = fused {
+            self.backward_through(self.layers.len() - 1, &grad_logits);
+            value
+        } else {
+            let (value, grad_predictions) = loss.evaluate(&predictions, targets, sample_weights);
+            self.backward(&grad_predictions);
+            value
+        };
+        let regularization = self.regularization;
+        regularization.add_gradients(&mut self.parameters_mut());
+        value + self.penalty()
+    }
+
+    /// One plain gradient-descent step on a batch
+    ///
+    /// # Returns
+    /// * The loss of the batch before the update
+    pub fn train_step(&mut self, inputs: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T], loss: Loss, learning_rate: T) -> T {
+        let value = self.compute_gradients(inputs, targets, sample_weights, loss);
+        self.update(|params| {
+            for param in params.iter_mut() {
+                for (w, &g) in param.value.data.iter_mut().zip(param.grad.data.iter()) {
+                    *w -= learning_rate * g;
+                }
+            }
+        });
+        value
+    }
+
+    /// `train_step` with the update made by `optimizer` instead of plain gradient descent
+    pub fn train_step_with(&mut self, inputs: &Matrix<T>, targets: &Matrix<T>, sample_weights: &[T], loss: Loss, optimizer: &mut dyn Optimizer<T>) -> T {
+        let value = self.compute_gradients(inputs, targets, sample_weights, loss);
+        self.update(|params| optimizer.step(params));
+        value
+    }
+
+    /// Clip the gradients, let `step` change the parameters, then apply the max-norm constraint
+    fn update(&mut self, step: impl FnOnce(&mut [&mut Param<T>])) {
+        let (gradient_clip, regularization) = (self.gradient_clip, self.regularization);
+        let mut params = self.parameters_mut();
+        if let Some(clip) = gradient_clip {
+            clip.apply(&mut params);
+        }
+        step(&mut params);
+        regularization.apply_max_norm(&mut params);
+    }
+
+    /// One epoch of mini-batch gradient descent: a `train_step` per batch of `batches`
+    ///
+    /// # Returns
+    /// * The weighted mean loss over the samples seen, each batch's loss taken from
+    ///   the forward pass its gradients came from (so before that batch's update)
+    #[allow(clippy::too_many_arguments)]
+    pub fn train_epoch(
+        &mut self,
+        inputs: &Matrix<T>,
+        targets: &Matrix<T>,
+        sample_weights: &[T],
+        loss: Loss,
+        batches: &BatchConfig,
+        epoch: usize,
+        learning_rate: T,
+    ) -> T {
+        let Ok(mean_loss) = self.run_epoch::<Infallible>(inputs, targets, sample_weights, batches, epoch, |model, _, x, y, w| Ok(model.train_step(x, y, w, loss, learning_rate)));
+        mean_loss
+    }
+
+    /// `train_epoch` with every batch's update made by `optimizer`
+    #[allow(clippy::too_many_arguments)]
+    pub fn train_epoch_with(
+        &mut self,
+        inputs: &Matrix<T>,
+        targets: &Matrix<T>,
+        sample_weights: &[T],
+        loss: Loss,
+        batches: &BatchConfig,
+        epoch: usize,
+        optimizer: &mut dyn Optimizer<T>,
+    ) -> T {
+        let Ok(mean_loss) = self.run_epoch::<Infallible>(inputs, targets, sample_weights, batches, epoch, |model, _, x, y, w| Ok(model.train_step_with(x, y, w, loss, optimizer)));
+        mean_loss
+    }
+
+    /// `train_epoch_with` that stops at the first NaN or infinity in the loss, the gradients
+    /// or the updated parameters
+    ///
+    /// `guard` takes a checkpoint at the start of the epoch; on divergence it either rolls
+    /// back to it or leaves the model as it is, and the error names the parameter that blew up.
+    #[allow(clippy::too_many_arguments)]
+    pub fn train_epoch_guarded(
+        &mut self,
+        inputs: &Matrix<T>,
+        targets: &Matrix<T>,
+        sample_weights: &[T],
+        loss: Loss,
+        batches: &BatchConfig,
+        epoch: usize,
+        optimizer: &mut dyn Optimizer<T>,
+        guard: &mut DivergenceGuard<T>,
+    ) -> Result<T, Divergence> {
+        guard.checkpoint(self, optimizer);
+        self.run_epoch(inputs, targets, sample_weights, batches, epoch, |model, batch, x, y, w| {
+            let value = model.compute_gradients(x, y, w, loss);
+            let names = model.parameter_names();
+            let mut problem = if value.is_finite() {
+                NonFinite::in_gradients(&names, &model.parameters())
+            } else {
+                Some(NonFinite::Loss(value.to_f64()))
+            };
+            if problem.is_none() {
+                model.update(|params| optimizer.step(params));
+                problem = NonFinite::in_values(&names, &model.parameters());
+            }
+            match problem {
+                None => Ok(value),
+                Some(source) => Err(guard.recover(model, optimizer, epoch, batch, source)),
+            }
+        })
+    }
+
+    /// Call `step` with the index and data of every batch of the epoch and average the
+    /// losses it returns, stopping at the first error
+    fn run_epoch<E>(
+        &mut self,
+        inputs: &Matrix<T>,
+        targets: &Matrix<T>,
+        sample_weights: &[T],
+        batches: &BatchConfig,
+        epoch: usize,
+        mut step: impl FnMut(&mut Self, usize, &Matrix<T>, &Matrix<T>, &[T]) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut total_loss = T::ZERO;
+        let mut total_weight = T::ZERO;
+        for (index, batch) in batches.batches(inputs.rows, epoch).into_iter().enumerate() {
+            let batch_weights = select(sample_weights, &batch);
+            let batch_loss = step(self, index, &inputs.select_rows(&batch), &targets.select_rows(&batch), &batch_weights)?;
+            let weight: T = batch_weights.iter().copied().sum();
+            total_loss += batch_loss * weight;
+            total_weight += weight;
+        }
+        if total_weight == T::ZERO {
+            panic!("Epoch has no samples to train on ({} samples, batch size {}, drop_last {})", inputs.rows, batches.batch_size, batches.drop_last);
+        }
+        Ok(total_loss / total_weight)
+    }
+}
@@ -0,0 +1,694 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+// Explicit SIMD kernels for the hot loops of `matrix_math`, `gemm` and `brains`.
+//
+// Every kernel takes the `SimdLevel` to run at, is clamped to what the CPU
+// actually supports, and has a plain scalar path that is also used on
+// non-x86_64 targets. Kernels that reorder or fuse floating-point operations
+// (dot products, GEMM tiles with FMA, exp) agree with the scalar path to a few
+// ulps rather than bit for bit; purely elementwise kernels are exact.
+
+/// Instruction set used by the kernels, in increasing order of capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SimdLevel {
+    /// Plain Rust loops, left to the compiler's auto-vectoriser
+    Scalar,
+    /// 128-bit SSE2, part of the x86_64 baseline
+    Sse2,
+    /// 256-bit AVX2 with fused multiply-add
+    Avx2Fma,
+}
+
+impl SimdLevel {
+    pub const ALL: [SimdLevel; 3] = [SimdLevel::Scalar, SimdLevel::Sse2, SimdLevel::Avx2Fma];
+}
+
+/// Best level this CPU supports, detected once at first use
+pub fn detected_level() -> SimdLevel {
+    static DETECTED: OnceLock<SimdLevel> = OnceLock::new();
+    *DETECTED.get_or_init(detect)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> SimdLevel {
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        SimdLevel::Avx2Fma
+    } else if is_x86_feature_detected!("sse2") {
+        SimdLevel::Sse2
+    } else {
+        SimdLevel::Scalar
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> SimdLevel {
+    SimdLevel::Scalar
+}
+
+/// u8::MAX means "no cap"
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Cap the level `active_level` returns (e.g. to benchmark or debug the scalar path); None removes the cap
+pub fn set_max_level(level: Option<SimdLevel>) {
+    MAX_LEVEL.store(level.map_or(u8::MAX, |l| l as u8), Ordering::Relaxed);
+}
+
+/// Level the library kernels run at: the detected level, limited by `set_max_level`
+pub fn active_level() -> SimdLevel {
+    let cap = MAX_LEVEL.load(Ordering::Relaxed);
+    SimdLevel::ALL.iter()
+        .copied()
+        .filter(|&level| level as u8 <= cap)
+        .fold(SimdLevel::Scalar, SimdLevel::max)
+        .min(detected_level())
+}
+
+/// Never run instructions the CPU lacks, whatever level was asked for
+fn supported(level: SimdLevel) -> SimdLevel {
+    level.min(detected_level())
+}
+
+/// Sum of `a[i] * b[i]` over the common length
+pub fn dot(level: SimdLevel, a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    let (a, b) = (&a[..n], &b[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a` and `b` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::dot_avx2(a, b) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a` and `b` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::dot_sse2(a, b) },
+        _ => a.iter().zip(b).map(|(x, y)| x * y).sum(),
+    }
+}
+
+/// `out[i] = a[i] + b[i]`
+pub fn add(level: SimdLevel, a: &[f64], b: &[f64], out: &mut [f64]) {
+    let n = out.len().min(a.len()).min(b.len());
+    let (a, b, out) = (&a[..n], &b[..n], &mut out[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a`, `b` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::add_avx2(a, b, out) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a`, `b` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::add_sse2(a, b, out) },
+        _ => {
+            for ((o, x), y) in out.iter_mut().zip(a).zip(b) {
+                *o = x + y;
+            }
+        }
+    }
+}
+
+/// `out[i] = a[i] * b[i]`
+pub fn mul(level: SimdLevel, a: &[f64], b: &[f64], out: &mut [f64]) {
+    let n = out.len().min(a.len()).min(b.len());
+    let (a, b, out) = (&a[..n], &b[..n], &mut out[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a`, `b` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::mul_avx2(a, b, out) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a`, `b` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::mul_sse2(a, b, out) },
+        _ => {
+            for ((o, x), y) in out.iter_mut().zip(a).zip(b) {
+                *o = x * y;
+            }
+        }
+    }
+}
+
+/// `out[i] = a[i] * scalar`
+pub fn scale(level: SimdLevel, a: &[f64], scalar: f64, out: &mut [f64]) {
+    let n = out.len().min(a.len());
+    let (a, out) = (&a[..n], &mut out[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::scale_avx2(a, scalar, out) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::scale_sse2(a, scalar, out) },
+        _ => {
+            for (o, x) in out.iter_mut().zip(a) {
+                *o = x * scalar;
+            }
+        }
+    }
+}
+
+/// `y[i] += alpha * x[i]`
+pub fn axpy(level: SimdLevel, alpha: f64, x: &[f64], y: &mut [f64]) {
+    let n = x.len().min(y.len());
+    let (x, y) = (&x[..n], &mut y[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `x` and `y` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::axpy_avx2(alpha, x, y) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `x` and `y` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::axpy_sse2(alpha, x, y) },
+        _ => {
+            for (y_i, x_i) in y.iter_mut().zip(x) {
+                *y_i += alpha * x_i;
+            }
+        }
+    }
+}
+
+/// `out[i] = max(0, a[i])`, keeping NaN and -0.0 exactly like `brains::relu`
+pub fn relu(level: SimdLevel, a: &[f64], out: &mut [f64]) {
+    let n = out.len().min(a.len());
+    let (a, out) = (&a[..n], &mut out[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::relu_avx2(a, out) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::relu_sse2(a, out) },
+        _ => {
+            for (o, &x) in out.iter_mut().zip(a) {
+                *o = if x < 0.0 { 0.0 } else { x };
+            }
+        }
+    }
+}
+
+/// `out[i] = 1 / (1 + exp(-a[i]))`
+pub fn sigmoid(level: SimdLevel, a: &[f64], out: &mut [f64]) {
+    let n = out.len().min(a.len());
+    let (a, out) = (&a[..n], &mut out[..n]);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::sigmoid_avx2(a, out) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `a` and `out` were cut to the same length
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::sigmoid_sse2(a, out) },
+        _ => {
+            for (o, &x) in out.iter_mut().zip(a) {
+                *o = 1.0 / (1.0 + (-x).exp());
+            }
+        }
+    }
+}
+
+/// GEMM register tile: `c[i * ldc + j] += sum_p a[4p + i] * b[4p + j]` for i < mr, j < nr
+///
+/// `a` is a packed 4-row sliver and `b` a packed 4-column sliver of the same depth.
+pub fn kernel_4x4(level: SimdLevel, a: &[f64], b: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+    let depth = (a.len() / 4).min(b.len() / 4);
+    check_tile(c.len(), ldc, mr, nr, 4);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `depth` fits both slivers
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::kernel_4x4_avx2(depth, a, b, c, ldc, mr, nr) },
+        // SAFETY: `supported` never exceeds the detected level, so SSE2 is available;
+        // `depth` fits both slivers
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => unsafe { x86::kernel_4x4_sse2(depth, a, b, c, ldc, mr, nr) },
+        _ => kernel_4x4_scalar(depth, a, b, c, ldc, mr, nr),
+    }
+}
+
+/// Two horizontally adjacent 4 x 4 tiles at once (`b_left` then `b_right`, nr <= 8)
+///
+/// Only AVX2 has enough registers to make this pay; other levels run two 4 x 4 tiles.
+#[allow(clippy::too_many_arguments)]
+pub fn kernel_4x8(level: SimdLevel, a: &[f64], b_left: &[f64], b_right: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+    let depth = (a.len() / 4).min(b_left.len() / 4).min(b_right.len() / 4);
+    check_tile(c.len(), ldc, mr, nr, 8);
+    match supported(level) {
+        // SAFETY: `supported` never exceeds the detected level, so AVX2 and FMA are available;
+        // `depth` fits all three slivers
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2Fma => unsafe { x86::kernel_4x8_avx2(depth, a, b_left, b_right, c, ldc, mr, nr) },
+        _ => {
+            kernel_4x4(level, a, b_left, c, ldc, mr, nr.min(4));
+            if nr > 4 {
+                kernel_4x4(level, a, b_right, &mut c[4..], ldc, mr, nr - 4);
+            }
+        }
+    }
+}
+
+/// Check a tile against its output up front, so a bad call panics with one clear message
+/// rather than an index error inside a kernel (the writes themselves go through checked slices)
+fn check_tile(len: usize, ldc: usize, mr: usize, nr: usize, max_cols: usize) {
+    if mr > 4 || nr > max_cols || (mr > 0 && nr > 0 && (mr - 1) * ldc + nr > len) {
+        panic!("GEMM tile of {}x{} does not fit an output of length {} with row stride {}", mr, nr, len, ldc);
+    }
+}
+
+fn kernel_4x4_scalar(depth: usize, a: &[f64], b: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+    let mut acc = [[0.0; 4]; 4];
+
+    for (a_column, b_row) in a.chunks_exact(4).zip(b.chunks_exact(4)).take(depth) {
+        // Fixed-length views let the compiler drop bounds checks and unroll completely
+        let a_column: &[f64; 4] = a_column.try_into().unwrap();
+        let b_row: &[f64; 4] = b_row.try_into().unwrap();
+        for (acc_row, &a_i) in acc.iter_mut().zip(a_column) {
+            for (acc_ij, &b_j) in acc_row.iter_mut().zip(b_row) {
+                *acc_ij += a_i * b_j;
+            }
+        }
+    }
+
+    for (i, acc_row) in acc.iter().enumerate().take(mr) {
+        for (c_ij, value) in c[i * ldc..i * ldc + nr].iter_mut().zip(acc_row) {
+            *c_ij += value;
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    // exp(x) = 2^n * exp(r) with n = round(x / ln 2) and |r| <= ln(2) / 2.
+    // ln 2 is split in two (Cody-Waite) so n * LN2_HI is exact, and exp(r) is
+    // its Taylor series to degree 13, whose truncation error is below 2e-16.
+    const LOG2E: f64 = std::f64::consts::LOG2_E;
+    const LN2_HI: f64 = 6.931_457_519_531_25e-1;
+    const LN2_LO: f64 = 1.428_606_820_309_417_3e-6;
+    // Beyond these exp over- or underflows; clamping keeps 2^n a normal number
+    const EXP_MIN: f64 = -708.0;
+    const EXP_MAX: f64 = 709.0;
+    /// 1/k! for k = 13 down to 2
+    const EXP_COEFFS: [f64; 12] = [
+        1.0 / 6_227_020_800.0,
+        1.0 / 479_001_600.0,
+        1.0 / 39_916_800.0,
+        1.0 / 3_628_800.0,
+        1.0 / 362_880.0,
+        1.0 / 40_320.0,
+        1.0 / 5_040.0,
+        1.0 / 720.0,
+        1.0 / 120.0,
+        1.0 / 24.0,
+        1.0 / 6.0,
+        1.0 / 2.0,
+    ];
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA.
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn hsum_avx2(v: __m256d) -> f64 {
+        let pair = _mm_add_pd(_mm256_castpd256_pd128(v), _mm256_extractf128_pd(v, 1));
+        _mm_cvtsd_f64(_mm_add_sd(pair, _mm_unpackhi_pd(pair, pair)))
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2.
+    #[target_feature(enable = "sse2")]
+    unsafe fn hsum_sse2(v: __m128d) -> f64 {
+        _mm_cvtsd_f64(_mm_add_sd(v, _mm_unpackhi_pd(v, v)))
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `b` must be at least as long as `a`.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot_avx2(a: &[f64], b: &[f64]) -> f64 {
+        let n = a.len();
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        // Four independent accumulators hide the FMA latency
+        let mut acc = [_mm256_setzero_pd(); 4];
+        let mut i = 0;
+        // SAFETY: every load reads 4 values starting below n - 3, inside both slices
+        while i + 16 <= n {
+            for (j, acc_j) in acc.iter_mut().enumerate() {
+                let x = _mm256_loadu_pd(pa.add(i + 4 * j));
+                let y = _mm256_loadu_pd(pb.add(i + 4 * j));
+                *acc_j = _mm256_fmadd_pd(x, y, *acc_j);
+            }
+            i += 16;
+        }
+        while i + 4 <= n {
+            acc[0] = _mm256_fmadd_pd(_mm256_loadu_pd(pa.add(i)), _mm256_loadu_pd(pb.add(i)), acc[0]);
+            i += 4;
+        }
+        let sum = _mm256_add_pd(_mm256_add_pd(acc[0], acc[1]), _mm256_add_pd(acc[2], acc[3]));
+        hsum_avx2(sum) + a[i..].iter().zip(&b[i..]).map(|(x, y)| x * y).sum::<f64>()
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `b` must be at least as long as `a`.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn dot_sse2(a: &[f64], b: &[f64]) -> f64 {
+        let n = a.len();
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [_mm_setzero_pd(); 4];
+        let mut i = 0;
+        // SAFETY: every load reads 2 values starting below n - 1, inside both slices
+        while i + 8 <= n {
+            for (j, acc_j) in acc.iter_mut().enumerate() {
+                let x = _mm_loadu_pd(pa.add(i + 2 * j));
+                let y = _mm_loadu_pd(pb.add(i + 2 * j));
+                *acc_j = _mm_add_pd(*acc_j, _mm_mul_pd(x, y));
+            }
+            i += 8;
+        }
+        while i + 2 <= n {
+            acc[0] = _mm_add_pd(acc[0], _mm_mul_pd(_mm_loadu_pd(pa.add(i)), _mm_loadu_pd(pb.add(i))));
+            i += 2;
+        }
+        let sum = _mm_add_pd(_mm_add_pd(acc[0], acc[1]), _mm_add_pd(acc[2], acc[3]));
+        hsum_sse2(sum) + a[i..].iter().zip(&b[i..]).map(|(x, y)| x * y).sum::<f64>()
+    }
+
+    // Elementwise kernels: full vectors first, then a scalar tail that uses
+    // the same operation, so results match the scalar path exactly
+    macro_rules! binary_kernel {
+        ($name:ident, $feature:literal, $width:expr, $load:ident, $store:ident, $op:ident, $scalar:expr) => {
+            /// # Safety
+            /// The CPU must support the kernel's target features, and `a` and `b` must be
+            /// at least as long as `out`.
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $name(a: &[f64], b: &[f64], out: &mut [f64]) {
+                let n = out.len();
+                let mut i = 0;
+                // SAFETY: each vector covers i..i + $width <= n, inside all three slices;
+                // the remainder goes through the checked tail loop
+                while i + $width <= n {
+                    let v = $op($load(a.as_ptr().add(i)), $load(b.as_ptr().add(i)));
+                    $store(out.as_mut_ptr().add(i), v);
+                    i += $width;
+                }
+                for ((o, &x), &y) in out[i..].iter_mut().zip(&a[i..]).zip(&b[i..]) {
+                    *o = $scalar(x, y);
+                }
+            }
+        };
+    }
+
+    binary_kernel!(add_avx2, "avx2,fma", 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_add_pd, |x: f64, y: f64| x + y);
+    binary_kernel!(add_sse2, "sse2", 2, _mm_loadu_pd, _mm_storeu_pd, _mm_add_pd, |x: f64, y: f64| x + y);
+    binary_kernel!(mul_avx2, "avx2,fma", 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_mul_pd, |x: f64, y: f64| x * y);
+    binary_kernel!(mul_sse2, "sse2", 2, _mm_loadu_pd, _mm_storeu_pd, _mm_mul_pd, |x: f64, y: f64| x * y);
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn scale_avx2(a: &[f64], scalar: f64, out: &mut [f64]) {
+        let s = _mm256_set1_pd(scalar);
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 4 <= n, inside both slices; the tail loop is checked
+        while i + 4 <= n {
+            _mm256_storeu_pd(out.as_mut_ptr().add(i), _mm256_mul_pd(_mm256_loadu_pd(a.as_ptr().add(i)), s));
+            i += 4;
+        }
+        for (o, x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = x * scalar;
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn scale_sse2(a: &[f64], scalar: f64, out: &mut [f64]) {
+        let s = _mm_set1_pd(scalar);
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 2 <= n, inside both slices; the tail loop is checked
+        while i + 2 <= n {
+            _mm_storeu_pd(out.as_mut_ptr().add(i), _mm_mul_pd(_mm_loadu_pd(a.as_ptr().add(i)), s));
+            i += 2;
+        }
+        for (o, x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = x * scalar;
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `x` must be at least as long as `y`.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn axpy_avx2(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let a = _mm256_set1_pd(alpha);
+        let n = y.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 4 <= n, inside both slices; the tail loop is checked
+        while i + 4 <= n {
+            let py = y.as_mut_ptr().add(i);
+            _mm256_storeu_pd(py, _mm256_fmadd_pd(a, _mm256_loadu_pd(x.as_ptr().add(i)), _mm256_loadu_pd(py)));
+            i += 4;
+        }
+        for (y_i, x_i) in y[i..].iter_mut().zip(&x[i..]) {
+            *y_i = alpha.mul_add(*x_i, *y_i);
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `x` must be at least as long as `y`.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn axpy_sse2(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let a = _mm_set1_pd(alpha);
+        let n = y.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 2 <= n, inside both slices; the tail loop is checked
+        while i + 2 <= n {
+            let py = y.as_mut_ptr().add(i);
+            _mm_storeu_pd(py, _mm_add_pd(_mm_loadu_pd(py), _mm_mul_pd(a, _mm_loadu_pd(x.as_ptr().add(i)))));
+            i += 2;
+        }
+        for (y_i, x_i) in y[i..].iter_mut().zip(&x[i..]) {
+            *y_i += alpha * x_i;
+        }
+    }
+
+    // MAXPD returns its second operand when either is NaN or both are zero,
+    // so max(0, x) passes NaN and -0.0 through just like the scalar `relu`
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn relu_avx2(a: &[f64], out: &mut [f64]) {
+        let zero = _mm256_setzero_pd();
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 4 <= n, inside both slices; the tail loop is checked
+        while i + 4 <= n {
+            _mm256_storeu_pd(out.as_mut_ptr().add(i), _mm256_max_pd(zero, _mm256_loadu_pd(a.as_ptr().add(i))));
+            i += 4;
+        }
+        for (o, &x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = if x < 0.0 { 0.0 } else { x };
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn relu_sse2(a: &[f64], out: &mut [f64]) {
+        let zero = _mm_setzero_pd();
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 2 <= n, inside both slices; the tail loop is checked
+        while i + 2 <= n {
+            _mm_storeu_pd(out.as_mut_ptr().add(i), _mm_max_pd(zero, _mm_loadu_pd(a.as_ptr().add(i))));
+            i += 2;
+        }
+        for (o, &x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = if x < 0.0 { 0.0 } else { x };
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA.
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn exp_avx2(x: __m256d) -> __m256d {
+        // max(lo, x) and min(hi, x) return x when it is NaN, so NaN propagates
+        let x = _mm256_min_pd(_mm256_set1_pd(EXP_MAX), _mm256_max_pd(_mm256_set1_pd(EXP_MIN), x));
+        let n = _mm256_round_pd(_mm256_mul_pd(x, _mm256_set1_pd(LOG2E)), _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC);
+        let r = _mm256_fnmadd_pd(n, _mm256_set1_pd(LN2_HI), x);
+        let r = _mm256_fnmadd_pd(n, _mm256_set1_pd(LN2_LO), r);
+
+        let mut p = _mm256_set1_pd(EXP_COEFFS[0]);
+        for &coeff in &EXP_COEFFS[1..] {
+            p = _mm256_fmadd_pd(p, r, _mm256_set1_pd(coeff));
+        }
+        p = _mm256_fmadd_pd(p, r, _mm256_set1_pd(1.0));
+        p = _mm256_fmadd_pd(p, r, _mm256_set1_pd(1.0));
+
+        // 2^n built directly in the exponent field
+        let exponent = _mm256_add_epi64(_mm256_cvtepi32_epi64(_mm256_cvtpd_epi32(n)), _mm256_set1_epi64x(1023));
+        _mm256_mul_pd(p, _mm256_castsi256_pd(_mm256_slli_epi64(exponent, 52)))
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2.
+    #[target_feature(enable = "sse2")]
+    unsafe fn exp_sse2(x: __m128d) -> __m128d {
+        let x = _mm_min_pd(_mm_set1_pd(EXP_MAX), _mm_max_pd(_mm_set1_pd(EXP_MIN), x));
+        // SSE2 has no round instruction; the conversion rounds to nearest
+        let n_int = _mm_cvtpd_epi32(_mm_mul_pd(x, _mm_set1_pd(LOG2E)));
+        let n = _mm_cvtepi32_pd(n_int);
+        let r = _mm_sub_pd(x, _mm_mul_pd(n, _mm_set1_pd(LN2_HI)));
+        let r = _mm_sub_pd(r, _mm_mul_pd(n, _mm_set1_pd(LN2_LO)));
+
+        let mut p = _mm_set1_pd(EXP_COEFFS[0]);
+        for &coeff in &EXP_COEFFS[1..] {
+            p = _mm_add_pd(_mm_mul_pd(p, r), _mm_set1_pd(coeff));
+        }
+        p = _mm_add_pd(_mm_mul_pd(p, r), _mm_set1_pd(1.0));
+        p = _mm_add_pd(_mm_mul_pd(p, r), _mm_set1_pd(1.0));
+
+        // n + 1023 is positive, so zero-extending the two 32-bit lanes is enough
+        let biased = _mm_add_epi32(n_int, _mm_set1_epi32(1023));
+        let exponent = _mm_unpacklo_epi32(biased, _mm_setzero_si128());
+        _mm_mul_pd(p, _mm_castsi128_pd(_mm_slli_epi64(exponent, 52)))
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn sigmoid_avx2(a: &[f64], out: &mut [f64]) {
+        let one = _mm256_set1_pd(1.0);
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 4 <= n, inside both slices; the tail loop is checked
+        while i + 4 <= n {
+            let negated = _mm256_sub_pd(_mm256_setzero_pd(), _mm256_loadu_pd(a.as_ptr().add(i)));
+            let value = _mm256_div_pd(one, _mm256_add_pd(one, exp_avx2(negated)));
+            _mm256_storeu_pd(out.as_mut_ptr().add(i), value);
+            i += 4;
+        }
+        for (o, &x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = 1.0 / (1.0 + (-x).exp());
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `a` must be at least as long as `out`.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn sigmoid_sse2(a: &[f64], out: &mut [f64]) {
+        let one = _mm_set1_pd(1.0);
+        let n = out.len();
+        let mut i = 0;
+        // SAFETY: each vector covers i..i + 2 <= n, inside both slices; the tail loop is checked
+        while i + 2 <= n {
+            let negated = _mm_sub_pd(_mm_setzero_pd(), _mm_loadu_pd(a.as_ptr().add(i)));
+            let value = _mm_div_pd(one, _mm_add_pd(one, exp_sse2(negated)));
+            _mm_storeu_pd(out.as_mut_ptr().add(i), value);
+            i += 2;
+        }
+        for (o, &x) in out[i..].iter_mut().zip(&a[i..]) {
+            *o = 1.0 / (1.0 + (-x).exp());
+        }
+    }
+
+    /// Add the top-left mr x nr corner of a 4-row accumulator block into C
+    fn write_tile<const W: usize>(acc: &[[f64; W]; 4], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+        for (i, acc_row) in acc.iter().enumerate().take(mr) {
+            for (c_ij, value) in c[i * ldc..i * ldc + nr].iter_mut().zip(acc_row) {
+                *c_ij += value;
+            }
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2, and `a` and `b` must hold at least `4 * depth` values.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn kernel_4x4_sse2(depth: usize, a: &[f64], b: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+        // Each row of the tile is two registers: 8 accumulators in total
+        let mut acc = [[_mm_setzero_pd(); 2]; 4];
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        // SAFETY: step p reads a[4p..4p + 4] and b[4p..4p + 4], inside both slivers for p < depth
+        for p in 0..depth {
+            let b_lo = _mm_loadu_pd(pb.add(4 * p));
+            let b_hi = _mm_loadu_pd(pb.add(4 * p + 2));
+            for (i, acc_row) in acc.iter_mut().enumerate() {
+                let a_i = _mm_set1_pd(*pa.add(4 * p + i));
+                acc_row[0] = _mm_add_pd(acc_row[0], _mm_mul_pd(a_i, b_lo));
+                acc_row[1] = _mm_add_pd(acc_row[1], _mm_mul_pd(a_i, b_hi));
+            }
+        }
+
+        // SAFETY: each pair of stores fills one 4-wide row of the local tile; C is written through checked slices
+        let mut tile = [[0.0; 4]; 4];
+        for (row, acc_row) in tile.iter_mut().zip(&acc) {
+            _mm_storeu_pd(row.as_mut_ptr(), acc_row[0]);
+            _mm_storeu_pd(row.as_mut_ptr().add(2), acc_row[1]);
+        }
+        write_tile(&tile, c, ldc, mr, nr);
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `a` and `b` must hold at least `4 * depth` values.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn kernel_4x4_avx2(depth: usize, a: &[f64], b: &[f64], c: &mut [f64], ldc: usize, mr: usize, nr: usize) {
+        let mut acc = [_mm256_setzero_pd(); 4];
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        // SAFETY: step p reads a[4p..4p + 4] and b[4p..4p + 4], inside both slivers for p < depth
+        for p in 0..depth {
+            let b_row = _mm256_loadu_pd(pb.add(4 * p));
+            for (i, acc_i) in acc.iter_mut().enumerate() {
+                *acc_i = _mm256_fmadd_pd(_mm256_broadcast_sd(&*pa.add(4 * p + i)), b_row, *acc_i);
+            }
+        }
+
+        // SAFETY: each store fills one 4-wide row of the local tile
+        let mut tile = [[0.0; 4]; 4];
+        for (row, acc_i) in tile.iter_mut().zip(&acc) {
+            _mm256_storeu_pd(row.as_mut_ptr(), *acc_i);
+        }
+        write_tile(&tile, c, ldc, mr, nr);
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2 and FMA, and `a`, `b_left` and `b_right` must hold at least
+    /// `4 * depth` values.
+    #[target_feature(enable = "avx2,fma")]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn kernel_4x8_avx2(
+        depth: usize,
+        a: &[f64],
+        b_left: &[f64],
+        b_right: &[f64],
+        c: &mut [f64],
+        ldc: usize,
+        mr: usize,
+        nr: usize,
+    ) {
+        // 8 accumulators plus two B rows and one broadcast fit the 16 YMM registers
+        let mut acc = [[_mm256_setzero_pd(); 2]; 4];
+        let (pa, pl, pr) = (a.as_ptr(), b_left.as_ptr(), b_right.as_ptr());
+        // SAFETY: step p reads 4 values at 4p from each sliver, inside all three for p < depth
+        for p in 0..depth {
+            let left = _mm256_loadu_pd(pl.add(4 * p));
+            let right = _mm256_loadu_pd(pr.add(4 * p));
+            for (i, acc_row) in acc.iter_mut().enumerate() {
+                let a_i = _mm256_broadcast_sd(&*pa.add(4 * p + i));
+                acc_row[0] = _mm256_fmadd_pd(a_i, left, acc_row[0]);
+                acc_row[1] = _mm256_fmadd_pd(a_i, right, acc_row[1]);
+            }
+        }
+
+        // SAFETY: the two stores per row fill columns 0..4 and 4..8 of the 8-wide local tile
+        let mut tile = [[0.0; 8]; 4];
+        for (row, acc_row) in tile.iter_mut().zip(&acc) {
+            _mm256_storeu_pd(row.as_mut_ptr(), acc_row[0]);
+            _mm256_storeu_pd(row.as_mut_ptr().add(4), acc_row[1]);
+        }
+        write_tile(&tile, c, ldc, mr, nr);
+    }
+}
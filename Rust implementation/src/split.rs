@@ -0,0 +1,93 @@
+use crate::rng::Rng;
+
+/// MIT-BIH training records of the de Chazal et al. (2004) inter-patient partition
+pub const DS1: [&str; 22] = [
+    "101", "106", "108", "109", "112", "114", "115", "116", "118", "119", "122",
+    "124", "201", "203", "205", "207", "208", "209", "215", "220", "223", "230",
+];
+
+/// MIT-BIH test records of the de Chazal et al. (2004) inter-patient partition
+pub const DS2: [&str; 22] = [
+    "100", "103", "105", "111", "113", "117", "121", "123", "200", "202", "210",
+    "212", "213", "214", "219", "221", "222", "228", "231", "232", "233", "234",
+];
+
+/// Indices of the samples (segments, beats, ...) in each side of a split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split {
+    pub train: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// Distinct record IDs, sorted
+pub fn unique_records(record_ids: &[String]) -> Vec<String> {
+    let mut unique = record_ids.to_vec();
+    unique.sort();
+    unique.dedup();
+    unique
+}
+
+/// Split samples so that each record ends up entirely on one side
+/// `is_test` decides the side of each record ID
+fn split_by_record<F: Fn(&str) -> Option<bool>>(record_ids: &[String], is_test: F) -> Split {
+    let mut split = Split { train: Vec::new(), test: Vec::new() };
+    for (index, id) in record_ids.iter().enumerate() {
+        match is_test(id) {
+            Some(true) => split.test.push(index),
+            Some(false) => split.train.push(index),
+            None => {}
+        }
+    }
+    split
+}
+
+/// Standard de Chazal DS1/DS2 split
+///
+/// # Arguments
+/// * `record_ids` - Record ID of every sample, e.g. "100"
+///
+/// # Returns
+/// * DS1 samples for training and DS2 samples for testing; samples from any
+///   other record (the paced records 102, 104, 107 and 217) are left out
+pub fn de_chazal_split(record_ids: &[String]) -> Split {
+    split_by_record(record_ids, |id| {
+        if DS2.contains(&id) {
+            Some(true)
+        } else if DS1.contains(&id) {
+            Some(false)
+        } else {
+            None
+        }
+    })
+}
+
+/// Random patient-level holdout
+/// Holds out `test_fraction` of the records (at least one, when there are two or more)
+pub fn patient_holdout_split(record_ids: &[String], test_fraction: f64, seed: u64) -> Split {
+    let mut records = unique_records(record_ids);
+    Rng::new(seed).shuffle(&mut records);
+
+    let mut num_test = (records.len() as f64 * test_fraction).round() as usize;
+    if records.len() > 1 {
+        num_test = num_test.clamp(1, records.len() - 1);
+    }
+    let test_records = &records[..num_test.min(records.len())];
+
+    split_by_record(record_ids, |id| Some(test_records.iter().any(|r| r == id)))
+}
+
+/// Leave-one-patient-out: one split per record, in sorted record order
+pub fn leave_one_patient_out(record_ids: &[String]) -> Vec<(String, Split)> {
+    unique_records(record_ids)
+        .into_iter()
+        .map(|held_out| {
+            let split = split_by_record(record_ids, |id| Some(id == held_out));
+            (held_out, split)
+        })
+        .collect()
+}
+
+/// Gather the items at the given indices
+pub fn select<T: Clone>(items: &[T], indices: &[usize]) -> Vec<T> {
+    indices.iter().map(|&i| items[i].clone()).collect()
+}
@@ -0,0 +1,114 @@
+use ecgnn::augment::*;
+use ecgnn::rng::Rng;
+
+// Two-lead segment in the data_segmentation layout: [index, lead_1, lead_2]
+fn test_segment(len: usize) -> Vec<Vec<f64>> {
+    (0..len)
+        .map(|i| {
+            let t = i as f64;
+            vec![t, (t * 0.2).sin(), (t * 0.05).cos()]
+        })
+        .collect()
+}
+
+fn lead(segment: &[Vec<f64>], column: usize) -> Vec<f64> {
+    segment.iter().map(|row| row[column]).collect()
+}
+
+#[cfg(test)]
+mod augment_tests {
+    use super::*;
+
+    #[test]
+    fn test_augmentations_preserve_shape_and_index_column() {
+        let original = test_segment(100);
+        let augmentations: Vec<Box<dyn Augmentation>> = vec![
+            Box::new(GaussianNoise { std_dev: 0.1 }),
+            Box::new(AmplitudeScale { min: 0.5, max: 1.5 }),
+            Box::new(BaselineShift { max_shift: 1.0 }),
+            Box::new(TimeShift { max_shift: 10 }),
+            Box::new(TimeWarp { max_strength: 0.3 }),
+            Box::new(RandomCrop { min_fraction: 0.7 }),
+            Box::new(LeadDropout { probability: 0.5 }),
+        ];
+
+        let mut rng = Rng::new(5);
+        for augmentation in &augmentations {
+            let mut segment = original.clone();
+            augmentation.apply(&mut segment, &mut rng);
+            assert_eq!(segment.len(), original.len());
+            assert!(segment.iter().all(|row| row.len() == 3));
+            assert_eq!(lead(&segment, 0), lead(&original, 0));
+        }
+    }
+
+    #[test]
+    fn test_amplitude_scale_uses_one_factor() {
+        let original = test_segment(50);
+        let mut segment = original.clone();
+        AmplitudeScale { min: 2.0, max: 3.0 }.apply(&mut segment, &mut Rng::new(1));
+
+        let factor = segment[1][1] / original[1][1];
+        assert!((2.0..3.0).contains(&factor));
+        for (a, b) in segment.iter().zip(original.iter()) {
+            assert!((a[1] - b[1] * factor).abs() < 1e-12);
+            assert!((a[2] - b[2] * factor).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_time_shift_moves_samples() {
+        let original = test_segment(50);
+        let mut segment = original.clone();
+        TimeShift { max_shift: 5 }.apply(&mut segment, &mut Rng::new(2));
+
+        let shift = (0..=10i64)
+            .map(|s| s - 5)
+            .find(|&s| (10..40).all(|i| segment[i][1] == original[(i as i64 - s) as usize][1]))
+            .expect("segment should be a shifted copy");
+        assert!(shift.abs() <= 5);
+    }
+
+    #[test]
+    fn test_time_warp_keeps_endpoints() {
+        let original = test_segment(80);
+        let mut segment = original.clone();
+        TimeWarp { max_strength: 0.5 }.apply(&mut segment, &mut Rng::new(3));
+
+        assert!((segment[0][1] - original[0][1]).abs() < 1e-12);
+        assert!((segment[79][2] - original[79][2]).abs() < 1e-12);
+        assert_ne!(lead(&segment, 1), lead(&original, 1));
+    }
+
+    #[test]
+    fn test_lead_dropout_keeps_one_lead() {
+        let original = test_segment(20);
+        for seed in 0..20 {
+            let mut segment = original.clone();
+            LeadDropout { probability: 1.0 }.apply(&mut segment, &mut Rng::new(seed));
+            let zeroed = (1..3).filter(|&c| segment.iter().all(|row| row[c] == 0.0)).count();
+            assert_eq!(zeroed, 1);
+        }
+    }
+
+    #[test]
+    fn test_pipeline_does_not_modify_dataset_and_is_seeded() {
+        let dataset = vec![test_segment(60), test_segment(60)];
+        let build = || {
+            Pipeline::new(9)
+                .with(GaussianNoise { std_dev: 0.1 }, 1.0)
+                .with(RandomCrop { min_fraction: 0.8 }, 0.5)
+        };
+
+        let mut a = build();
+        let mut b = build();
+        let first = a.apply_batch(&dataset);
+        assert_eq!(first, b.apply_batch(&dataset));
+        assert_eq!(dataset[0], test_segment(60));
+        assert_ne!(first[0], dataset[0]);
+
+        // Each batch draws fresh augmentations
+        let second = a.apply_batch(&dataset);
+        assert_ne!(first, second);
+    }
+}
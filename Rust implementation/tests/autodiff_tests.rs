@@ -0,0 +1,164 @@
+mod common;
+
+use ecgnn::autodiff::Tape;
+use ecgnn::brains::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use common::random_matrix;
+
+fn assert_close(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-10, "{} != {}", a, e);
+    }
+}
+
+#[cfg(test)]
+mod autodiff_tests {
+    use super::*;
+
+    #[test]
+    fn test_tape_matches_brains_multiclass_backprop() {
+        let mut rng = Rng::new(1);
+        let segments = random_matrix(8, 10, &mut rng);
+        let (w1, b1) = (random_matrix(10, 5, &mut rng), random_matrix(1, 5, &mut rng));
+        let (w2, b2) = (random_matrix(5, 3, &mut rng), random_matrix(1, 3, &mut rng));
+        let mut targets = Matrix::zeros(8, 3);
+        for i in 0..8 {
+            targets[(i, i % 3)] = 1.0;
+        }
+
+        let mut tape = Tape::new();
+        let x = tape.input(segments.clone());
+        let (tw1, tb1, tw2, tb2) = (tape.input(w1.clone()), tape.input(b1.clone()), tape.input(w2.clone()), tape.input(b2.clone()));
+        let hidden = tape.matmul(x, tw1);
+        let hidden = tape.add_row(hidden, tb1);
+        let hidden = tape.relu(hidden);
+        let logits = tape.matmul(hidden, tw2);
+        let logits = tape.add_row(logits, tb2);
+        let probabilities = tape.softmax(logits);
+        let loss = tape.softmax_cross_entropy(logits, &targets, &[1.0; 8]);
+        let grads = tape.backward(loss);
+
+        let expected = predict_proba_batch(&segments, &w1, &b1.data, &w2, &b2.data);
+        assert_close(&tape.value(probabilities).data, &expected.data);
+        let (gw1, gb1, gw2, gb2) = backprop_multiclass(&segments, &w1, &b1.data, &w2, &b2.data, &targets);
+        assert_close(&grads[tw1].data, &gw1.data);
+        assert_close(&grads[tb1].data, &gb1);
+        assert_close(&grads[tw2].data, &gw2.data);
+        assert_close(&grads[tb2].data, &gb2);
+        assert!((tape.value(loss).data[0] - categorical_cross_entropy(&expected, &targets)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sigmoid_cross_entropy_matches_weighted_backprop() {
+        let mut rng = Rng::new(2);
+        let segments = random_matrix(6, 4, &mut rng);
+        let (w1, b1) = (random_matrix(4, 3, &mut rng), random_matrix(1, 3, &mut rng));
+        let w2 = random_matrix(3, 1, &mut rng);
+        let labels = vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0];
+        let weights = vec![2.0, 1.0, 1.0, 2.0, 2.0, 1.0];
+
+        let mut tape = Tape::new();
+        let x = tape.input(segments.clone());
+        let (tw1, tb1, tw2, tb2) = (tape.input(w1.clone()), tape.input(b1.clone()), tape.input(w2.clone()), tape.input(Matrix::filled(1, 1, 0.1)));
+        let hidden = tape.matmul(x, tw1);
+        let hidden = tape.add_row(hidden, tb1);
+        let hidden = tape.relu(hidden);
+        let logits = tape.matmul(hidden, tw2);
+        let logits = tape.add_row(logits, tb2);
+        let loss = tape.sigmoid_cross_entropy(logits, &Matrix::from_vec(6, 1, labels.clone()), &weights);
+        let grads = tape.backward(loss);
+
+        let (gw1, gb1, gw2, gb2) = backprop_weighted(&segments, &w1, &b1.data, &w2.data, 0.1, &labels, &weights);
+        assert_close(&grads[tw1].data, &gw1.data);
+        assert_close(&grads[tb1].data, &gb1);
+        assert_close(&grads[tw2].data, &gw2);
+        assert_close(&grads[tb2].data, &[gb2]);
+
+        let outputs = apply_sigmoid(&tape.value(logits).data);
+        assert!((tape.value(loss).data[0] - weighted_loss_function(&outputs, &labels, &[1.0, 2.0])).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sigmoid_cross_entropy_is_exact_when_saturated() {
+        // sigmoid(40) rounds to 1, so the clamped loss of the probability is capped near 34.5
+        let mut tape: Tape = Tape::new();
+        let z = tape.input(Matrix::from(vec![vec![40.0], vec![-40.0]]));
+        let loss = tape.sigmoid_cross_entropy(z, &Matrix::from(vec![vec![0.0], vec![0.0]]), &[1.0, 1.0]);
+        assert!((tape.value(loss).data[0] - 20.0).abs() < 1e-9);
+        let grads = tape.backward(loss);
+        assert_close(&grads[z].data, &[0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_elementwise_and_reduction_gradients() {
+        let a_value = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let mut tape = Tape::new();
+        let a = tape.input(a_value.clone());
+        let b = tape.input(Matrix::from(vec![vec![0.5, -1.0], vec![2.0, 0.0]]));
+
+        // mean(a * a - b) + sum(exp(b)) + sum(ln(a)) + sum(column_sums(2 a^T))
+        let squared = tape.mul(a, a);
+        let difference = tape.sub(squared, b);
+        let mean = tape.mean(difference);
+        let exp = tape.exp(b);
+        let exp_sum = tape.sum(exp);
+        let ln = tape.ln(a);
+        let ln_sum = tape.sum(ln);
+        let transposed = tape.transpose(a);
+        let doubled = tape.scale(transposed, 2.0);
+        let columns = tape.column_sums(doubled);
+        let column_total = tape.sum(columns);
+        let total = tape.add(mean, exp_sum);
+        let total = tape.add(total, ln_sum);
+        let total = tape.add(total, column_total);
+        let grads = tape.backward(total);
+
+        let expected_a: Vec<f64> = a_value.data.iter().map(|&x| 2.0 * x / 4.0 + 1.0 / x + 2.0).collect();
+        assert_close(&grads[a].data, &expected_a);
+        let expected_b: Vec<f64> = tape.value(b).data.iter().map(|&x| -0.25 + x.exp()).collect();
+        assert_close(&grads[b].data, &expected_b);
+    }
+
+    #[test]
+    fn test_unused_values_get_zero_gradients() {
+        let mut tape = Tape::new();
+        let a = tape.input(Matrix::filled(2, 3, 1.0));
+        let unused = tape.input(Matrix::filled(4, 1, 1.0));
+        let s = tape.sigmoid(a);
+        let loss = tape.sum(s);
+        let grads = tape.backward(loss);
+        assert_eq!(grads[unused], Matrix::zeros(4, 1));
+        let slope = sigmoid_derivative(sigmoid(1.0));
+        assert_close(&grads[a].data, &[slope; 6]);
+        assert_eq!(tape.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "backward needs a 1x1 output, got 2x2")]
+    fn test_backward_needs_scalar() {
+        let mut tape: Tape = Tape::new();
+        let a = tape.input(Matrix::zeros(2, 2));
+        tape.backward(a);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tape::loss of a sigmoid loses its gradient at saturation; use sigmoid_cross_entropy on the logits")]
+    fn test_loss_rejects_binary_cross_entropy_of_sigmoid() {
+        let mut tape: Tape = Tape::new();
+        let z = tape.input(Matrix::filled(2, 1, 40.0));
+        let probabilities = tape.sigmoid(z);
+        tape.loss(probabilities, &Matrix::zeros(2, 1), &[1.0, 1.0], Loss::BinaryCrossEntropy);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tape::loss of a softmax loses its gradient at saturation; use softmax_cross_entropy on the logits")]
+    fn test_loss_rejects_categorical_cross_entropy_of_softmax() {
+        let mut tape: Tape = Tape::new();
+        let z = tape.input(Matrix::from(vec![vec![40.0, -40.0]]));
+        let probabilities = tape.softmax(z);
+        tape.loss(probabilities, &Matrix::from(vec![vec![0.0, 1.0]]), &[1.0], Loss::CategoricalCrossEntropy);
+    }
+}
@@ -0,0 +1,124 @@
+use ecgnn::batching::BatchConfig;
+use ecgnn::brains::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+
+/// Two noisy, linearly separable classes
+fn two_class_problem(n: usize) -> (Matrix, Matrix) {
+    let mut rng = Rng::new(8);
+    let mut inputs = Matrix::zeros(n, 2);
+    let mut targets = Matrix::zeros(n, 1);
+    for i in 0..n {
+        let label = (i % 2) as f64;
+        inputs[(i, 0)] = label * 2.0 - 1.0 + rng.normal(0.0, 0.3);
+        inputs[(i, 1)] = rng.normal(0.0, 1.0);
+        targets[(i, 0)] = label;
+    }
+    (inputs, targets)
+}
+
+#[cfg(test)]
+mod batching_tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_cover_every_sample_once() {
+        let config = BatchConfig::new(4, 1);
+        let batches = config.batches(10, 0);
+        let sizes: Vec<usize> = batches.iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+
+        let mut seen: Vec<usize> = batches.concat();
+        assert_ne!(seen, (0..10).collect::<Vec<_>>());
+        seen.sort();
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drop_last() {
+        let config = BatchConfig { drop_last: true, ..BatchConfig::new(4, 1) };
+        let batches = config.batches(10, 0);
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|b| b.len() == 4));
+        assert_eq!(config.batches(8, 0).len(), 2);
+        assert!(config.batches(3, 0).is_empty());
+    }
+
+    #[test]
+    fn test_shuffle_is_seeded_per_epoch() {
+        let config = BatchConfig::new(3, 5);
+        assert_eq!(config.batches(20, 2), config.batches(20, 2));
+        assert_ne!(config.batches(20, 2), config.batches(20, 3));
+        assert_ne!(config.batches(20, 2), BatchConfig::new(3, 6).batches(20, 2));
+
+        let ordered = BatchConfig { shuffle: false, ..config };
+        assert_eq!(ordered.batches(7, 4), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+        assert_eq!(BatchConfig::full_batch(5).batches(5, 9), vec![vec![0, 1, 2, 3, 4]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch size must be at least 1")]
+    fn test_zero_batch_size() {
+        BatchConfig::new(0, 0).batches(4, 0);
+    }
+
+    #[test]
+    fn test_epoch_loss_comes_from_training_forward_passes() {
+        let (inputs, targets) = two_class_problem(10);
+        let weights: Vec<f64> = (0..10).map(|i| 1.0 + i as f64).collect();
+        let mut model = Sequential::mlp_seeded(2, &[3], 1, 1);
+
+        // Without updates every batch sees the same network, so the epoch loss is the full weighted loss
+        let full = Loss::BinaryCrossEntropy.evaluate(&model.forward(&inputs), &targets, &weights).0;
+        let epoch_loss = model.train_epoch(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &BatchConfig::new(3, 0), 0, 0.0);
+        assert!((epoch_loss - full).abs() < 1e-12);
+
+        // One full batch is exactly one train_step
+        let mut stepped = Sequential::mlp_seeded(2, &[3], 1, 1);
+        let step_loss = stepped.train_step(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, 0.1);
+        let epoch_loss = model.train_epoch(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &BatchConfig::full_batch(10), 0, 0.1);
+        assert_eq!(epoch_loss, step_loss);
+        assert_eq!(model.forward(&inputs), stepped.forward(&inputs));
+    }
+
+    #[test]
+    fn test_mini_batches_learn_faster_per_epoch() {
+        let (inputs, targets) = two_class_problem(128);
+        let weights = vec![1.0; 128];
+        let mut full = Sequential::mlp_seeded(2, &[8], 1, 3);
+        let mut mini = Sequential::mlp_seeded(2, &[8], 1, 3);
+        let batches = BatchConfig::new(16, 3);
+
+        for epoch in 0..5 {
+            full.train_epoch(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &BatchConfig::full_batch(128), epoch, 0.1);
+            mini.train_epoch(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, epoch, 0.1);
+        }
+
+        let loss = |model: &mut Sequential| Loss::BinaryCrossEntropy.evaluate(&model.forward(&inputs), &targets, &weights).0;
+        assert!(loss(&mut mini) < loss(&mut full));
+        assert!(calculate_accuracy(&mini.forward(&inputs).data, &targets.data) > 0.9);
+    }
+
+    #[test]
+    fn test_training_is_reproducible_from_seeds() {
+        let (inputs, targets) = two_class_problem(40);
+        let run = || {
+            let mut model = Sequential::mlp_seeded(2, &[4], 1, 9);
+            let losses: Vec<f64> = (0..3)
+                .map(|epoch| model.train_epoch(&inputs, &targets, &[1.0; 40], Loss::BinaryCrossEntropy, &BatchConfig::new(8, 9), epoch, 0.2))
+                .collect();
+            (losses, model.forward(&inputs))
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    #[should_panic(expected = "Epoch has no samples to train on")]
+    fn test_epoch_without_batches() {
+        let (inputs, targets) = two_class_problem(3);
+        let config = BatchConfig { drop_last: true, ..BatchConfig::new(4, 0) };
+        Sequential::mlp_seeded(2, &[2], 1, 0).train_epoch(&inputs, &targets, &[1.0; 3], Loss::BinaryCrossEntropy, &config, 0, 0.1);
+    }
+}
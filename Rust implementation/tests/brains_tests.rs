@@ -0,0 +1,189 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use ecgnn::brains::*;
+    use ecgnn::init::Initializer;
+    use ecgnn::matrix_math::*;
+    use ecgnn::rng::Rng;
+    use super::common::random_matrix;
+
+    #[test]
+    fn test_softmax_sums_to_one() {
+        let probabilities = softmax(&[1.0, 2.0, 3.0]);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(probabilities[2] > probabilities[1] && probabilities[1] > probabilities[0]);
+    }
+
+    #[test]
+    fn test_softmax_is_numerically_stable() {
+        let probabilities = softmax(&[1000.0f64, 1001.0, -1000.0]);
+        assert!(probabilities.iter().all(|p| p.is_finite()));
+        assert!((probabilities[1] - 1.0 / (1.0 + (-1.0f64).exp())).abs() < 1e-12);
+        assert_eq!(probabilities[2], 0.0);
+
+        // Shifting all logits does not change the result
+        let shifted = softmax(&[0.0, 1.0, -2000.0]);
+        assert!((probabilities[0] - shifted[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_argmax() {
+        assert_eq!(argmax(&[0.1, 0.7, 0.2]), 1);
+        assert_eq!(argmax(&[0.5, 0.5]), 0);
+    }
+
+    #[test]
+    fn test_categorical_cross_entropy() {
+        let probabilities = Matrix::from(vec![vec![0.5, 0.25, 0.25], vec![0.0, 1.0, 0.0]]);
+        let targets = Matrix::from(vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]]);
+        let loss = categorical_cross_entropy(&probabilities, &targets);
+        assert!((loss - (2.0f64.ln() / 2.0)).abs() < 1e-12);
+
+        // A zero probability on the true class is clamped rather than infinite
+        let worst: f64 = categorical_cross_entropy(&Matrix::from_vec(1, 2, vec![1.0, 0.0]), &Matrix::from_vec(1, 2, vec![0.0, 1.0]));
+        assert!(worst.is_finite() && worst > 30.0);
+    }
+
+    #[test]
+    fn test_softmax_cross_entropy_gradient_matches_finite_difference() {
+        let logits: Vec<f64> = vec![0.3, -1.2, 2.0];
+        let target = Matrix::from_vec(1, 3, vec![0.0, 0.0, 1.0]);
+        let gradient = softmax_cross_entropy_gradient(&softmax(&logits), target.row(0));
+
+        let h = 1e-6;
+        for i in 0..logits.len() {
+            let mut plus = logits.clone();
+            let mut minus = logits.clone();
+            plus[i] += h;
+            minus[i] -= h;
+            let numeric = (categorical_cross_entropy(&Matrix::from_vec(1, 3, softmax(&plus)), &target)
+                - categorical_cross_entropy(&Matrix::from_vec(1, 3, softmax(&minus)), &target)) / (2.0 * h);
+            assert!((numeric - gradient[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_train_epoch_multiclass_learns_three_classes() {
+        // Class is the position of the largest of three features
+        let mut segments = Matrix::filled(30, 3, 0.1);
+        let mut targets = Matrix::zeros(30, 3);
+        let classes: Vec<usize> = (0..30).map(|i| i % 3).collect();
+        for (i, &c) in classes.iter().enumerate() {
+            segments[(i, c)] = 1.0;
+            targets[(i, c)] = 1.0;
+        }
+
+        // Break the symmetry of the constant initialisation
+        let (mut w1, mut b1) = initialize_weights_and_bias(3, 4, Initializer::Constant(0.01), &mut Rng::new(0));
+        for (i, row) in w1.iter_rows_mut().enumerate() {
+            for (j, w) in row.iter_mut().enumerate() {
+                *w = if i == j { 1.0 } else { -0.1 };
+            }
+        }
+        let (mut w2, mut b2) = initialize_weights_and_bias(4, 3, Initializer::Constant(0.01), &mut Rng::new(0));
+
+        let first = train_epoch_multiclass(&segments, &targets, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+        let mut last = first;
+        for _ in 0..200 {
+            last = train_epoch_multiclass(&segments, &targets, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+        }
+
+        assert!(last < first / 4.0);
+        let predicted = predict_class_batch(&segments, &w1, &b1, &w2, &b2);
+        assert_eq!(calculate_multiclass_accuracy(&predicted, &classes), 1.0);
+    }
+
+    #[test]
+    fn test_weighted_losses_reduce_to_unweighted() {
+        let outputs: Vec<f64> = vec![0.9, 0.2, 0.6];
+        let labels = vec![1.0, 0.0, 0.0];
+        assert!((weighted_loss_function(&outputs, &labels, &[1.0, 1.0]) - loss_function(&outputs, &labels)).abs() < 1e-12);
+
+        let probabilities: Matrix = Matrix::from(vec![vec![0.7, 0.3], vec![0.4, 0.6]]);
+        let targets = Matrix::from(vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        let unweighted = categorical_cross_entropy(&probabilities, &targets);
+        assert!((weighted_categorical_cross_entropy(&probabilities, &targets, &[1.0, 1.0]) - unweighted).abs() < 1e-12);
+
+        // Up-weighting class 1 moves the loss towards that sample's loss
+        let weighted = weighted_categorical_cross_entropy(&probabilities, &targets, &[1.0, 3.0]);
+        assert!((weighted - (-(0.7f64).ln() - 3.0 * (0.6f64).ln()) / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_class_weights_shift_binary_decision() {
+        // Identical inputs with conflicting labels: the optimum output is the weighted label mean
+        let segments = Matrix::from(vec![vec![1.0, 0.5]; 4]);
+        let labels = vec![1.0, 0.0, 0.0, 0.0];
+
+        let train = |class_weights: &[f64]| {
+            let (mut w1, mut b1) = initialize_weights_and_bias(2, 2, Initializer::Constant(0.01), &mut Rng::new(0));
+            let mut w2 = vec![0.01; 2];
+            let mut b2 = 0.01;
+            for _ in 0..2000 {
+                train_epoch_weighted(&segments, &labels, class_weights, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+            }
+            predict(segments.row(0), &w1, &b1, &w2, b2)
+        };
+
+        assert!((train(&[1.0, 1.0]) - 0.25).abs() < 0.02);
+        assert!((train(&[1.0, 3.0]) - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_batched_forward_matches_per_sample() {
+        let mut rng = Rng::new(7);
+        let segments = random_matrix(9, 20, &mut rng);
+        let w1 = random_matrix(20, 6, &mut rng);
+        let b1 = random_matrix(1, 6, &mut rng).data;
+        let w2 = random_matrix(1, 6, &mut rng).data;
+        let w2_multi = random_matrix(6, 3, &mut rng);
+
+        let (outputs, hidden) = forward_pass_batch(&segments, &w1, &b1, &w2, 0.2);
+        let (probabilities, hidden_multi) = forward_pass_multiclass_batch(&segments, &w1, &b1, &w2_multi, &[0.1, -0.1, 0.0]);
+        assert_eq!((hidden.shape(), probabilities.shape()), ((9, 6), (9, 3)));
+        assert_eq!(hidden, hidden_multi);
+
+        for (i, segment) in segments.iter_rows().enumerate() {
+            let (output, activations) = forward_pass(segment, &w1, &b1, &w2, 0.2);
+            assert!((outputs[i] - output).abs() < 1e-12);
+            for (a, b) in hidden.row(i).iter().zip(&activations) {
+                assert!((a - b).abs() < 1e-12);
+            }
+
+            let (p, _) = forward_pass_multiclass(segment, &w1, &b1, &w2_multi, &[0.1, -0.1, 0.0]);
+            for (a, b) in probabilities.row(i).iter().zip(&p) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_batched_backprop_is_weighted_mean_of_single_samples() {
+        let mut rng = Rng::new(8);
+        let segments = random_matrix(5, 7, &mut rng);
+        let w1 = random_matrix(7, 4, &mut rng);
+        let b1 = random_matrix(1, 4, &mut rng).data;
+        let w2 = random_matrix(1, 4, &mut rng).data;
+        let labels = vec![1.0, 0.0, 1.0, 1.0, 0.0];
+        let weights = vec![1.0, 2.0, 0.5, 1.0, 3.0];
+
+        let (gw1, gb1, gw2, gb2) = backprop_weighted(&segments, &w1, &b1, &w2, 0.1, &labels, &weights);
+
+        let total: f64 = weights.iter().sum();
+        let mut expected_gw1 = Matrix::zeros(7, 4);
+        let mut expected_gb2 = 0.0;
+        for i in 0..5 {
+            let single = segments.select_rows(&[i]);
+            let (sw1, _, _, sb2) = backprop(&single, &w1, &b1, &w2, 0.1, &labels[i..=i]);
+            matrix_add_inplace(&mut expected_gw1, &scalar_multiply(&sw1, weights[i] / total));
+            expected_gb2 += sb2 * weights[i] / total;
+        }
+
+        for (a, b) in gw1.data.iter().zip(&expected_gw1.data) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        assert!((gb2 - expected_gb2).abs() < 1e-12);
+        assert_eq!((gb1.len(), gw2.len()), (4, 4));
+    }
+}
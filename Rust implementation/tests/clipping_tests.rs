@@ -0,0 +1,79 @@
+use ecgnn::clipping::*;
+use ecgnn::layers::Param;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::Sgd;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+
+fn with_grad(grad: Vec<f64>) -> Param {
+    let mut param = Param::new("weights", Matrix::zeros(1, grad.len()));
+    param.grad = Matrix::from_vec(1, grad.len(), grad);
+    param
+}
+
+#[cfg(test)]
+mod clipping_tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_by_value() {
+        let (mut a, mut b) = (with_grad(vec![3.0, -0.5]), with_grad(vec![-7.0]));
+        let norm = GradientClip::Value(1.0).apply(&mut [&mut a, &mut b]);
+        assert!((norm - (9.0f64 + 0.25 + 49.0).sqrt()).abs() < 1e-12);
+        assert_eq!(a.grad.data, vec![1.0, -0.5]);
+        assert_eq!(b.grad.data, vec![-1.0]);
+    }
+
+    #[test]
+    fn test_clip_by_global_norm_keeps_direction() {
+        // Global norm 5 across both parameters
+        let (mut a, mut b) = (with_grad(vec![3.0]), with_grad(vec![0.0, -4.0]));
+        assert_eq!(GradientClip::GlobalNorm(1.0).apply(&mut [&mut a, &mut b]), 5.0);
+        assert!((a.grad.data[0] - 0.6).abs() < 1e-12);
+        assert!((b.grad.data[1] + 0.8).abs() < 1e-12);
+        assert!((global_norm([&a, &b]) - 1.0).abs() < 1e-12);
+
+        // Gradients already inside the limit are untouched
+        let mut small = with_grad(vec![0.1, 0.2]);
+        GradientClip::GlobalNorm(1.0).apply(&mut [&mut small]);
+        assert_eq!(small.grad.data, vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_sequential_clips_before_the_update() {
+        let mut rng = Rng::new(6);
+        let inputs = Matrix::from_vec(16, 3, (0..48).map(|_| rng.uniform(-5.0, 5.0)).collect());
+        let targets = Matrix::from_vec(16, 1, (0..16).map(|i| (i % 2) as f64).collect());
+        let weights = vec![1.0; 16];
+
+        let before = Sequential::mlp_seeded(3, &[4], 1, 6);
+        let mut model = Sequential::mlp_seeded(3, &[4], 1, 6).with_gradient_clip(GradientClip::GlobalNorm(0.01));
+        model.train_step_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &mut Sgd::new(1.0));
+
+        // The whole update is lr * clipped gradient, so its norm is at most 0.01
+        let moved: f64 = model.parameters().iter().zip(before.parameters())
+            .flat_map(|(after, before)| after.value.data.iter().zip(&before.value.data).map(|(a, b)| (a - b) * (a - b)))
+            .sum();
+        assert!(moved.sqrt() <= 0.01 + 1e-12, "{}", moved.sqrt());
+        assert!(moved > 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "GradientClip::Value limit must be a non-negative number, got -1")]
+    fn test_value_limit_must_not_be_negative() {
+        GradientClip::Value(-1.0).apply(&mut [&mut with_grad(vec![0.5])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "GradientClip::Value limit must be a non-negative number, got NaN")]
+    fn test_value_limit_must_not_be_nan() {
+        GradientClip::Value(f64::NAN).apply(&mut [&mut with_grad(vec![0.5])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "GradientClip::GlobalNorm limit must be a non-negative number, got -1")]
+    fn test_global_norm_limit_must_not_be_negative() {
+        GradientClip::GlobalNorm(-1.0).apply(&mut [&mut with_grad(vec![0.5])]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by the integration tests; each test file uses only some of them
+#![allow(dead_code)]
+
+use ecgnn::matrix_math::Matrix;
+use ecgnn::rng::Rng;
+
+/// Values uniform in [-1, 1)
+pub fn random_matrix(rows: usize, cols: usize, rng: &mut Rng) -> Matrix {
+    Matrix::from_vec(rows, cols, (0..rows * cols).map(|_| rng.uniform(-1.0, 1.0)).collect())
+}
+
+/// Random inputs labelled 1 where `rule` holds, as (inputs, one-column targets, unit sample weights)
+pub fn binary_problem(samples: usize, features: usize, seed: u64, rule: impl Fn(&[f64]) -> bool) -> (Matrix, Matrix, Vec<f64>) {
+    let inputs = random_matrix(samples, features, &mut Rng::new(seed));
+    let targets = Matrix::from_vec(samples, 1, inputs.iter_rows().map(|x| if rule(x) { 1.0 } else { 0.0 }).collect());
+    (inputs, targets, vec![1.0; samples])
+}
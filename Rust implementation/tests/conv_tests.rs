@@ -0,0 +1,172 @@
+mod common;
+
+use ecgnn::conv::*;
+use ecgnn::gradient_check::{check_layer, DEFAULT_STEP};
+use ecgnn::init::Initializer;
+use ecgnn::layers::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::Adam;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+use common::random_matrix;
+
+fn config(in_channels: usize, out_channels: usize, kernel_size: usize, input_length: usize, stride: usize, padding: usize, dilation: usize) -> Conv1DConfig {
+    Conv1DConfig { stride, padding, dilation, ..Conv1DConfig::new(in_channels, out_channels, kernel_size, input_length) }
+}
+
+/// Direct nested-loop convolution of time-major rows
+fn naive_conv(layer: &Conv1D, input: &Matrix) -> Matrix {
+    let c = layer.config;
+    let output_length = c.output_length();
+    let mut output = Matrix::zeros(input.rows, c.output_size());
+    for b in 0..input.rows {
+        for p in 0..output_length {
+            for o in 0..c.out_channels {
+                let mut sum = layer.bias.value[(0, o)];
+                for k in 0..c.kernel_size {
+                    let t = (p * c.stride + k * c.dilation) as isize - c.padding as isize;
+                    if t < 0 || t >= c.input_length as isize {
+                        continue;
+                    }
+                    for i in 0..c.in_channels {
+                        sum += input[(b, t as usize * c.in_channels + i)] * layer.weights.value[(k * c.in_channels + i, o)];
+                    }
+                }
+                output[(b, p * c.out_channels + o)] = sum;
+            }
+        }
+    }
+    output
+}
+
+fn configs() -> Vec<Conv1DConfig> {
+    vec![
+        Conv1DConfig::new(1, 1, 3, 8),
+        config(2, 3, 3, 10, 2, 1, 1),
+        config(3, 2, 2, 9, 1, 2, 3),
+        config(2, 4, 5, 12, 3, 2, 2),
+    ]
+}
+
+#[cfg(test)]
+mod conv_tests {
+    use super::*;
+
+    #[test]
+    fn test_output_length() {
+        assert_eq!(Conv1DConfig::new(1, 1, 3, 10).output_length(), 8);
+        assert_eq!(config(1, 1, 3, 10, 2, 0, 1).output_length(), 4);
+        assert_eq!(config(1, 1, 3, 10, 1, 1, 1).output_length(), 10);
+        // Dilation 3 makes a 3-tap kernel span 7 steps
+        assert_eq!(config(1, 1, 3, 10, 1, 0, 3).output_length(), 4);
+        assert_eq!(config(2, 8, 7, 250, 2, 0, 1).output_size(), 122 * 8);
+    }
+
+    #[test]
+    fn test_im2col_layout() {
+        // Two channels, three steps: (t0: 1, 2), (t1: 3, 4), (t2: 5, 6)
+        let input = Matrix::from_vec(1, 6, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let padded = config(2, 1, 2, 3, 2, 1, 1);
+        let columns = im2col(&input, &padded);
+        assert_eq!((columns.rows, columns.cols), (2, 4));
+        assert_eq!(columns.data, vec![0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        // col2im sums back every copy of each input position
+        let ones = Matrix::filled(3, 6, 1.0);
+        let overlapping = Conv1DConfig::new(2, 1, 2, 3);
+        let counts = col2im(&im2col(&ones, &overlapping), 3, &overlapping);
+        assert_eq!(&counts.data[..6], &[1.0, 1.0, 2.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_forward_matches_direct_convolution() {
+        for (i, c) in configs().into_iter().enumerate() {
+            let mut layer: Conv1D = Conv1D::new(c, Initializer::Normal { mean: 0.0, std_dev: 1.0 }, &mut Rng::new(i as u64));
+            layer.bias.value = random_matrix(1, c.out_channels, &mut Rng::new(50 + i as u64));
+            let input = random_matrix(3, c.input_size(), &mut Rng::new(100 + i as u64));
+            let output = layer.forward(&input);
+            let expected = naive_conv(&layer, &input);
+            assert_eq!((output.rows, output.cols), (expected.rows, expected.cols));
+            for (a, e) in output.data.iter().zip(&expected.data) {
+                assert!((a - e).abs() < 1e-12, "{:?}: {} != {}", c, a, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gradients() {
+        for (i, c) in configs().into_iter().enumerate() {
+            let mut layer: Conv1D = Conv1D::new(c, Initializer::HeNormal, &mut Rng::new(i as u64));
+            layer.bias.value = random_matrix(1, c.out_channels, &mut Rng::new(60 + i as u64));
+            let report = check_layer(&mut layer, &random_matrix(2, c.input_size(), &mut Rng::new(200 + i as u64)), DEFAULT_STEP);
+            assert!(report.passes(1e-6), "{:?}\n{}", c, report);
+        }
+    }
+
+    #[test]
+    fn test_cnn_learns_a_shifted_pattern() {
+        // Class 1 windows contain a short spike at a random position, class 0 windows only noise
+        let (length, samples) = (24, 64);
+        let mut rng = Rng::new(11);
+        let mut inputs = Matrix::zeros(samples, length);
+        let mut labels = Vec::new();
+        for s in 0..samples {
+            for t in 0..length {
+                inputs[(s, t)] = rng.normal(0.0, 0.1);
+            }
+            let label = s % 2;
+            if label == 1 {
+                let at = rng.below(length - 3);
+                for (offset, height) in [1.0, 2.0, 1.0].into_iter().enumerate() {
+                    inputs[(s, at + offset)] += height;
+                }
+            }
+            labels.push(label as f64);
+        }
+        let targets = Matrix::from_vec(samples, 1, labels);
+
+        let mut rng = Rng::new(3);
+        let conv = config(1, 4, 3, length, 1, 1, 1);
+        let mut model = Sequential::new(Vec::new());
+        model.push(Conv1D::new(conv, Initializer::HeNormal, &mut rng));
+        model.push(ReLU::new());
+        model.push(Dense::with_initializer(conv.output_size(), 1, Initializer::XavierUniform, &mut rng));
+        model.push(Sigmoid::new());
+
+        let mut optimizer = Adam::new(0.01);
+        let weights = vec![1.0; samples];
+        let first = model.train_step_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &mut optimizer);
+        let mut last = first;
+        for _ in 0..150 {
+            last = model.train_step_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &mut optimizer);
+        }
+        assert!(last < 0.2 * first, "{} -> {}", first, last);
+    }
+
+    #[test]
+    fn test_f32_matches_f64() {
+        let c = config(2, 3, 3, 7, 2, 1, 2);
+        let mut layer: Conv1D = Conv1D::new(c, Initializer::HeNormal, &mut Rng::new(5));
+        let mut layer32: Conv1D<f32> = Conv1D::new(c, Initializer::HeNormal, &mut Rng::new(5));
+        let input = random_matrix(2, c.input_size(), &mut Rng::new(9));
+        let output = layer.forward(&input);
+        let output32 = layer32.forward(&input.cast());
+        for (a, b) in output.data.iter().zip(&output32.data) {
+            assert!((a - *b as f64).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Conv1D expects 20 values per row (2 channels x 10 steps), got 10")]
+    fn test_wrong_input_width() {
+        let mut layer: Conv1D = Conv1D::new(Conv1DConfig::new(2, 1, 3, 10), Initializer::HeNormal, &mut Rng::new(0));
+        layer.forward(&Matrix::zeros(1, 10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Conv1D kernel spans 9 steps but the padded input has only 8")]
+    fn test_kernel_longer_than_input() {
+        config(1, 1, 5, 6, 1, 1, 2).output_length();
+    }
+}
@@ -0,0 +1,117 @@
+use ecgnn::cross_validation::*;
+use ecgnn::matrix_math::Matrix;
+use ecgnn::split::{select, unique_records};
+
+fn assert_partition(folds: &[ecgnn::split::Split], n: usize) {
+    let mut seen = vec![0; n];
+    for split in folds {
+        assert_eq!(split.train.len() + split.test.len(), n);
+        for &i in &split.test {
+            seen[i] += 1;
+        }
+    }
+    assert!(seen.iter().all(|&count| count == 1), "every sample is tested exactly once");
+}
+
+#[cfg(test)]
+mod cross_validation_tests {
+    use super::*;
+
+    #[test]
+    fn test_stratified_k_fold_balances_classes() {
+        // 40 of class 0, 10 of class 1
+        let classes: Vec<usize> = (0..50).map(|i| if i % 5 == 0 { 1 } else { 0 }).collect();
+        let folds = stratified_k_fold(&classes, 5, 3);
+
+        assert_eq!(folds.len(), 5);
+        assert_partition(&folds, classes.len());
+        for split in &folds {
+            let minority = split.test.iter().filter(|&&i| classes[i] == 1).count();
+            assert_eq!(split.test.len(), 10);
+            assert_eq!(minority, 2);
+        }
+    }
+
+    #[test]
+    fn test_stratified_k_fold_is_seeded() {
+        let classes: Vec<usize> = (0..30).map(|i| i % 3).collect();
+        assert_eq!(stratified_k_fold(&classes, 3, 1), stratified_k_fold(&classes, 3, 1));
+        assert_ne!(stratified_k_fold(&classes, 3, 1), stratified_k_fold(&classes, 3, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cross-validation needs at least 2 folds")]
+    fn test_k_fold_needs_two_folds() {
+        stratified_k_fold(&[0, 1, 0], 1, 0);
+    }
+
+    #[test]
+    fn test_group_k_fold_keeps_patients_together() {
+        let record_ids: Vec<String> = (0..60).map(|i| format!("patient-{}", i % 7)).collect();
+        let classes = vec![0; 60];
+        let folds = k_fold(FoldStrategy::GroupByPatient, &classes, &record_ids, 3, 11);
+
+        assert_partition(&folds, record_ids.len());
+        for split in &folds {
+            let test_records = unique_records(&select(&record_ids, &split.test));
+            let train_records = unique_records(&select(&record_ids, &split.train));
+            assert!(!test_records.is_empty());
+            assert!(test_records.iter().all(|r| !train_records.contains(r)));
+        }
+    }
+
+    #[test]
+    fn test_summarize_mean_and_std() {
+        let fold_metrics = vec![
+            vec![("accuracy".to_string(), 0.8), ("loss".to_string(), 0.5)],
+            vec![("accuracy".to_string(), 0.9), ("loss".to_string(), 0.3)],
+            vec![("accuracy".to_string(), 1.0), ("loss".to_string(), 0.1)],
+        ];
+        let summary = summarize(&fold_metrics);
+
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].name, "accuracy");
+        assert!((summary[0].mean - 0.9).abs() < 1e-12);
+        assert!((summary[0].std_dev - 0.1).abs() < 1e-12);
+        assert_eq!(summary[1].values, vec![0.5, 0.3, 0.1]);
+    }
+
+    #[test]
+    fn test_classification_metrics() {
+        let predicted = vec![0, 0, 1, 1, 2];
+        let truth = vec![0, 1, 1, 1, 0];
+        let metrics = classification_metrics(&predicted, &truth, 3);
+        let get = |name: &str| metrics.iter().find(|(n, _)| n == name).unwrap().1;
+
+        assert!((get("accuracy") - 0.6).abs() < 1e-12);
+        // Sensitivities: class 0 = 1/2, class 1 = 2/3, class 2 = 0
+        assert!((get("macro_sensitivity") - (0.5 + 2.0 / 3.0) / 3.0).abs() < 1e-12);
+        // Precisions: class 0 = 1/2, class 1 = 1, class 2 = 0
+        assert!((get("macro_precision") - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cross_validate_trains_fresh_model_per_fold() {
+        // Two well-separated classes
+        let rows: Vec<Vec<f64>> = (0..40)
+            .map(|i| if i % 2 == 0 { vec![1.0, 0.0] } else { vec![0.0, 3.0] })
+            .collect();
+        let segments = Matrix::from_rows(&rows);
+        let classes: Vec<usize> = (0..40).map(|i| i % 2).collect();
+        let config = MlpConfig { num_hidden: 4, num_classes: 2, epochs: 100, learning_rate: 0.5, seed: 7 };
+
+        let folds = stratified_k_fold(&classes, 4, 0);
+        let mut calls = 0;
+        let summary = cross_validate(&folds, |_, split| {
+            calls += 1;
+            train_mlp_fold(&config, &segments, &classes, split)
+        });
+
+        assert_eq!(calls, 4);
+        let accuracy = summary.iter().find(|m| m.name == "accuracy").unwrap();
+        assert_eq!(accuracy.values.len(), 4);
+        assert_eq!(accuracy.mean, 1.0);
+        assert_eq!(accuracy.std_dev, 0.0);
+        assert!(summary.iter().any(|m| m.name == "loss"));
+    }
+}
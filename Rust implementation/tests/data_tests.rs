@@ -101,6 +101,22 @@ mod data_tests {
         cleanup_test_directory(&test_dir).expect("Failed to cleanup test directory");
     }
 
+    #[test]
+    fn test_load_records_keeps_files_apart() {
+        let test_dir = setup_test_directory().expect("Failed to setup test directory");
+        
+        let records = load_records(&test_dir).expect("Failed to load records");
+        
+        // Only the two CSV files, sorted by record ID
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "data1");
+        assert_eq!(records[1].id, "data2");
+        assert_eq!(records[0].data.len(), 4);
+        assert_eq!(records[1].data[0], vec![4.0, 5.0, 10.0]);
+        
+        cleanup_test_directory(&test_dir).expect("Failed to cleanup test directory");
+    }
+
     #[test]
     fn test_load_all_data_empty_directory() {
         // Create empty directory
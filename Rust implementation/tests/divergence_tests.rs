@@ -0,0 +1,133 @@
+mod common;
+
+use ecgnn::batching::BatchConfig;
+use ecgnn::divergence::*;
+use ecgnn::layers::{Dense, Param};
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::{Adam, Optimizer, Sgd};
+use ecgnn::schedule::{LinearWarmup, Scheduled, Scheduler};
+use ecgnn::sequential::Sequential;
+use common::binary_problem;
+
+fn values(model: &Sequential) -> Vec<Matrix> {
+    model.parameters().iter().map(|p| p.value.clone()).collect()
+}
+
+fn all_finite(model: &Sequential) -> bool {
+    model.parameters().iter().all(|p| p.value.data.iter().all(|x| x.is_finite()))
+}
+
+#[cfg(test)]
+mod divergence_tests {
+    use super::*;
+
+    #[test]
+    fn test_rollback_after_exploding_update() {
+        // A linear model with large inputs and targets has gradients far above 1,
+        // so the first step overflows
+        let (inputs, _, weights) = binary_problem(20, 3, 9, |x| x[1] > 0.0);
+        let (inputs, targets) = (scalar_multiply(&inputs, 1e3), Matrix::filled(20, 1, 1e3));
+        let mut model = Sequential::new(Vec::new());
+        model.push(Dense::from_parameters(Matrix::filled(3, 1, 0.01), Matrix::zeros(1, 1)));
+        let start = values(&model);
+        let mut guard = DivergenceGuard::new(Recovery::Rollback);
+        let divergence = model
+            .train_epoch_guarded(&inputs, &targets, &weights, Loss::MeanSquaredError, &BatchConfig::new(5, 1), 0, &mut Sgd::new(1e308), &mut guard)
+            .unwrap_err();
+
+        assert!(matches!(divergence.source, NonFinite::Parameter(ref name, _) if name == "layer 0 Dense weights"), "{:?}", divergence);
+        assert_eq!(divergence.batch, 0);
+        assert!(divergence.rolled_back);
+        assert_eq!(values(&model), start);
+    }
+
+    #[test]
+    fn test_abort_on_nan_loss_reports_batch() {
+        let (mut inputs, targets, weights) = binary_problem(20, 3, 9, |x| x[1] > 0.0);
+        // Unshuffled batches of 5: sample 12 is in batch 2
+        inputs[(12, 0)] = f64::NAN;
+        let mut batches = BatchConfig::new(5, 0);
+        batches.shuffle = false;
+        let mut model = Sequential::mlp_seeded(3, &[4], 1, 2);
+        let start = values(&model);
+        let mut guard = DivergenceGuard::new(Recovery::Abort);
+        let divergence = model
+            .train_epoch_guarded(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 3, &mut Sgd::new(0.1), &mut guard)
+            .unwrap_err();
+
+        assert!(matches!(divergence.source, NonFinite::Loss(x) if x.is_nan()));
+        assert_eq!((divergence.epoch, divergence.batch, divergence.rolled_back), (3, 2, false));
+        // The two good batches were applied and the bad one never reached the weights
+        assert_ne!(values(&model), start);
+        assert!(all_finite(&model));
+    }
+
+    #[test]
+    fn test_rollback_restores_optimizer_state() {
+        let (inputs, targets, weights) = binary_problem(20, 3, 9, |x| x[1] > 0.0);
+        let batches = BatchConfig::new(5, 4);
+        let mut model = Sequential::mlp_seeded(3, &[4], 1, 4);
+        let mut optimizer = Adam::new(0.01);
+        let mut guard = DivergenceGuard::new(Recovery::Rollback);
+        let loss = model.train_epoch_guarded(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 0, &mut optimizer, &mut guard).unwrap();
+        assert!(loss.is_finite());
+        let (good_values, good_state) = (values(&model), optimizer.state());
+
+        optimizer.learning_rate = 1e305;
+        let divergence = model
+            .train_epoch_guarded(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 1, &mut optimizer, &mut guard)
+            .unwrap_err();
+        assert!(divergence.rolled_back);
+        assert_eq!(values(&model), good_values);
+        // The checkpoint is taken at the start of the epoch, so it has the raised learning rate
+        let restored = optimizer.state();
+        assert_eq!((restored.step, &restored.buffers), (good_state.step, &good_state.buffers));
+        assert_eq!(optimizer.learning_rate, 1e305);
+    }
+
+    #[test]
+    fn test_rollback_restores_schedule_position() {
+        let (inputs, targets, weights) = binary_problem(20, 3, 9, |x| x[1] > 0.0);
+        let batches = BatchConfig::new(5, 4);
+        let mut model = Sequential::mlp_seeded(3, &[4], 1, 4);
+        let mut optimizer = Scheduled::new(Adam::new(0.0), LinearWarmup::new(0.01, 100, 0.5));
+        let mut guard = DivergenceGuard::new(Recovery::Rollback);
+        model.train_epoch_guarded(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 0, &mut optimizer, &mut guard).unwrap();
+        let good = optimizer.scheduler.state();
+
+        optimizer.scheduler.target = 1e305;
+        model
+            .train_epoch_guarded(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 1, &mut optimizer, &mut guard)
+            .unwrap_err();
+        assert_eq!(optimizer.scheduler.state(), good);
+        assert_eq!(good.value("step").unwrap(), 4.0);
+    }
+
+    #[test]
+    fn test_non_finite_gradient_names_parameter() {
+        let ok: Param = Param::new("weights", Matrix::zeros(2, 2));
+        let mut bad = Param::new("bias", Matrix::zeros(1, 2));
+        bad.grad.data[1] = f64::INFINITY;
+        let names = vec!["layer 0 Dense weights".to_string(), "layer 0 Dense bias".to_string()];
+
+        let found = NonFinite::in_gradients(&names, &[&ok, &bad]).unwrap();
+        assert_eq!(found, NonFinite::Gradient("layer 0 Dense bias".to_string(), f64::INFINITY));
+        assert_eq!(NonFinite::in_values(&names, &[&ok, &bad]), None);
+
+        let divergence = Divergence { epoch: 4, batch: 17, source: found, rolled_back: true };
+        assert_eq!(
+            divergence.to_string(),
+            "Training diverged in epoch 4, batch 17: gradient of layer 0 Dense bias is inf; rolled back to the start of the epoch"
+        );
+    }
+
+    #[test]
+    fn test_parameter_names() {
+        let model: Sequential = Sequential::mlp_seeded(3, &[4], 2, 0);
+        assert_eq!(
+            model.parameter_names(),
+            ["layer 0 Dense weights", "layer 0 Dense bias", "layer 2 Dense weights", "layer 2 Dense bias"]
+        );
+    }
+}
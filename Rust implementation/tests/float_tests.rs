@@ -0,0 +1,110 @@
+mod common;
+
+use ecgnn::brains::*;
+use ecgnn::float::{cast_slice, Float};
+use ecgnn::init::Initializer;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use common::random_matrix;
+
+/// Three linearly separable classes: the class is the position of the largest feature
+fn three_class_problem<T: Float>() -> (Matrix<T>, Matrix<T>, Vec<usize>) {
+    let mut segments = Matrix::filled(30, 3, T::from_f64(0.1));
+    let mut targets = Matrix::zeros(30, 3);
+    let classes: Vec<usize> = (0..30).map(|i| i % 3).collect();
+    for (i, &c) in classes.iter().enumerate() {
+        segments[(i, c)] = T::ONE;
+        targets[(i, c)] = T::ONE;
+    }
+    (segments, targets, classes)
+}
+
+#[cfg(test)]
+mod float_tests {
+    use super::*;
+
+    #[test]
+    fn test_f32_kernels_match_f64() {
+        let mut rng = Rng::new(1);
+        // Large enough to take the blocked GEMM path
+        let a = random_matrix(67, 130, &mut rng);
+        let b = random_matrix(130, 45, &mut rng);
+        let (a32, b32) = (a.cast::<f32>(), b.cast::<f32>());
+
+        let product = matrix_multiply(&a32, &b32).cast::<f64>();
+        for (x, y) in product.data.iter().zip(matrix_multiply(&a, &b).data.iter()) {
+            assert!((x - y).abs() < 1e-4, "{} != {}", x, y);
+        }
+
+        let (x, y) = (a.row(0), b.col(0).copied().collect::<Vec<_>>());
+        let dot = dot_product(&cast_slice::<f64, f32>(x), &cast_slice(&y));
+        assert!((dot as f64 - dot_product(x, &y)).abs() < 1e-4);
+
+        assert_eq!(matrix_add(&a32, &a32), scalar_multiply(&a32, 2.0f32));
+        assert_eq!(apply_relu(&[-1.0f32, 0.5, f32::NAN])[..2], [0.0, 0.5]);
+    }
+
+    #[test]
+    fn test_cast_round_trips_through_f32() {
+        let matrix = Matrix::from(vec![vec![0.5, -2.0], vec![1e-3, 3.0]]);
+        assert_eq!(matrix.cast::<f32>().data, vec![0.5f32, -2.0, 1e-3, 3.0]);
+        assert_eq!(matrix.cast::<f32>().cast::<f64>().shape(), (2, 2));
+        // Exactly representable values survive the round trip
+        assert_eq!(cast_slice::<f32, f64>(&cast_slice::<f64, f32>(&[0.5, -2.0])), vec![0.5, -2.0]);
+    }
+
+    #[test]
+    fn test_f32_losses_stay_finite_at_saturation() {
+        // 1 - 1e-15 rounds to 1 in f32, so the clamp must use a wider margin
+        let loss = loss_function(&[1.0f32, 0.0], &[0.0, 1.0]);
+        assert!(loss.is_finite() && loss > 10.0);
+        assert!(find_gradient(&[1.0f32], &[0.0]).iter().all(|g| g.is_finite()));
+    }
+
+    #[test]
+    fn test_train_in_f64_and_deploy_in_f32() {
+        let (segments, targets, classes) = three_class_problem::<f64>();
+        let (mut w1, mut b1) = initialize_weights_and_bias(3, 4, Initializer::Constant(0.01), &mut Rng::new(0));
+        for (i, row) in w1.iter_rows_mut().enumerate() {
+            for (j, w) in row.iter_mut().enumerate() {
+                *w = if i == j { 1.0 } else { -0.1 };
+            }
+        }
+        let (mut w2, mut b2) = initialize_weights_and_bias(4, 3, Initializer::Constant(0.01), &mut Rng::new(0));
+        for _ in 0..200 {
+            train_epoch_multiclass(&segments, &targets, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+        }
+
+        let (segments32, _, _) = three_class_problem::<f32>();
+        let probabilities = predict_proba_batch(&segments, &w1, &b1, &w2, &b2);
+        let probabilities32 = predict_proba_batch(&segments32, &w1.cast(), &cast_slice(&b1), &w2.cast(), &cast_slice(&b2));
+        for (p, p32) in probabilities.data.iter().zip(probabilities32.data.iter()) {
+            assert!((p - *p32 as f64).abs() < 1e-5);
+        }
+
+        let predicted = predict_class_batch(&segments32, &w1.cast(), &cast_slice(&b1), &w2.cast(), &cast_slice(&b2));
+        assert_eq!(calculate_multiclass_accuracy(&predicted, &classes), 1.0);
+    }
+
+    #[test]
+    fn test_f32_network_trains() {
+        let (segments, targets, classes) = three_class_problem::<f32>();
+        let (mut w1, mut b1) = initialize_weights_and_bias(3, 4, Initializer::Constant(0.01), &mut Rng::new(0));
+        for (i, row) in w1.iter_rows_mut().enumerate() {
+            for (j, w) in row.iter_mut().enumerate() {
+                *w = if i == j { 1.0f32 } else { -0.1 };
+            }
+        }
+        let (mut w2, mut b2) = initialize_weights_and_bias(4, 3, Initializer::Constant(0.01), &mut Rng::new(0));
+
+        let first = train_epoch_multiclass(&segments, &targets, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+        let mut last = first;
+        for _ in 0..200 {
+            last = train_epoch_multiclass(&segments, &targets, &mut w1, &mut b1, &mut w2, &mut b2, 0.5);
+        }
+
+        assert!(last < first / 4.0);
+        let predicted = predict_class_batch(&segments, &w1, &b1, &w2, &b2);
+        assert_eq!(calculate_multiclass_accuracy(&predicted, &classes), 1.0);
+    }
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use ecgnn::gemm::gemm;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use common::random_matrix;
+
+fn assert_close(actual: &Matrix, expected: &Matrix) {
+    assert_eq!(actual.shape(), expected.shape());
+    for (x, y) in actual.data.iter().zip(expected.data.iter()) {
+        assert!((x - y).abs() < 1e-9 * (1.0 + y.abs()), "{} != {}", x, y);
+    }
+}
+
+#[cfg(test)]
+mod gemm_tests {
+    use super::*;
+
+    #[test]
+    fn test_blocked_matches_naive_on_ragged_shapes() {
+        let mut rng = Rng::new(1);
+        // Sizes straddling the register tile (4 x 8) and the cache blocks (64, 256, 1024)
+        let shapes = [(1, 750, 64), (750, 1, 64), (5, 9, 17), (63, 257, 65), (67, 300, 33), (130, 40, 1030)];
+        for (m, k, n) in shapes {
+            let a = random_matrix(m, k, &mut rng);
+            let b = random_matrix(k, n, &mut rng);
+            assert_close(&matrix_multiply(&a, &b), &matrix_multiply_naive(&a, &b));
+        }
+    }
+
+    #[test]
+    fn test_gemm_accumulates_into_c() {
+        let mut rng = Rng::new(2);
+        let a = random_matrix(40, 50, &mut rng);
+        let b = random_matrix(50, 60, &mut rng);
+        let mut c = Matrix::filled(40, 60, 1.0);
+
+        gemm(a.view(), b.view(), &mut c);
+        assert_close(&c, &matrix_add(&matrix_multiply_naive(&a, &b), &Matrix::filled(40, 60, 1.0)));
+    }
+
+    #[test]
+    fn test_gemm_works_on_views() {
+        let mut rng = Rng::new(3);
+        let a = random_matrix(100, 70, &mut rng);
+        let b = random_matrix(70, 50, &mut rng);
+        let top = a.slice_rows(10..90);
+        assert_close(&matrix_multiply(top, &b), &matrix_multiply_naive(top, &b));
+    }
+
+    #[test]
+    #[should_panic(expected = "Matrix dimensions do not match for multiplication")]
+    fn test_gemm_checks_output_shape() {
+        let a: Matrix = Matrix::zeros(2, 3);
+        let b = Matrix::zeros(3, 4);
+        let mut c = Matrix::zeros(2, 3);
+        gemm(a.view(), b.view(), &mut c);
+    }
+}
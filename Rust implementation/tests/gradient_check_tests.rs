@@ -0,0 +1,230 @@
+mod common;
+
+use ecgnn::autodiff::Tape;
+use ecgnn::brains::*;
+use ecgnn::gradient_check::*;
+use ecgnn::layers::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+use common::random_matrix;
+
+const TOLERANCE: f64 = 1e-6;
+
+/// `Sequential::mlp_seeded` with every parameter, biases included, uniform in [-1, 1)
+fn random_mlp(num_features: usize, hidden_sizes: &[usize], num_outputs: usize, rng: &mut Rng) -> Sequential {
+    let mut model = Sequential::mlp_seeded(num_features, hidden_sizes, num_outputs, 0);
+    for param in model.parameters_mut() {
+        param.value = random_matrix(param.value.rows, param.value.cols, rng);
+    }
+    model
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn assert_passes(report: &GradientCheck) {
+    assert!(report.passes(TOLERANCE), "gradient check failed:\n{}", report);
+}
+
+#[cfg(test)]
+mod gradient_check_tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_layer() {
+        let mut rng = Rng::new(1);
+        let mut layer = Dense::from_parameters(random_matrix(4, 3, &mut rng), random_matrix(1, 3, &mut rng));
+        let report = check_layer(&mut layer, &random_matrix(5, 4, &mut rng), DEFAULT_STEP);
+        assert_eq!(report.params.len(), 3);
+        assert_eq!(report.params[1].name, "Dense weights");
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_activation_layers() {
+        let mut rng = Rng::new(2);
+        let layers: Vec<Box<dyn Layer>> = vec![Box::new(ReLU::new()), Box::new(Sigmoid::new()), Box::new(Softmax::new())];
+        for mut layer in layers {
+            let report = check_layer(layer.as_mut(), &random_matrix(5, 4, &mut rng), DEFAULT_STEP);
+            assert_eq!(report.params.len(), 1);
+            assert_passes(&report);
+        }
+    }
+
+    #[test]
+    fn test_sequential_with_every_loss() {
+        let mut rng = Rng::new(3);
+        let inputs = random_matrix(6, 5, &mut rng);
+        let weights = [1.0, 2.0, 1.0, 0.5, 1.0, 3.0];
+
+        let mut binary = random_mlp(5, &[4, 3], 1, &mut rng);
+        let labels = Matrix::from_vec(6, 1, vec![1.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+        let report = check_sequential(&mut binary, &inputs, &labels, &weights, Loss::BinaryCrossEntropy, DEFAULT_STEP);
+        assert_eq!(report.params.len(), 6);
+        assert_eq!(report.params[2].name, "layer 2 Dense weights");
+        assert_passes(&report);
+
+        let mut multiclass = random_mlp(5, &[4], 3, &mut rng);
+        let mut targets = Matrix::zeros(6, 3);
+        for i in 0..6 {
+            targets[(i, i % 3)] = 1.0;
+        }
+        assert_passes(&check_sequential(&mut multiclass, &inputs, &targets, &weights, Loss::CategoricalCrossEntropy, DEFAULT_STEP));
+
+        let mut regression = random_mlp(5, &[4], 2, &mut rng);
+        let targets = random_matrix(6, 2, &mut rng);
+        assert_passes(&check_sequential(&mut regression, &inputs, &targets, &weights, Loss::MeanSquaredError, DEFAULT_STEP));
+    }
+
+    #[test]
+    fn test_check_restores_parameters() {
+        let mut rng = Rng::new(4);
+        let mut model = random_mlp(3, &[2], 2, &mut rng);
+        let before: Vec<Matrix> = model.parameters().iter().map(|p| p.value.clone()).collect();
+        let inputs = random_matrix(2, 3, &mut rng);
+        let targets = Matrix::from(vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        check_sequential(&mut model, &inputs, &targets, &[1.0, 1.0], Loss::CategoricalCrossEntropy, DEFAULT_STEP);
+        let after: Vec<Matrix> = model.parameters().iter().map(|p| p.value.clone()).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_brains_backprop_is_gradient_of_loss_function() {
+        let mut rng = Rng::new(5);
+        let segments = random_matrix(6, 4, &mut rng);
+        let labels = vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0];
+        let values = vec![random_matrix(4, 3, &mut rng), random_matrix(1, 3, &mut rng), random_matrix(3, 1, &mut rng), random_matrix(1, 1, &mut rng)];
+
+        let (gw1, gb1, gw2, gb2) = backprop(&segments, &values[0], &values[1].data, &values[2].data, values[3].data[0], &labels);
+        let analytic = vec![gw1, Matrix::from_vec(1, 3, gb1), Matrix::from_vec(3, 1, gw2), Matrix::filled(1, 1, gb2)];
+        let report = check_gradients(&names(&["w1", "b1", "w2", "b2"]), &values, &analytic, |v| {
+            let (outputs, _) = forward_pass_batch(&segments, &v[0], &v[1].data, &v[2].data, v[3].data[0]);
+            loss_function(&outputs, &labels)
+        }, DEFAULT_STEP);
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_brains_multiclass_backprop_is_gradient_of_cross_entropy() {
+        let mut rng = Rng::new(6);
+        let segments = random_matrix(6, 4, &mut rng);
+        let mut targets = Matrix::zeros(6, 3);
+        for i in 0..6 {
+            targets[(i, (i * 2) % 3)] = 1.0;
+        }
+        let values = vec![random_matrix(4, 5, &mut rng), random_matrix(1, 5, &mut rng), random_matrix(5, 3, &mut rng), random_matrix(1, 3, &mut rng)];
+
+        let (gw1, gb1, gw2, gb2) = backprop_multiclass(&segments, &values[0], &values[1].data, &values[2], &values[3].data, &targets);
+        let analytic = vec![gw1, Matrix::from_vec(1, 5, gb1), gw2, Matrix::from_vec(1, 3, gb2)];
+        let report = check_gradients(&names(&["w1", "b1", "w2", "b2"]), &values, &analytic, |v| {
+            categorical_cross_entropy(&predict_proba_batch(&segments, &v[0], &v[1].data, &v[2], &v[3].data), &targets)
+        }, DEFAULT_STEP);
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_autodiff_tape() {
+        let mut rng = Rng::new(7);
+        let values = vec![random_matrix(4, 3, &mut rng), random_matrix(3, 2, &mut rng)];
+        let targets = Matrix::from(vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0], vec![0.0, 0.0]]);
+        let record = |v: &[Matrix]| {
+            let mut tape = Tape::new();
+            let (a, b) = (tape.input(v[0].clone()), tape.input(v[1].clone()));
+            let product = tape.matmul(a, b);
+            let squashed = tape.sigmoid(product);
+            let logits = tape.mul(squashed, product);
+            let loss = tape.sigmoid_cross_entropy(logits, &targets, &[1.0; 4]);
+            (tape, [a, b], loss)
+        };
+
+        let (tape, vars, loss) = record(&values);
+        let grads = tape.backward(loss);
+        let analytic: Vec<Matrix> = vars.iter().map(|&v| grads[v].clone()).collect();
+        let report = check_gradients(&names(&["a", "b"]), &values, &analytic, |v| {
+            let (tape, _, loss) = record(v);
+            tape.value(loss).data[0]
+        }, DEFAULT_STEP);
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_saturated_softmax_with_cross_entropy() {
+        // Logits 60 apart put the target class (the last) far below epsilon, where the
+        // clamped -t / p derivative through the softmax Jacobian would lose the gradient
+        let inputs = Matrix::from(vec![vec![1.0, 0.3], vec![0.5, -0.2], vec![0.8, 1.0], vec![0.6, -0.7]]);
+        let weights = Matrix::from(vec![vec![60.0, 59.0, -60.0], vec![0.5, -0.5, 0.0]]);
+        let bias = Matrix::from(vec![vec![0.0, 0.1, 0.0]]);
+        let mut targets = Matrix::zeros(4, 3);
+        for i in 0..4 {
+            targets[(i, 2)] = 1.0;
+        }
+        let mut model = Sequential::new(Vec::new());
+        model.push(Dense::from_parameters(weights.clone(), bias.clone()));
+        model.push(Softmax::new());
+        model.compute_gradients(&inputs, &targets, &[1.0; 4], Loss::CategoricalCrossEntropy);
+        assert!(model.forward(&inputs).col(2).all(|&p| p < 1e-15));
+        let analytic: Vec<Matrix> = model.gradients().into_iter().cloned().collect();
+
+        // Reference: cross-entropy computed from the logits with log-sum-exp, which never saturates
+        let report = check_gradients(&names(&["weights", "bias"]), &[weights, bias], &analytic, |v| {
+            let mut logits = matrix_multiply(&inputs, &v[0]);
+            add_row_vector_inplace(&mut logits, &v[1].data);
+            logits.iter_rows().zip(targets.iter_rows()).map(|(z, t)| {
+                let max = z.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let log_sum = max + z.iter().map(|&x| (x - max).exp()).sum::<f64>().ln();
+                z.iter().zip(t).map(|(&x, &t)| t * (log_sum - x)).sum::<f64>()
+            }).sum::<f64>() / 4.0
+        }, DEFAULT_STEP);
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_saturated_sigmoid_with_binary_cross_entropy() {
+        // |z| = 40 rounds the sigmoid to exactly 0 or 1, where y(1 - y) in Sigmoid::backward is zero;
+        // every sample is confidently wrong, so the true gradient is about 1 per sample
+        let inputs = Matrix::from(vec![vec![1.0], vec![-1.0], vec![1.2], vec![-1.1]]);
+        let targets = Matrix::from(vec![vec![0.0], vec![1.0], vec![0.0], vec![1.0]]);
+        let (weights, bias) = (Matrix::filled(1, 1, 40.0), Matrix::filled(1, 1, 0.0));
+        let mut model = Sequential::new(Vec::new());
+        model.push(Dense::from_parameters(weights.clone(), bias.clone()));
+        model.push(Sigmoid::new());
+        model.compute_gradients(&inputs, &targets, &[1.0; 4], Loss::BinaryCrossEntropy);
+        assert!(model.forward(&inputs).data.iter().all(|&y| y == 1.0 || y < 1e-15));
+        let analytic: Vec<Matrix> = model.gradients().into_iter().cloned().collect();
+        assert!(analytic[0].data[0] > 0.9);
+
+        // Reference: max(z, 0) - z t + ln(1 + e^-|z|), binary cross-entropy from the logits
+        let report = check_gradients(&names(&["weights", "bias"]), &[weights, bias], &analytic, |v| {
+            inputs.data.iter().zip(&targets.data).map(|(&x, &t)| {
+                let z = x * v[0].data[0] + v[1].data[0];
+                z.max(0.0) - z * t + (-z.abs()).exp().ln_1p()
+            }).sum::<f64>() / 4.0
+        }, DEFAULT_STEP);
+        assert_passes(&report);
+    }
+
+    #[test]
+    fn test_reports_wrong_gradient() {
+        let values = vec![Matrix::from(vec![vec![1.0, 2.0, 3.0]])];
+        // d/dx sum(x^2) is 2x; the last element is deliberately wrong
+        let analytic = vec![Matrix::from(vec![vec![2.0, 4.0, 5.0]])];
+        let report = check_gradients(&names(&["x"]), &values, &analytic, |v| v[0].data.iter().map(|x| x * x).sum(), DEFAULT_STEP);
+
+        assert!(!report.passes(TOLERANCE));
+        let worst = report.worst().unwrap();
+        assert_eq!(worst.worst_element, 2);
+        assert!((worst.numerical - 6.0).abs() < 1e-6);
+        assert!((report.max_relative_error() - 1.0 / 6.0).abs() < 1e-6);
+        assert!(report.to_string().starts_with("x: max relative error"));
+    }
+
+    #[test]
+    fn test_relative_error() {
+        assert_eq!(relative_error(0.0, 0.0), 0.0);
+        assert!((relative_error(1.0, 1.1) - 0.1 / 1.1).abs() < 1e-12);
+        assert!(relative_error(1e-12, 0.0) < 1e-3);
+    }
+}
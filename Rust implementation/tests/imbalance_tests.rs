@@ -0,0 +1,85 @@
+use ecgnn::imbalance::*;
+
+// 12 of class 0, 3 of class 1, 1 of class 2
+fn skewed_classes() -> Vec<usize> {
+    let mut classes = vec![0; 12];
+    classes.extend([1, 1, 1, 2]);
+    classes
+}
+
+fn counts_of(classes: &[usize], indices: &[usize]) -> Vec<usize> {
+    let selected: Vec<usize> = indices.iter().map(|&i| classes[i]).collect();
+    class_counts(&selected, 3)
+}
+
+#[cfg(test)]
+mod imbalance_tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_frequency_weights() {
+        let weights = inverse_frequency_weights(&skewed_classes(), 4);
+        assert!((weights[0] - 16.0 / 48.0).abs() < 1e-12);
+        assert!((weights[1] - 16.0 / 12.0).abs() < 1e-12);
+        assert!((weights[2] - 16.0 / 4.0).abs() < 1e-12);
+        // Absent class
+        assert_eq!(weights[3], 0.0);
+
+        let balanced = inverse_frequency_weights(&[0, 1, 0, 1], 2);
+        assert_eq!(balanced, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_random_oversample_balances_and_keeps_originals() {
+        let classes = skewed_classes();
+        let indices = random_oversample(&classes, 5);
+
+        assert_eq!(counts_of(&classes, &indices), vec![12, 12, 12]);
+        assert!((0..classes.len()).all(|i| indices.contains(&i)));
+        assert_eq!(indices, random_oversample(&classes, 5));
+    }
+
+    #[test]
+    fn test_random_undersample_balances_without_repeats() {
+        let classes = skewed_classes();
+        let indices = random_undersample(&classes, 5);
+
+        assert_eq!(counts_of(&classes, &indices), vec![1, 1, 1]);
+        let mut unique = indices.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), indices.len());
+    }
+
+    #[test]
+    fn test_smote_interpolates_within_class() {
+        let mut features = Vec::new();
+        let mut classes = Vec::new();
+        for i in 0..10 {
+            features.push(vec![i as f64, 0.0]);
+            classes.push(0);
+        }
+        for i in 0..3 {
+            features.push(vec![100.0 + i as f64, 50.0]);
+            classes.push(1);
+        }
+
+        let (synthetic, synthetic_classes) = smote(&features, &classes, 2, 9);
+        assert_eq!(synthetic.len(), 7);
+        assert!(synthetic_classes.iter().all(|&c| c == 1));
+        for sample in &synthetic {
+            // On a segment between two minority samples
+            assert!(sample[0] >= 100.0 && sample[0] <= 102.0);
+            assert_eq!(sample[1], 50.0);
+        }
+        assert_eq!(smote(&features, &classes, 2, 9).0, synthetic);
+    }
+
+    #[test]
+    fn test_smote_single_sample_class_is_copied() {
+        let features = vec![vec![0.0], vec![1.0], vec![7.0]];
+        let (synthetic, classes) = smote(&features, &[0, 0, 1], 5, 1);
+        assert_eq!(synthetic, vec![vec![7.0]]);
+        assert_eq!(classes, vec![1]);
+    }
+}
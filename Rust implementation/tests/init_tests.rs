@@ -0,0 +1,152 @@
+use ecgnn::brains::*;
+use ecgnn::init::Initializer;
+use ecgnn::layers::Dense;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    (mean, variance)
+}
+
+fn assert_identity(product: &Matrix, scale: f64) {
+    for i in 0..product.rows {
+        for j in 0..product.cols {
+            let expected = if i == j { scale } else { 0.0 };
+            assert!((product[(i, j)] - expected).abs() < 1e-10, "({}, {}) = {}", i, j, product[(i, j)]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod init_tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_gives_same_weights() {
+        let all = [
+            Initializer::Constant(0.5),
+            Initializer::Uniform { low: -0.1, high: 0.2 },
+            Initializer::Normal { mean: 1.0, std_dev: 0.1 },
+            Initializer::XavierUniform,
+            Initializer::XavierNormal,
+            Initializer::HeUniform,
+            Initializer::HeNormal,
+            Initializer::Orthogonal { gain: 1.0 },
+        ];
+        for initializer in all {
+            let a: Matrix = initializer.weights(7, 5, &mut Rng::new(3));
+            let b: Matrix = initializer.weights(7, 5, &mut Rng::new(3));
+            assert_eq!(a, b, "{:?}", initializer);
+            assert_eq!(a.shape(), (7, 5));
+            if initializer != Initializer::Constant(0.5) {
+                let c: Matrix = initializer.weights(7, 5, &mut Rng::new(4));
+                assert_ne!(a, c, "{:?}", initializer);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fan_based_scales() {
+        let mut rng = Rng::new(1);
+        let (fan_in, fan_out) = (200, 100);
+
+        let xavier: Matrix = Initializer::XavierUniform.weights(fan_in, fan_out, &mut rng);
+        let limit = (6.0f64 / 300.0).sqrt();
+        assert!(xavier.data.iter().all(|w| w.abs() <= limit));
+        let (mean, variance) = mean_and_variance(&xavier.data);
+        assert!(mean.abs() < 0.005);
+        assert!((variance / (2.0 / 300.0) - 1.0).abs() < 0.05);
+
+        let he: Matrix = Initializer::HeNormal.weights(fan_in, fan_out, &mut rng);
+        let (mean, variance) = mean_and_variance(&he.data);
+        assert!(mean.abs() < 0.005);
+        assert!((variance / (2.0 / 200.0) - 1.0).abs() < 0.05);
+
+        let he_uniform: Matrix = Initializer::HeUniform.weights(fan_in, fan_out, &mut rng);
+        assert!(he_uniform.data.iter().all(|w| w.abs() <= (6.0f64 / 200.0).sqrt()));
+
+        let uniform: Matrix = Initializer::Uniform { low: 2.0, high: 3.0 }.weights(10, 10, &mut rng);
+        assert!(uniform.data.iter().all(|&w| (2.0..3.0).contains(&w)));
+    }
+
+    #[test]
+    fn test_orthogonal_weights() {
+        let mut rng = Rng::new(2);
+        let tall: Matrix = Initializer::Orthogonal { gain: 1.0 }.weights(8, 3, &mut rng);
+        assert_identity(&matrix_multiply(&matrix_transpose(&tall), &tall), 1.0);
+
+        let wide: Matrix = Initializer::Orthogonal { gain: 2.0 }.weights(3, 8, &mut rng);
+        assert_identity(&matrix_multiply(&wide, &matrix_transpose(&wide)), 4.0);
+
+        let square: Matrix = Initializer::Orthogonal { gain: 1.0 }.weights(5, 5, &mut rng);
+        assert_identity(&matrix_multiply(&square, &matrix_transpose(&square)), 1.0);
+    }
+
+    #[test]
+    fn test_f32_weights_match_f64_draw() {
+        let w64: Matrix = Initializer::HeNormal.weights(4, 4, &mut Rng::new(9));
+        let w32: Matrix<f32> = Initializer::HeNormal.weights(4, 4, &mut Rng::new(9));
+        assert_eq!(w64.cast::<f32>(), w32);
+    }
+
+    #[test]
+    fn test_random_init_breaks_hidden_unit_symmetry() {
+        let mut rng = Rng::new(5);
+        let inputs = Matrix::from_vec(6, 3, (0..18).map(|_| rng.uniform(-1.0, 1.0)).collect());
+        let targets = Matrix::from_vec(6, 1, vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        let hidden_columns_equal = |model: &Sequential| {
+            let weights = &model.parameters()[0].value;
+            (1..weights.cols).all(|j| weights.col(j).eq(weights.col(0)))
+        };
+
+        // With constant weights every hidden unit gets the same gradient, forever
+        let constant_init = [Initializer::Constant(0.01), Initializer::Constant(0.01)];
+        let mut constant = Sequential::mlp_with_initializers(3, &[4], 1, &constant_init, &mut Rng::new(0));
+        let mut seeded = Sequential::mlp_seeded(3, &[4], 1, 11);
+        for _ in 0..20 {
+            constant.train_step(&inputs, &targets, &[1.0; 6], Loss::BinaryCrossEntropy, 0.5);
+            seeded.train_step(&inputs, &targets, &[1.0; 6], Loss::BinaryCrossEntropy, 0.5);
+        }
+        assert!(hidden_columns_equal(&constant));
+        assert!(!hidden_columns_equal(&seeded));
+    }
+
+    #[test]
+    fn test_seeded_models_are_reproducible() {
+        let a = Sequential::mlp_seeded(5, &[4, 3], 2, 42);
+        let b = Sequential::mlp_seeded(5, &[4, 3], 2, 42);
+        let c = Sequential::mlp_seeded(5, &[4, 3], 2, 43);
+        let values = |model: &Sequential| model.parameters().iter().map(|p| p.value.clone()).collect::<Vec<_>>();
+        assert_eq!(values(&a), values(&b));
+        assert_ne!(values(&a), values(&c));
+        // Biases start at zero
+        assert!(a.parameters()[1].value.data.iter().all(|&b| b == 0.0));
+    }
+
+    #[test]
+    fn test_initializer_per_layer() {
+        let initializers = [Initializer::Constant(1.0), Initializer::Orthogonal { gain: 1.0 }, Initializer::Constant(-2.0)];
+        let model = Sequential::mlp_with_initializers(3, &[4, 4], 2, &initializers, &mut Rng::new(0));
+        let names: Vec<&str> = model.layers.iter().map(|layer| layer.name()).collect();
+        assert_eq!(names, vec!["Dense", "ReLU", "Dense", "ReLU", "Dense", "Softmax"]);
+        let params = model.parameters();
+        assert!(params[0].value.data.iter().all(|&w| w == 1.0));
+        assert_identity(&matrix_multiply(&matrix_transpose(&params[2].value), &params[2].value), 1.0);
+        assert!(params[4].value.data.iter().all(|&w| w == -2.0));
+
+        let layer: Dense = Dense::with_initializer(3, 2, Initializer::HeUniform, &mut Rng::new(0));
+        assert_eq!(layer.bias.value, Matrix::zeros(1, 2));
+        let (weights, bias) = initialize_weights_and_bias::<f64>(3, 2, Initializer::HeUniform, &mut Rng::new(0));
+        assert_eq!((weights, bias), (layer.weights.value, vec![0.0; 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "An MLP with 1 hidden layers needs 2 initializers, got 1")]
+    fn test_initializer_count_must_match_layers() {
+        let _: Sequential = Sequential::mlp_with_initializers(3, &[4], 2, &[Initializer::HeNormal], &mut Rng::new(0));
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs::{self, File};
+use std::io::Write;
+use ecgnn::labels::*;
+
+#[cfg(test)]
+mod labels_tests {
+    use super::*;
+
+    #[test]
+    fn test_aami_class_mapping() {
+        for symbol in ['N', 'L', 'R', 'e', 'j'] {
+            assert_eq!(aami_class(symbol), Some(AamiClass::N));
+        }
+        for symbol in ['A', 'a', 'J', 'S'] {
+            assert_eq!(aami_class(symbol), Some(AamiClass::S));
+        }
+        for symbol in ['V', 'E'] {
+            assert_eq!(aami_class(symbol), Some(AamiClass::V));
+        }
+        assert_eq!(aami_class('F'), Some(AamiClass::F));
+        for symbol in ['/', 'f', 'Q'] {
+            assert_eq!(aami_class(symbol), Some(AamiClass::Q));
+        }
+        for symbol in ['+', '~', '|', 'x', '!', '"', '[', ']'] {
+            assert_eq!(aami_class(symbol), None);
+        }
+    }
+
+    #[test]
+    fn test_aami_class_targets() {
+        assert_eq!(AamiClass::V.index(), 2);
+        assert_eq!(AamiClass::from_index(4), Some(AamiClass::Q));
+        assert_eq!(AamiClass::from_index(5), None);
+        assert_eq!(AamiClass::S.one_hot(), vec![0.0, 1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_parse_rhythm() {
+        assert_eq!(parse_rhythm("(AFIB"), Some(RhythmLabel::AtrialFibrillation));
+        assert_eq!(parse_rhythm("(VT"), Some(RhythmLabel::VentricularTachycardia));
+        assert_eq!(parse_rhythm("(N"), Some(RhythmLabel::Normal));
+        assert_eq!(parse_rhythm("(N\0"), Some(RhythmLabel::Normal));
+        assert_eq!(parse_rhythm(" (SBR "), Some(RhythmLabel::SinusBradycardia));
+        assert_eq!(parse_rhythm("(XYZ"), None);
+        assert_eq!(parse_rhythm(""), None);
+
+        for rhythm in RhythmLabel::ALL {
+            assert_eq!(parse_rhythm(rhythm.aux()), Some(rhythm));
+            assert_eq!(RhythmLabel::from_index(rhythm.index()), Some(rhythm));
+        }
+    }
+
+    #[test]
+    fn test_load_annotations_and_targets() {
+        let path = "test_annotations.txt";
+        let mut file = File::create(path).unwrap();
+        writeln!(file, "      Time   Sample #  Type  Sub Chan  Num\tAux").unwrap();
+        writeln!(file, "    0:00.050       18     +    0    0    0\t(N").unwrap();
+        writeln!(file, "    0:00.214       77     N    0    0    0").unwrap();
+        writeln!(file, "    0:01.028      370     V    0    0    0").unwrap();
+        writeln!(file, "    0:01.839      662     N    0    0    0").unwrap();
+        writeln!(file, "    0:02.000      720     +    0    0    0\t(AFIB").unwrap();
+        writeln!(file, "    0:02.639      950     A    0    0    0").unwrap();
+        writeln!(file, "    0:02.700      972     ~    0    0    0").unwrap();
+
+        let annotations = load_annotations(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(annotations.len(), 7);
+        assert_eq!(annotations[0], Annotation { sample: 18, symbol: '+', aux: Some("(N".to_string()) });
+        assert_eq!(annotations[2].aux, None);
+
+        let beats = beat_labels(&annotations);
+        assert_eq!(beats, vec![(77, AamiClass::N), (370, AamiClass::V), (662, AamiClass::N), (950, AamiClass::S)]);
+
+        let changes = rhythm_changes(&annotations);
+        assert_eq!(changes, vec![(18, RhythmLabel::Normal), (720, RhythmLabel::AtrialFibrillation)]);
+        assert_eq!(rhythm_at(&changes, 10), None);
+        assert_eq!(rhythm_at(&changes, 500), Some(RhythmLabel::Normal));
+        assert_eq!(rhythm_at(&changes, 720), Some(RhythmLabel::AtrialFibrillation));
+
+        // 250-sample segments: [0,250) N, [250,500) V, [500,750) N, [750,1000) S
+        let classes = segment_classes(&annotations, 1100, 250);
+        assert_eq!(classes, vec![Some(AamiClass::N), Some(AamiClass::V), Some(AamiClass::N), Some(AamiClass::S)]);
+    }
+
+    #[test]
+    fn test_segment_classes_priority_and_gaps() {
+        let annotations = vec![
+            Annotation { sample: 10, symbol: 'N', aux: None },
+            Annotation { sample: 20, symbol: 'F', aux: None },
+            Annotation { sample: 30, symbol: 'A', aux: None },
+            Annotation { sample: 250, symbol: '+', aux: Some("(VT".to_string()) },
+        ];
+        let classes = segment_classes(&annotations, 500, 100);
+        assert_eq!(classes, vec![Some(AamiClass::F), None, None, None, None]);
+    }
+
+    #[test]
+    fn test_load_annotations_nonexistent_file() {
+        assert!(load_annotations("nonexistent_annotations.txt").is_err());
+    }
+}
@@ -4,26 +4,26 @@ mod tests {
 
     #[test]
     fn test_matrix_multiply() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![5.0, 6.0], vec![7.0, 8.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![5.0, 6.0], vec![7.0, 8.0]]);
         let result = matrix_multiply(&a, &b);
-        let expected = vec![vec![19.0, 22.0], vec![43.0, 50.0]];
+        let expected = Matrix::from(vec![vec![19.0, 22.0], vec![43.0, 50.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_multiply_single_element() {
-        let a = vec![vec![2.0]];
-        let b = vec![vec![3.0]];
+        let a = Matrix::from(vec![vec![2.0]]);
+        let b = Matrix::from(vec![vec![3.0]]);
         let result = matrix_multiply(&a, &b);
-        let expected = vec![vec![6.0]];
+        let expected = Matrix::from(vec![vec![6.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_multiply_identity() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let identity = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let identity = Matrix::from(vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
         let result = matrix_multiply(&a, &identity);
         assert_eq!(result, a);
     }
@@ -31,66 +31,66 @@ mod tests {
     #[test]
     #[should_panic(expected = "Matrix dimensions do not match for multiplication")]
     fn test_matrix_multiply_dimension_mismatch() {
-        let a = vec![vec![1.0, 2.0]];
-        let b = vec![vec![1.0], vec![2.0], vec![3.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0]]);
+        let b = Matrix::from(vec![vec![1.0], vec![2.0], vec![3.0]]);
         matrix_multiply(&a, &b);
     }
 
     #[test]
     fn test_matrix_transpose() {
-        let matrix = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
         let result = matrix_transpose(&matrix);
-        let expected = vec![vec![1.0, 4.0], vec![2.0, 5.0], vec![3.0, 6.0]];
+        let expected = Matrix::from(vec![vec![1.0, 4.0], vec![2.0, 5.0], vec![3.0, 6.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_transpose_square() {
-        let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
         let result = matrix_transpose(&matrix);
-        let expected = vec![vec![1.0, 3.0], vec![2.0, 4.0]];
+        let expected = Matrix::from(vec![vec![1.0, 3.0], vec![2.0, 4.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_transpose_single_row() {
-        let matrix = vec![vec![1.0, 2.0, 3.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0, 3.0]]);
         let result = matrix_transpose(&matrix);
-        let expected = vec![vec![1.0], vec![2.0], vec![3.0]];
+        let expected = Matrix::from(vec![vec![1.0], vec![2.0], vec![3.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_add() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![5.0, 6.0], vec![7.0, 8.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![5.0, 6.0], vec![7.0, 8.0]]);
         let result = matrix_add(&a, &b);
-        let expected = vec![vec![6.0, 8.0], vec![10.0, 12.0]];
+        let expected = Matrix::from(vec![vec![6.0, 8.0], vec![10.0, 12.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_matrix_add_zeros() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let zeros = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let zeros = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
         let result = matrix_add(&a, &zeros);
         assert_eq!(result, a);
     }
 
     #[test]
     fn test_matrix_add_negative() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![-1.0, -2.0], vec![-3.0, -4.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![-1.0, -2.0], vec![-3.0, -4.0]]);
         let result = matrix_add(&a, &b);
-        let expected = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let expected = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     #[should_panic(expected = "Matrix dimensions do not match for addition")]
     fn test_matrix_add_dimension_mismatch() {
-        let a = vec![vec![1.0, 2.0]];
-        let b = vec![vec![1.0], vec![2.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0]]);
+        let b = Matrix::from(vec![vec![1.0], vec![2.0]]);
         matrix_add(&a, &b);
     }
 
@@ -128,50 +128,50 @@ mod tests {
 
     #[test]
     fn test_scalar_multiply() {
-        let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
         let result = scalar_multiply(&matrix, 2.0);
-        let expected = vec![vec![2.0, 4.0], vec![6.0, 8.0]];
+        let expected = Matrix::from(vec![vec![2.0, 4.0], vec![6.0, 8.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_scalar_multiply_zero() {
-        let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
         let result = scalar_multiply(&matrix, 0.0);
-        let expected = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let expected = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_scalar_multiply_negative() {
-        let matrix = vec![vec![1.0, -2.0], vec![3.0, -4.0]];
+        let matrix = Matrix::from(vec![vec![1.0, -2.0], vec![3.0, -4.0]]);
         let result = scalar_multiply(&matrix, -1.0);
-        let expected = vec![vec![-1.0, 2.0], vec![-3.0, 4.0]];
+        let expected = Matrix::from(vec![vec![-1.0, 2.0], vec![-3.0, 4.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_elementwise_multiply() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![2.0, 3.0], vec![4.0, 5.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![2.0, 3.0], vec![4.0, 5.0]]);
         let result = elementwise_multiply(&a, &b);
-        let expected = vec![vec![2.0, 6.0], vec![12.0, 20.0]];
+        let expected = Matrix::from(vec![vec![2.0, 6.0], vec![12.0, 20.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_elementwise_multiply_zeros() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
         let result = elementwise_multiply(&a, &b);
-        let expected = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let expected = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_elementwise_multiply_ones() {
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![1.0, 1.0], vec![1.0, 1.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![1.0, 1.0], vec![1.0, 1.0]]);
         let result = elementwise_multiply(&a, &b);
         assert_eq!(result, a);
     }
@@ -179,8 +179,8 @@ mod tests {
     #[test]
     #[should_panic(expected = "Matrix dimensions do not match for element-wise multiplication")]
     fn test_elementwise_multiply_dimension_mismatch() {
-        let a = vec![vec![1.0, 2.0]];
-        let b = vec![vec![1.0], vec![2.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0]]);
+        let b = Matrix::from(vec![vec![1.0], vec![2.0]]);
         elementwise_multiply(&a, &b);
     }
 
@@ -189,27 +189,27 @@ mod tests {
         println!("Testing all matrix functions...");
         
         // Test matrix_multiply
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![5.0, 6.0], vec![7.0, 8.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![5.0, 6.0], vec![7.0, 8.0]]);
         let result = matrix_multiply(&a, &b);
-        let expected = vec![vec![19.0, 22.0], vec![43.0, 50.0]];
+        let expected = Matrix::from(vec![vec![19.0, 22.0], vec![43.0, 50.0]]);
         if result != expected {
             panic!("matrix_multiply is not working correctly");
         }
 
         // Test matrix_transpose
-        let matrix = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
         let result = matrix_transpose(&matrix);
-        let expected = vec![vec![1.0, 4.0], vec![2.0, 5.0], vec![3.0, 6.0]];
+        let expected = Matrix::from(vec![vec![1.0, 4.0], vec![2.0, 5.0], vec![3.0, 6.0]]);
         if result != expected {
             panic!("matrix_transpose is not working correctly");
         }
 
         // Test matrix_add
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![5.0, 6.0], vec![7.0, 8.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![5.0, 6.0], vec![7.0, 8.0]]);
         let result = matrix_add(&a, &b);
-        let expected = vec![vec![6.0, 8.0], vec![10.0, 12.0]];
+        let expected = Matrix::from(vec![vec![6.0, 8.0], vec![10.0, 12.0]]);
         if result != expected {
             panic!("matrix_add is not working correctly");
         }
@@ -223,22 +223,139 @@ mod tests {
         }
 
         // Test scalar_multiply
-        let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
         let result = scalar_multiply(&matrix, 2.0);
-        let expected = vec![vec![2.0, 4.0], vec![6.0, 8.0]];
+        let expected = Matrix::from(vec![vec![2.0, 4.0], vec![6.0, 8.0]]);
         if result != expected {
             panic!("scalar_multiply is not working correctly");
         }
 
         // Test elementwise_multiply
-        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
-        let b = vec![vec![2.0, 3.0], vec![4.0, 5.0]];
+        let a = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let b = Matrix::from(vec![vec![2.0, 3.0], vec![4.0, 5.0]]);
         let result = elementwise_multiply(&a, &b);
-        let expected = vec![vec![2.0, 6.0], vec![12.0, 20.0]];
+        let expected = Matrix::from(vec![vec![2.0, 6.0], vec![12.0, 20.0]]);
         if result != expected {
             panic!("elementwise_multiply is not working correctly");
         }
 
         println!("All functions are working correctly - all good!");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_matrix_is_row_major() {
+        let matrix = Matrix::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert_eq!(matrix.shape(), (2, 3));
+        assert_eq!(matrix.data, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(matrix[(1, 0)], 4.0);
+        assert_eq!(matrix.row(1), &[4.0, 5.0, 6.0]);
+        assert_eq!(matrix.to_rows(), vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+    }
+
+    #[test]
+    fn test_matrix_index_mut_and_iterators() {
+        let mut matrix = Matrix::zeros(2, 2);
+        matrix[(0, 1)] = 3.0;
+        for row in matrix.iter_rows_mut() {
+            row[0] += 1.0;
+        }
+        assert_eq!(matrix.data, vec![1.0, 3.0, 1.0, 0.0]);
+
+        let rows: Vec<&[f64]> = matrix.iter_rows().collect();
+        assert_eq!(rows, vec![&[1.0, 3.0][..], &[1.0, 0.0][..]]);
+        let col: Vec<f64> = matrix.col(1).copied().collect();
+        assert_eq!(col, vec![3.0, 0.0]);
+        let col_sums: Vec<f64> = matrix.iter_cols().map(|c| c.sum()).collect();
+        assert_eq!(col_sums, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_views_share_data() {
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]);
+        let view = matrix.slice_rows(1..3);
+        assert_eq!(view.shape(), (2, 2));
+        assert_eq!(view[(0, 1)], 4.0);
+        assert_eq!(view.to_matrix(), Matrix::from(vec![vec![3.0, 4.0], vec![5.0, 6.0]]));
+
+        // A plain slice viewed as a 1 x n matrix, without copying
+        let features = [1.0, 1.0];
+        let product = matrix_multiply(MatrixView::from_row(&features), &matrix_transpose(view));
+        assert_eq!(product, Matrix::from(vec![vec![7.0, 11.0]]));
+    }
+
+    #[test]
+    fn test_select_rows() {
+        let matrix = Matrix::from(vec![vec![1.0], vec![2.0], vec![3.0]]);
+        assert_eq!(matrix.select_rows(&[2, 0, 2]).data, vec![3.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "All rows must have the same length")]
+    fn test_ragged_rows_are_rejected() {
+        let _ = Matrix::from(vec![vec![1.0, 2.0], vec![3.0]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index (0, 2) out of bounds for 2x2 matrix")]
+    fn test_index_does_not_wrap_into_next_row() {
+        let matrix: Matrix = Matrix::zeros(2, 2);
+        let _ = matrix[(0, 2)];
+    }
+
+    #[test]
+    fn test_try_variants_report_shapes() {
+        let a: Matrix = Matrix::zeros(1, 2);
+        let b = Matrix::zeros(3, 1);
+
+        let error = try_matrix_multiply(&a, &b).unwrap_err();
+        assert_eq!(error.left, Shape::Matrix(1, 2));
+        assert_eq!(error.right, Shape::Matrix(3, 1));
+        assert_eq!(error.to_string(), "Matrix dimensions do not match for multiplication (got 1x2 and 3x1)");
+
+        let error = try_dot_product(&[1.0, 2.0], &[1.0]).unwrap_err();
+        assert_eq!(error.to_string(), "Vectors must be of the same length for dot product (got length 2 and length 1)");
+
+        assert!(try_matrix_add(&a, &b).is_err());
+        assert!(try_elementwise_multiply(&a, &b).is_err());
+        assert!(Matrix::try_from_vec(2, 2, vec![1.0; 3]).is_err());
+        assert!(Matrix::try_from_rows(&[vec![1.0], vec![]]).is_err());
+        assert_eq!(try_matrix_multiply(&a, &matrix_transpose(&a)), Ok(Matrix::zeros(1, 1)));
+    }
+
+    #[test]
+    fn test_failed_inplace_add_leaves_target_unchanged() {
+        let mut a = Matrix::filled(2, 2, 1.0);
+        assert!(try_matrix_add_inplace(&mut a, &Matrix::zeros(2, 3)).is_err());
+        assert_eq!(a, Matrix::filled(2, 2, 1.0));
+
+        let mut v = vec![1.0, 2.0];
+        assert!(try_vector_add_inplace(&mut v, &[1.0]).is_err());
+        assert_eq!(v, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_empty_matrices() {
+        let empty: Matrix = Matrix::from_rows(&[]);
+        assert_eq!(empty.shape(), (0, 0));
+        assert!(empty.is_empty());
+        assert_eq!(matrix_transpose(&empty), empty);
+        assert_eq!(matrix_add(&empty, &empty), empty);
+
+        // (0 x 3)(3 x 2) is 0 x 2 and (2 x 0)(0 x 3) is a 2 x 3 matrix of zeros
+        assert_eq!(matrix_multiply(&Matrix::<f64>::zeros(0, 3), &Matrix::zeros(3, 2)).shape(), (0, 2));
+        assert_eq!(matrix_multiply(&Matrix::zeros(2, 0), &Matrix::zeros(0, 3)), Matrix::<f64>::zeros(2, 3));
+        assert_eq!(dot_product::<f64>(&[], &[]), 0.0);
+        assert_eq!(Matrix::<f64>::zeros(3, 0).iter_rows().count(), 3);
+    }
+
+    #[test]
+    fn test_add_row_vector_inplace() {
+        let mut matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]);
+        add_row_vector_inplace(&mut matrix, &[10.0, 20.0]);
+        assert_eq!(matrix.to_rows(), vec![vec![11.0, 22.0], vec![13.0, 24.0], vec![15.0, 26.0]]);
+
+        let error = try_add_row_vector_inplace(&mut matrix, &[1.0]).unwrap_err();
+        assert_eq!(error.to_string(), "Row vector length does not match matrix columns (got 3x2 and length 1)");
+        assert_eq!(matrix[(0, 0)], 11.0);
+    }
+}
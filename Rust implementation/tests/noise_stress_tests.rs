@@ -0,0 +1,134 @@
+use std::fs::{self, File};
+use std::io::Write;
+use ecgnn::noise_stress::*;
+use ecgnn::rng::Rng;
+use ecgnn::synthetic::{generate_ecg, EcgSynConfig, Morphology};
+
+fn clean_rows() -> Vec<Vec<f64>> {
+    let config = EcgSynConfig {
+        duration: 10.0,
+        noise_std: 0.0,
+        leads: vec![Morphology::lead_ii(), Morphology::lead_v1()],
+        ..EcgSynConfig::default()
+    };
+    generate_ecg(&config).to_rows()
+}
+
+fn measured_snr_db(clean: &[f64], noisy: &[f64]) -> f64 {
+    let noise: Vec<f64> = noisy.iter().zip(clean.iter()).map(|(n, c)| n - c).collect();
+    10.0 * (signal_power(clean) / signal_power(&noise)).log10()
+}
+
+#[cfg(test)]
+mod noise_stress_tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_at_snr_hits_target() {
+        let rows = clean_rows();
+        let clean: Vec<f64> = rows.iter().map(|r| r[1]).collect();
+
+        for kind in [NoiseKind::BaselineWander, NoiseKind::MuscleArtifact, NoiseKind::ElectrodeMotion] {
+            let noise = synthetic_noise(kind, clean.len(), 360.0, &mut Rng::new(1));
+            for snr in [12.0, 0.0, -6.0] {
+                let noisy = mix_at_snr(&clean, &noise, snr, None);
+                assert!((measured_snr_db(&clean, &noisy) - snr).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mix_at_snr_respects_mask() {
+        let clean = vec![1.0, -1.0, 1.0, -1.0];
+        let noise = vec![0.5, 0.2, -0.3, 0.1];
+        let mask = vec![false, true, false, true];
+        let noisy = mix_at_snr(&clean, &noise, 6.0, Some(&mask));
+
+        assert_eq!(noisy[0], 1.0);
+        assert_eq!(noisy[2], 1.0);
+        assert_ne!(noisy[1], -1.0);
+        // The gain comes from the whole record, so the noisy spans match an unmasked mix
+        let unmasked = mix_at_snr(&clean, &noise, 6.0, None);
+        assert_eq!(noisy[1], unmasked[1]);
+        assert_eq!(noisy[3], unmasked[3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Noise signal is shorter than the clean signal")]
+    fn test_mix_at_snr_short_noise() {
+        mix_at_snr(&[1.0, 2.0, 3.0], &[0.1], 6.0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Noise mask needs one entry per sample of the clean signal")]
+    fn test_mix_at_snr_short_mask() {
+        mix_at_snr(&[1.0, 2.0, 3.0], &[0.1, 0.2, 0.3], 6.0, Some(&[true, false]));
+    }
+
+    #[test]
+    fn test_nst_schedule() {
+        let schedule = nst_schedule(11 * 60, 1.0);
+        assert!(schedule[..300].iter().all(|&on| !on));
+        assert!(schedule[300..420].iter().all(|&on| on));
+        assert!(schedule[420..540].iter().all(|&on| !on));
+        assert!(schedule[540..].iter().all(|&on| on));
+    }
+
+    #[test]
+    fn test_noise_stress_dataset_labels_and_layout() {
+        let rows = clean_rows();
+        let config = StressConfig {
+            snr_levels: vec![12.0, 0.0],
+            ..StressConfig::default()
+        };
+        let records = noise_stress_dataset(&rows, &config);
+
+        assert_eq!(records.len(), 3 * 2);
+        assert_eq!(records[0].noise, NoiseKind::BaselineWander);
+        assert_eq!(records[5].noise, NoiseKind::ElectrodeMotion);
+        assert_eq!(records[1].snr_db, 0.0);
+
+        for record in &records {
+            assert_eq!(record.rows.len(), rows.len());
+            assert_eq!(record.rows[7][0], rows[7][0]);
+            for lead in 1..3 {
+                let clean: Vec<f64> = rows.iter().map(|r| r[lead]).collect();
+                let noisy: Vec<f64> = record.rows.iter().map(|r| r[lead]).collect();
+                assert!((measured_snr_db(&clean, &noisy) - record.snr_db).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_stress_dataset_is_reproducible() {
+        let rows = clean_rows();
+        let a = noise_stress_dataset(&rows, &StressConfig::default());
+        let b = noise_stress_dataset(&rows, &StressConfig::default());
+        assert_eq!(a[3].rows, b[3].rows);
+    }
+
+    #[test]
+    fn test_recorded_noise_from_file() {
+        let path = "test_noise_record.csv";
+        let mut file = File::create(path).unwrap();
+        writeln!(file, "time,noise").unwrap();
+        for i in 0..5 {
+            writeln!(file, "{},{}", i, i as f64 * 0.1).unwrap();
+        }
+
+        let samples = load_noise_record(path, 1).unwrap();
+        assert!(load_noise_record(path, 2).is_err());
+        fs::remove_file(path).unwrap();
+        assert_eq!(samples.len(), 5);
+
+        let source = NoiseSource::Recorded { kind: NoiseKind::MuscleArtifact, samples };
+        assert_eq!(source.kind(), NoiseKind::MuscleArtifact);
+
+        // Shorter recordings are reused cyclically
+        let drawn = source.realise(12, 360.0, &mut Rng::new(3));
+        assert_eq!(drawn.len(), 12);
+        for i in 0..7 {
+            assert_eq!(drawn[i], drawn[i + 5]);
+        }
+    }
+}
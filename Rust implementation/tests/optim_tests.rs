@@ -0,0 +1,198 @@
+mod common;
+
+use ecgnn::batching::BatchConfig;
+use ecgnn::layers::Param;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::*;
+use ecgnn::sequential::Sequential;
+use common::binary_problem;
+
+/// A single scalar parameter with the given value and gradient
+fn scalar(value: f64, grad: f64) -> Param {
+    let mut param = Param::new("w", Matrix::filled(1, 1, value));
+    param.grad = Matrix::filled(1, 1, grad);
+    param
+}
+
+fn step_scalar(optimizer: &mut dyn Optimizer, param: &mut Param, grad: f64) -> f64 {
+    param.grad = Matrix::filled(1, 1, grad);
+    optimizer.step(&mut [param]);
+    param.value.data[0]
+}
+
+fn all_optimizers() -> Vec<Box<dyn Optimizer>> {
+    vec![
+        Box::new(Sgd::new(0.1)),
+        Box::new(Sgd::with_momentum(0.05, 0.9, false)),
+        Box::new(Sgd::with_momentum(0.05, 0.9, true)),
+        Box::new(Adam::new(0.01)),
+        Box::new(AdamW::new(0.01, 0.01)),
+        Box::new(RmsProp::new(0.01)),
+        Box::new(Adagrad::new(0.1)),
+    ]
+}
+
+#[cfg(test)]
+mod optim_tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_sgd_matches_train_step() {
+        let (inputs, targets, weights) = binary_problem(32, 3, 4, |x| x[0] + x[1] > 0.0);
+        let mut a = Sequential::mlp_seeded(3, &[4], 1, 1);
+        let mut b = Sequential::mlp_seeded(3, &[4], 1, 1);
+        let mut optimizer = Sgd::new(0.3);
+        for _ in 0..3 {
+            let loss_a = a.train_step(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, 0.3);
+            let loss_b = b.train_step_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &mut optimizer);
+            assert_eq!(loss_a, loss_b);
+        }
+        assert_eq!(a.forward(&inputs), b.forward(&inputs));
+    }
+
+    #[test]
+    fn test_momentum_and_nesterov_updates() {
+        // v1 = g = 1, v2 = 0.5 * 1 + 1 = 1.5
+        let mut param = scalar(0.0, 0.0);
+        let mut momentum = Sgd::with_momentum(0.1, 0.5, false);
+        assert!((step_scalar(&mut momentum, &mut param, 1.0) + 0.1).abs() < 1e-15);
+        assert!((step_scalar(&mut momentum, &mut param, 1.0) + 0.25).abs() < 1e-15);
+
+        // Nesterov steps along g + mu * v: 1 + 0.5, then 1 + 0.75
+        let mut param = scalar(0.0, 0.0);
+        let mut nesterov = Sgd::with_momentum(0.1, 0.5, true);
+        assert!((step_scalar(&mut nesterov, &mut param, 1.0) + 0.15).abs() < 1e-15);
+        assert!((step_scalar(&mut nesterov, &mut param, 1.0) + 0.325).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_adam_and_adamw_updates() {
+        // The bias-corrected first Adam step is lr * g / (|g| + eps), about lr in the direction of -g
+        let mut param = scalar(1.0, 0.0);
+        let mut adam = Adam::new(0.01);
+        assert!((step_scalar(&mut adam, &mut param, 250.0) - 0.99).abs() < 1e-9);
+        assert!((step_scalar(&mut adam, &mut param, -1e-3) - 0.99).abs() < 0.01);
+
+        // With no gradient AdamW only decays: w *= 1 - lr * weight_decay
+        let mut param = scalar(2.0, 0.0);
+        let mut adamw = AdamW::new(0.1, 0.5);
+        assert!((step_scalar(&mut adamw, &mut param, 0.0) - 1.9).abs() < 1e-15);
+        assert!((step_scalar(&mut adamw, &mut param, 0.0) - 1.805).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_rmsprop_and_adagrad_updates() {
+        // s = 0.01 * 4 = 0.04, step = 0.1 * 2 / 0.2
+        let mut param = scalar(0.0, 0.0);
+        let mut rmsprop = RmsProp::new(0.1);
+        assert!((step_scalar(&mut rmsprop, &mut param, 2.0) + 1.0).abs() < 1e-6);
+
+        // s = 4, then 4 + 9 = 13
+        let mut param = scalar(0.0, 0.0);
+        let mut adagrad = Adagrad::new(0.1);
+        assert!((step_scalar(&mut adagrad, &mut param, 2.0) + 0.1).abs() < 1e-9);
+        assert!((step_scalar(&mut adagrad, &mut param, 3.0) + 0.1 + 0.3 / 13f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_every_optimizer_trains_a_network() {
+        let (inputs, targets, weights) = binary_problem(32, 3, 4, |x| x[0] + x[1] > 0.0);
+        let batches = BatchConfig::new(8, 2);
+        for mut optimizer in all_optimizers() {
+            let mut model = Sequential::mlp_seeded(3, &[8], 1, 2);
+            let first = model.train_epoch_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, 0, optimizer.as_mut());
+            let mut last = first;
+            for epoch in 1..60 {
+                last = model.train_epoch_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, epoch, optimizer.as_mut());
+            }
+            assert!(last < 0.7 * first, "{}: {} -> {}", optimizer.name(), first, last);
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip_resumes_exactly() {
+        let (inputs, targets, weights) = binary_problem(32, 3, 4, |x| x[0] + x[1] > 0.0);
+        let batches = BatchConfig::new(8, 3);
+        let train = |model: &mut Sequential, optimizer: &mut dyn Optimizer, epochs: std::ops::Range<usize>| {
+            for epoch in epochs {
+                model.train_epoch_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, epoch, optimizer);
+            }
+        };
+
+        for (mut continuous, mut resumed) in all_optimizers().into_iter().zip(all_optimizers()) {
+            let mut model = Sequential::mlp_seeded(3, &[5], 1, 3);
+            train(&mut model, continuous.as_mut(), 0..3);
+
+            // Restart from a copy of the parameters and the saved optimizer state
+            let mut restarted = Sequential::mlp_seeded(3, &[5], 1, 99);
+            for (to, from) in restarted.parameters_mut().into_iter().zip(model.parameters()) {
+                to.value = from.value.clone();
+            }
+            let text = continuous.state().to_text();
+            resumed.load_state(&OptimizerState::from_text(&text).unwrap()).unwrap();
+            assert_eq!(resumed.state(), continuous.state());
+
+            train(&mut model, continuous.as_mut(), 3..6);
+            train(&mut restarted, resumed.as_mut(), 3..6);
+            assert_eq!(model.forward(&inputs), restarted.forward(&inputs), "{}", continuous.name());
+        }
+    }
+
+    #[test]
+    fn test_f32_state_round_trips() {
+        let mut param: Param<f32> = Param::new("w", Matrix::filled(2, 2, 0.3f32));
+        param.grad = Matrix::from_vec(2, 2, vec![0.1f32, -0.7, 1e-3, 3.0]);
+        let mut adam: Adam<f32> = Adam::new(0.01);
+        adam.step(&mut [&mut param]);
+
+        let mut restored: Adam<f32> = Adam::new(0.5);
+        restored.load_state(&OptimizerState::from_text(&adam.state().to_text()).unwrap()).unwrap();
+        assert_eq!(restored.learning_rate, 0.01);
+        let mut copy = param.clone();
+        adam.step(&mut [&mut param]);
+        restored.step(&mut [&mut copy]);
+        assert_eq!(param, copy);
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let mut param = scalar(1.0, 0.5);
+        let mut optimizer = RmsProp::new(0.01);
+        optimizer.step(&mut [&mut param]);
+
+        let path = std::env::temp_dir().join(format!("ecgnn_optimizer_state_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        optimizer.state().save(path).unwrap();
+        let loaded = OptimizerState::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, optimizer.state());
+        assert_eq!(loaded.step, 1);
+        assert_eq!(loaded.buffers[0].0, "square_average");
+    }
+
+    #[test]
+    fn test_state_errors() {
+        let adam_state = Optimizer::<f64>::state(&Adam::new(0.1));
+        let error = Optimizer::<f64>::load_state(&mut Sgd::new(0.1), &adam_state).unwrap_err();
+        assert_eq!(error.to_string(), "Cannot load Adam state into a SGD optimizer");
+
+        assert!(OptimizerState::from_text("").is_err());
+        assert!(OptimizerState::from_text("optimizer Adam\nlearning_rate x\nstep 0\n").is_err());
+        let bad_shape = "optimizer SGD\nlearning_rate 0.1\nstep 1\nbuffer velocity 0 2 2\n1 2 3\n";
+        assert!(OptimizerState::from_text(bad_shape).is_err());
+        assert!(OptimizerState::from_text("optimizer SGD\nlearning_rate 0.1\nstep 0\nschedule OneCycle step\n").is_err());
+        let schedule = OptimizerState::from_text("optimizer SGD\nlearning_rate 0.1\nstep 0\nschedule OneCycle step 7\n").unwrap().schedule.unwrap();
+        assert_eq!(schedule.value("step").unwrap(), 7.0);
+        assert_eq!(schedule.value("epoch").unwrap_err().to_string(), "OneCycle schedule state has no 'epoch'");
+    }
+
+    #[test]
+    #[should_panic(expected = "Optimizer state was created for different parameters")]
+    fn test_parameters_must_not_change() {
+        let mut optimizer = Adam::new(0.1);
+        optimizer.step(&mut [&mut scalar(1.0, 1.0)]);
+        optimizer.step(&mut [&mut scalar(1.0, 1.0), &mut scalar(2.0, 1.0)]);
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use ecgnn::matrix_math::*;
+use ecgnn::parallel::*;
+use ecgnn::rng::Rng;
+use common::random_matrix;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread;
+
+#[cfg(test)]
+mod parallel_tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each_chunk_mut_covers_every_element_once() {
+        let mut data = vec![0usize; 1003];
+        for_each_chunk_mut(&mut data, 10, usize::MAX, |offset, chunk| {
+            assert_eq!(offset % 10, 0);
+            for (i, value) in chunk.iter_mut().enumerate() {
+                *value += offset + i;
+            }
+        });
+        assert!(data.iter().enumerate().all(|(i, &v)| v == i));
+    }
+
+    #[test]
+    fn test_map_blocks_keeps_block_order() {
+        let blocks = map_blocks(10, 4, usize::MAX, |range| (range.start, range.end));
+        assert_eq!(blocks, vec![(0, 4), (4, 8), (8, 10)]);
+    }
+
+    #[test]
+    fn test_reductions() {
+        let matrix = Matrix::from(vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]);
+        assert_eq!(matrix_sum(&matrix), 21.0);
+        assert_eq!(column_sums(&matrix), vec![9.0, 12.0]);
+        assert_eq!(column_sums(&Matrix::<f64>::zeros(0, 3)), vec![0.0; 3]);
+    }
+
+    #[test]
+    fn test_threaded_kernels_match_single_threaded_exactly() {
+        let mut rng = Rng::new(7);
+        let a = random_matrix(203, 150, &mut rng);
+        let b = random_matrix(150, 70, &mut rng);
+        let c = random_matrix(203, 150, &mut rng);
+        let long: Vec<f64> = (0..50_000).map(|_| rng.uniform(-1.0, 1.0)).collect();
+
+        let run = || {
+            let mut accumulated = a.clone();
+            matrix_add_inplace(&mut accumulated, &c);
+            (
+                matrix_multiply(&a, &b),
+                matrix_transpose(&a),
+                elementwise_multiply(&a, &c),
+                accumulated,
+                dot_product(&long, &long),
+                column_sums(&a),
+                matrix_sum(&a),
+            )
+        };
+
+        set_num_threads(1);
+        let serial = run();
+        // Force the threaded paths even for these small inputs and on single-core machines
+        set_num_threads(4);
+        set_min_parallel_work(1);
+        let threaded = run();
+        set_num_threads(0);
+        set_min_parallel_work(DEFAULT_MIN_PARALLEL_WORK);
+
+        assert_eq!(serial, threaded);
+        assert_eq!(serial.0.data.len(), 203 * 70);
+        assert!((serial.6 - a.data.iter().sum::<f64>()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_workers_are_reused_across_calls() {
+        set_num_threads(4);
+        let threads = Mutex::new(HashSet::new());
+        let mut data = vec![0u8; 64];
+        for _ in 0..50 {
+            for_each_chunk_mut(&mut data, 1, usize::MAX, |_, _| {
+                threads.lock().unwrap().insert(thread::current().id());
+            });
+        }
+        set_num_threads(0);
+        // The caller plus at most one worker per extra thread (other tests may ask for up to 4),
+        // where a thread per chunk and call would be 150
+        let distinct = threads.lock().unwrap().len();
+        assert!(distinct <= 4.max(num_threads()), "{}", distinct);
+    }
+
+    #[test]
+    fn test_nested_calls_complete() {
+        set_num_threads(3);
+        let mut data = vec![0usize; 90];
+        for_each_chunk_mut(&mut data, 30, usize::MAX, |offset, chunk| {
+            for_each_chunk_mut(chunk, 1, usize::MAX, |inner, values| {
+                for (i, value) in values.iter_mut().enumerate() {
+                    *value = offset + inner + i;
+                }
+            });
+        });
+        set_num_threads(0);
+        assert!(data.iter().enumerate().all(|(i, &v)| v == i));
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk at 32 failed")]
+    fn test_worker_panic_reaches_the_caller() {
+        set_num_threads(2);
+        let mut data = vec![0u8; 64];
+        for_each_chunk_mut(&mut data, 32, usize::MAX, |offset, _| {
+            if offset == 32 {
+                panic!("chunk at {} failed", offset);
+            }
+        });
+    }
+}
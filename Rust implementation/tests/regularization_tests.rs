@@ -0,0 +1,136 @@
+mod common;
+
+use ecgnn::batching::BatchConfig;
+use ecgnn::gradient_check::{check_layer, check_sequential, DEFAULT_STEP};
+use ecgnn::init::Initializer;
+use ecgnn::layers::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::Adam;
+use ecgnn::regularization::Regularization;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+use common::binary_problem;
+
+fn column_norms(weights: &Matrix) -> Vec<f64> {
+    (0..weights.cols).map(|j| weights.col(j).map(|w| w * w).sum::<f64>().sqrt()).collect()
+}
+
+#[cfg(test)]
+mod regularization_tests {
+    use super::*;
+
+    #[test]
+    fn test_penalty_skips_biases() {
+        let mut weights: Param = Param::new("weights", Matrix::from_vec(1, 3, vec![1.0, -2.0, 0.0]));
+        let mut bias = Param::new("bias", Matrix::filled(1, 1, 5.0));
+        let regularization = Regularization { l1: 0.1, l2: 0.5, max_norm: None };
+        // 0.1 * 3 + 0.25 * 5
+        assert!((regularization.penalty(&[&weights, &bias]) - 1.55).abs() < 1e-12);
+
+        regularization.add_gradients(&mut [&mut weights, &mut bias]);
+        assert_eq!(weights.grad.data, vec![0.6, -1.1, 0.0]);
+        assert_eq!(bias.grad.data, vec![0.0]);
+
+        assert_eq!(Regularization::default().penalty(&[&weights]), 0.0);
+    }
+
+    #[test]
+    fn test_penalty_in_reported_loss_and_gradients() {
+        let (inputs, targets, weights) = binary_problem(24, 4, 8, |x| x[0] > x[2]);
+        let regularization = Regularization { l1: 0.01, l2: 0.1, max_norm: None };
+        let mut plain = Sequential::mlp_seeded(4, &[5], 1, 1);
+        let mut model = Sequential::mlp_seeded(4, &[5], 1, 1).with_regularization(regularization);
+
+        let data_loss = plain.compute_gradients(&inputs, &targets, &weights, Loss::BinaryCrossEntropy);
+        let total = model.compute_gradients(&inputs, &targets, &weights, Loss::BinaryCrossEntropy);
+        assert!(model.penalty() > 0.0);
+        assert!((total - data_loss - model.penalty()).abs() < 1e-12);
+
+        let report = check_sequential(&mut model, &inputs, &targets, &weights, Loss::BinaryCrossEntropy, DEFAULT_STEP);
+        assert!(report.passes(1e-6), "{}", report);
+    }
+
+    #[test]
+    fn test_l2_shrinks_weights() {
+        let (inputs, targets, weights) = binary_problem(24, 4, 8, |x| x[0] > x[2]);
+        let norm = |model: &Sequential| model.parameters().iter()
+            .filter(|p| p.name == "weights")
+            .map(|p| p.value.data.iter().map(|w| w * w).sum::<f64>())
+            .sum::<f64>();
+        let mut plain = Sequential::mlp_seeded(4, &[8], 1, 2);
+        let mut decayed = Sequential::mlp_seeded(4, &[8], 1, 2)
+            .with_regularization(Regularization { l2: 0.5, ..Default::default() });
+        for _ in 0..50 {
+            plain.train_step(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, 0.5);
+            decayed.train_step(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, 0.5);
+        }
+        assert!(norm(&decayed) < 0.5 * norm(&plain), "{} vs {}", norm(&decayed), norm(&plain));
+    }
+
+    #[test]
+    fn test_max_norm_constraint() {
+        let (inputs, targets, weights) = binary_problem(24, 4, 8, |x| x[0] > x[2]);
+        let mut model = Sequential::mlp_seeded(4, &[6], 1, 3)
+            .with_regularization(Regularization { max_norm: Some(0.5), ..Default::default() });
+        let mut optimizer = Adam::new(0.1);
+        let batches = BatchConfig::new(8, 3);
+        for epoch in 0..5 {
+            model.train_epoch_with(&inputs, &targets, &weights, Loss::BinaryCrossEntropy, &batches, epoch, &mut optimizer);
+        }
+        for param in model.parameters() {
+            let norms = column_norms(&param.value);
+            if param.name == "weights" {
+                assert!(norms.iter().all(|&n| n <= 0.5 + 1e-12), "{:?}", norms);
+            }
+        }
+        // Biases are unconstrained and free to grow past the limit
+        assert!(model.parameters().iter().any(|p| p.name == "bias" && column_norms(&p.value).iter().any(|&n| n > 0.5)));
+    }
+
+    #[test]
+    fn test_dropout_train_and_eval() {
+        let input = Matrix::filled(200, 50, 1.0);
+        let mut dropout: Dropout = Dropout::new(0.25, 5);
+        assert!(dropout.is_training());
+        let output = dropout.forward(&input);
+        let dropped = output.data.iter().filter(|&&x| x == 0.0).count();
+        assert!(output.data.iter().all(|&x| x == 0.0 || (x - 4.0 / 3.0).abs() < 1e-12));
+        assert!((dropped as f64 / 10_000.0 - 0.25).abs() < 0.02, "{}", dropped);
+        // Inverted scaling keeps the expected activation
+        let mean = output.data.iter().sum::<f64>() / 10_000.0;
+        assert!((mean - 1.0).abs() < 0.03);
+
+        // The gradient flows only through the units that were kept
+        let grad = dropout.backward(&Matrix::filled(200, 50, 1.0));
+        assert_eq!(grad, output);
+
+        dropout.set_training(false);
+        assert_eq!(dropout.forward(&input), input);
+        let report = check_layer(&mut dropout, &Matrix::from_vec(2, 3, vec![0.5, -1.0, 2.0, 0.1, 0.0, -0.3]), DEFAULT_STEP);
+        assert!(report.passes(1e-7), "{}", report);
+    }
+
+    #[test]
+    fn test_sequential_train_and_eval_modes() {
+        let (inputs, _, _) = binary_problem(24, 4, 8, |x| x[0] > x[2]);
+        let mut model = Sequential::new(Vec::new());
+        model.push(Dense::with_initializer(4, 16, Initializer::HeNormal, &mut Rng::new(0)));
+        model.push(ReLU::new());
+        model.push(Dropout::new(0.5, 0));
+        model.push(Dense::with_initializer(16, 1, Initializer::XavierUniform, &mut Rng::new(1)));
+        model.push(Sigmoid::new());
+
+        assert_ne!(model.forward(&inputs), model.forward(&inputs));
+        model.eval();
+        assert_eq!(model.forward(&inputs), model.forward(&inputs));
+        model.train();
+        assert_ne!(model.forward(&inputs), model.forward(&inputs));
+    }
+
+    #[test]
+    #[should_panic(expected = "Dropout rate must be in [0, 1), got 1")]
+    fn test_dropout_rate_must_be_below_one() {
+        let _: Dropout = Dropout::new(1.0, 0);
+    }
+}
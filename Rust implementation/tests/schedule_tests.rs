@@ -0,0 +1,203 @@
+use ecgnn::batching::BatchConfig;
+use ecgnn::layers::Param;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::optim::{Adam, Optimizer, OptimizerState, Sgd};
+use ecgnn::schedule::*;
+use ecgnn::sequential::Sequential;
+
+/// Learning rate at each of the first `n` positions
+fn rates(scheduler: &mut dyn Scheduler, n: usize) -> Vec<f64> {
+    (0..n)
+        .map(|_| {
+            let rate = scheduler.learning_rate();
+            scheduler.advance(None);
+            rate
+        })
+        .collect()
+}
+
+/// Every schedule, each part-way through
+fn advanced_schedules() -> Vec<Box<dyn Scheduler>> {
+    let mut plateau = ReduceOnPlateau::new(1.0, Mode::Min);
+    plateau.patience = 1;
+    let mut schedules: Vec<Box<dyn Scheduler>> = vec![
+        Box::new(StepDecay::new(1.0, 2, 0.5)),
+        Box::new(Exponential::new(1.0, 0.9)),
+        Box::new(CosineWarmRestarts::new(1.0, 0.1, 2, 2)),
+        Box::new(LinearWarmup::new(1.0, 10, 0.1)),
+        Box::new(OneCycle::new(1.0, 20)),
+        Box::new(plateau),
+    ];
+    for schedule in schedules.iter_mut() {
+        for metric in [1.0, 0.5, 0.6, 0.7, 0.4] {
+            schedule.advance(Some(metric));
+        }
+    }
+    schedules
+}
+
+fn assert_rates(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-12, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    #[test]
+    fn test_step_and_exponential_decay() {
+        let mut step = StepDecay::new(1.0, 2, 0.5);
+        assert_eq!(step.interval(), Interval::Epoch);
+        assert_rates(&rates(&mut step, 5), &[1.0, 1.0, 0.5, 0.5, 0.25]);
+
+        let mut exponential = Exponential::new(2.0, 0.1);
+        assert_rates(&rates(&mut exponential, 3), &[2.0, 0.2, 0.02]);
+    }
+
+    #[test]
+    fn test_cosine_warm_restarts() {
+        let mut cosine = CosineWarmRestarts::new(1.0, 0.0, 2, 2);
+        assert_eq!(cosine.interval(), Interval::Step);
+        // Cycles of 2 then 4 steps, restarting at the maximum
+        assert_rates(&rates(&mut cosine, 7), &[1.0, 0.5, 1.0, 0.8535533905932737, 0.5, 0.14644660940672627, 1.0]);
+
+        let mut fixed = CosineWarmRestarts::new(0.1, 0.1, 3, 1);
+        assert!(rates(&mut fixed, 10).iter().all(|&rate| (rate - 0.1).abs() < 1e-15));
+    }
+
+    #[test]
+    fn test_linear_warmup() {
+        let mut warmup = LinearWarmup::new(1.0, 4, 0.2);
+        assert_rates(&rates(&mut warmup, 6), &[0.2, 0.4, 0.6, 0.8, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_one_cycle() {
+        let mut one_cycle = OneCycle::new(1.0, 11);
+        let all = rates(&mut one_cycle, 13);
+        assert!((all[0] - 0.04).abs() < 1e-12);
+        assert!((all[3] - 1.0).abs() < 1e-12);
+        assert!((all[10] - 0.04 / 1e4).abs() < 1e-12);
+        assert_eq!(all[11], all[10]);
+        assert!(all[..4].windows(2).all(|w| w[0] < w[1]));
+        assert!(all[3..11].windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn test_reduce_on_plateau() {
+        let mut plateau = ReduceOnPlateau::new(1.0, Mode::Min);
+        (plateau.patience, plateau.cooldown, plateau.min_lr) = (1, 1, 0.3);
+        let losses = [1.0, 0.9, 0.9, 0.95, 0.9, 0.9, 0.9, 0.9, 0.9, 0.9];
+        let mut seen = Vec::new();
+        for loss in losses {
+            plateau.advance(Some(loss));
+            seen.push(plateau.learning_rate());
+        }
+        // Two epochs without improvement halve the rate, then one cooldown epoch; never below min_lr
+        assert_rates(&seen, &[1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.3, 0.3, 0.3, 0.3]);
+
+        let mut accuracy = ReduceOnPlateau::new(1.0, Mode::Max);
+        accuracy.patience = 0;
+        accuracy.advance(Some(0.5));
+        accuracy.advance(Some(0.6));
+        assert_eq!(accuracy.learning_rate(), 1.0);
+        accuracy.advance(Some(0.55));
+        assert_eq!(accuracy.learning_rate(), 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "ReduceOnPlateau needs a validation metric every epoch")]
+    fn test_reduce_on_plateau_needs_metric() {
+        ReduceOnPlateau::new(1.0, Mode::Min).advance(None);
+    }
+
+    #[test]
+    fn test_scheduled_optimizer_sets_and_logs_rates() {
+        let mut param = Param::new("w", Matrix::filled(1, 1, 0.0));
+        param.grad = Matrix::filled(1, 1, 1.0);
+        let mut optimizer = Scheduled::new(Sgd::new(123.0), LinearWarmup::new(1.0, 2, 0.5));
+        for _ in 0..3 {
+            optimizer.step(&mut [&mut param]);
+        }
+        assert_eq!(optimizer.history(), &[0.5, 0.75, 1.0]);
+        assert_eq!(param.value.data[0], -2.25);
+        assert_eq!(Optimizer::<f64>::name(&optimizer), "SGD");
+
+        // Per-epoch schedules only move in end_epoch
+        let mut epochs = Scheduled::new(Sgd::new(0.0), StepDecay::new(1.0, 1, 0.1));
+        epochs.step(&mut [&mut param]);
+        epochs.step(&mut [&mut param]);
+        epochs.end_epoch(None);
+        epochs.step(&mut [&mut param]);
+        assert_rates(epochs.history(), &[1.0, 1.0, 0.1]);
+    }
+
+    #[test]
+    fn test_training_loop_queries_schedule_every_step() {
+        let inputs = Matrix::from_vec(10, 2, (0..20).map(|i| (i as f64 * 0.37).sin()).collect());
+        let targets = Matrix::from_vec(10, 1, (0..10).map(|i| (i % 2) as f64).collect());
+        let batches = BatchConfig::new(4, 0);
+        let mut model = Sequential::mlp_seeded(2, &[3], 1, 0);
+        let mut optimizer = Scheduled::new(Sgd::new(0.1), OneCycle::new(0.1, 9));
+        for epoch in 0..3 {
+            model.train_epoch_with(&inputs, &targets, &[1.0; 10], Loss::BinaryCrossEntropy, &batches, epoch, &mut optimizer);
+            optimizer.end_epoch(None);
+        }
+        assert_eq!(optimizer.history(), rates(&mut OneCycle::new(0.1, 9), 9));
+    }
+
+    #[test]
+    fn test_schedule_state_round_trips() {
+        for (mut saved, mut fresh) in advanced_schedules().into_iter().zip(advanced_schedules()) {
+            fresh.advance(Some(0.3));
+            fresh.load_state(&saved.state()).unwrap();
+            assert_eq!(fresh.state(), saved.state(), "{}", saved.name());
+            for metric in [0.8, 0.9, 0.2, 0.9, 0.9] {
+                assert_eq!(fresh.learning_rate(), saved.learning_rate(), "{}", saved.name());
+                fresh.advance(Some(metric));
+                saved.advance(Some(metric));
+            }
+        }
+
+        let error = StepDecay::new(1.0, 1, 0.5).load_state(&OneCycle::new(1.0, 5).state()).unwrap_err();
+        assert_eq!(error.to_string(), "Cannot load OneCycle schedule state into a StepDecay schedule");
+    }
+
+    #[test]
+    fn test_scheduled_resume_continues_the_schedule() {
+        let inputs = Matrix::from_vec(10, 2, (0..20).map(|i| (i as f64 * 0.37).sin()).collect());
+        let targets = Matrix::from_vec(10, 1, (0..10).map(|i| (i % 2) as f64).collect());
+        let batches = BatchConfig::new(4, 0);
+        let train = |model: &mut Sequential, optimizer: &mut Scheduled<Adam, OneCycle>, epochs: std::ops::Range<usize>| {
+            for epoch in epochs {
+                model.train_epoch_with(&inputs, &targets, &[1.0; 10], Loss::BinaryCrossEntropy, &batches, epoch, optimizer);
+                optimizer.end_epoch(None);
+            }
+        };
+
+        let mut model = Sequential::mlp_seeded(2, &[3], 1, 0);
+        let mut optimizer = Scheduled::new(Adam::new(0.1), OneCycle::new(0.1, 18));
+        train(&mut model, &mut optimizer, 0..3);
+
+        // Restart from copies of the parameters and the saved text, with a schedule at step 0
+        let mut restarted = Sequential::mlp_seeded(2, &[3], 1, 5);
+        for (to, from) in restarted.parameters_mut().into_iter().zip(model.parameters()) {
+            to.value = from.value.clone();
+        }
+        let mut resumed = Scheduled::new(Adam::new(0.1), OneCycle::new(0.1, 18));
+        resumed.load_state(&OptimizerState::from_text(&optimizer.state().to_text()).unwrap()).unwrap();
+
+        train(&mut model, &mut optimizer, 3..6);
+        train(&mut restarted, &mut resumed, 3..6);
+        assert_eq!(resumed.history(), &optimizer.history()[9..]);
+        assert_eq!(model.forward(&inputs), restarted.forward(&inputs));
+
+        // A plain optimizer's state has no schedule to resume from
+        let error = resumed.load_state(&Optimizer::<f64>::state(&Adam::new(0.1))).unwrap_err();
+        assert_eq!(error.to_string(), "Adam state has no schedule to load into OneCycle");
+    }
+}
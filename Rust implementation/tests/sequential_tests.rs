@@ -0,0 +1,138 @@
+mod common;
+
+use ecgnn::brains::*;
+use ecgnn::layers::*;
+use ecgnn::loss::Loss;
+use ecgnn::matrix_math::*;
+use ecgnn::rng::Rng;
+use ecgnn::sequential::Sequential;
+use common::random_matrix;
+
+fn assert_close(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-10, "{} != {}", a, e);
+    }
+}
+
+/// Two Dense layers with random parameters around a ReLU, plus the output activation
+fn two_layer_model(w1: &Matrix, b1: &[f64], w2: &Matrix, b2: &[f64], output: impl Layer + 'static) -> Sequential {
+    let mut model = Sequential::new(Vec::new());
+    model.push(Dense::from_parameters(w1.clone(), Matrix::from_vec(1, b1.len(), b1.to_vec())));
+    model.push(ReLU::new());
+    model.push(Dense::from_parameters(w2.clone(), Matrix::from_vec(1, b2.len(), b2.to_vec())));
+    model.push(output);
+    model
+}
+
+#[cfg(test)]
+mod sequential_tests {
+    use super::*;
+
+    #[test]
+    fn test_mlp_matches_brains_multiclass_network() {
+        let mut rng = Rng::new(1);
+        let segments = random_matrix(8, 10, &mut rng);
+        let (w1, b1) = (random_matrix(10, 5, &mut rng), random_matrix(1, 5, &mut rng).data);
+        let (w2, b2) = (random_matrix(5, 3, &mut rng), random_matrix(1, 3, &mut rng).data);
+        let mut targets = Matrix::zeros(8, 3);
+        for i in 0..8 {
+            targets[(i, i % 3)] = 1.0;
+        }
+
+        let mut model = two_layer_model(&w1, &b1, &w2, &b2, Softmax::new());
+        let loss = model.compute_gradients(&segments, &targets, &[1.0; 8], Loss::CategoricalCrossEntropy);
+
+        let probabilities = predict_proba_batch(&segments, &w1, &b1, &w2, &b2);
+        assert_close(&model.forward(&segments).data, &probabilities.data);
+        assert!((loss - categorical_cross_entropy(&probabilities, &targets)).abs() < 1e-12);
+
+        let (gw1, gb1, gw2, gb2) = backprop_multiclass(&segments, &w1, &b1, &w2, &b2, &targets);
+        let gradients = model.gradients();
+        assert_close(&gradients[0].data, &gw1.data);
+        assert_close(&gradients[1].data, &gb1);
+        assert_close(&gradients[2].data, &gw2.data);
+        assert_close(&gradients[3].data, &gb2);
+    }
+
+    #[test]
+    fn test_mlp_matches_brains_binary_network() {
+        let mut rng = Rng::new(2);
+        let segments = random_matrix(6, 4, &mut rng);
+        let (w1, b1) = (random_matrix(4, 3, &mut rng), random_matrix(1, 3, &mut rng).data);
+        let (w2, b2) = (random_matrix(3, 1, &mut rng), 0.1);
+        let labels = vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0];
+        let weights = vec![2.0, 1.0, 1.0, 2.0, 2.0, 1.0];
+
+        let mut model = two_layer_model(&w1, &b1, &w2, &[b2], Sigmoid::new());
+        let targets = Matrix::from_vec(6, 1, labels.clone());
+        model.compute_gradients(&segments, &targets, &weights, Loss::BinaryCrossEntropy);
+
+        let (gw1, gb1, gw2, gb2) = backprop_weighted(&segments, &w1, &b1, &w2.data, b2, &labels, &weights);
+        let gradients = model.gradients();
+        assert_close(&gradients[0].data, &gw1.data);
+        assert_close(&gradients[1].data, &gb1);
+        assert_close(&gradients[2].data, &gw2);
+        assert_close(&gradients[3].data, &[gb2]);
+    }
+
+    #[test]
+    fn test_deeper_network_learns_three_classes() {
+        let mut rng = Rng::new(3);
+        let mut segments = Matrix::filled(30, 3, 0.1);
+        let mut targets = Matrix::zeros(30, 3);
+        let classes: Vec<usize> = (0..30).map(|i| i % 3).collect();
+        for (i, &c) in classes.iter().enumerate() {
+            segments[(i, c)] = 1.0;
+            targets[(i, c)] = 1.0;
+        }
+
+        // Two hidden layers with random weights to break the symmetry
+        let mut model = Sequential::new(Vec::new());
+        for (inputs, outputs) in [(3, 8), (8, 8)] {
+            model.push(Dense::from_parameters(random_matrix(inputs, outputs, &mut rng), Matrix::zeros(1, outputs)));
+            model.push(ReLU::new());
+        }
+        model.push(Dense::from_parameters(random_matrix(8, 3, &mut rng), Matrix::zeros(1, 3)));
+        model.push(Softmax::new());
+
+        let weights = vec![1.0; 30];
+        let first = model.train_step(&segments, &targets, &weights, Loss::CategoricalCrossEntropy, 0.2);
+        let mut last = first;
+        for _ in 0..300 {
+            last = model.train_step(&segments, &targets, &weights, Loss::CategoricalCrossEntropy, 0.2);
+        }
+
+        assert!(last < first / 4.0);
+        let predicted: Vec<usize> = model.forward(&segments).iter_rows().map(argmax).collect();
+        assert_eq!(calculate_multiclass_accuracy(&predicted, &classes), 1.0);
+    }
+
+    #[test]
+    fn test_mlp_configuration() {
+        let model: Sequential = Sequential::mlp_seeded(3, &[4, 5], 2, 0);
+        let names: Vec<&str> = model.layers.iter().map(|layer| layer.name()).collect();
+        assert_eq!(names, vec!["Dense", "ReLU", "Dense", "ReLU", "Dense", "Softmax"]);
+        assert_eq!(model.parameters().len(), 6);
+        assert_eq!(model.num_parameters(), 3 * 4 + 4 + 4 * 5 + 5 + 5 * 2 + 2);
+
+        let binary: Sequential = Sequential::mlp_seeded(3, &[4], 1, 0);
+        assert_eq!(binary.layers.last().unwrap().name(), "Sigmoid");
+    }
+
+    #[test]
+    fn test_mean_squared_error_gradient() {
+        let predictions = Matrix::from(vec![vec![1.0, 2.0], vec![0.0, 0.0]]);
+        let targets = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 1.0]]);
+        let (loss, gradient) = Loss::MeanSquaredError.evaluate(&predictions, &targets, &[1.0, 1.0]);
+        assert_eq!(loss, 3.0);
+        assert_eq!(gradient.data, vec![1.0, 2.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dense backward called before forward")]
+    fn test_backward_requires_forward() {
+        let mut layer: Dense = Dense::from_parameters(Matrix::zeros(2, 2), Matrix::zeros(1, 2));
+        layer.backward(&Matrix::zeros(1, 2));
+    }
+}
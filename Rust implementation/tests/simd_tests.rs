@@ -0,0 +1,153 @@
+use ecgnn::rng::Rng;
+use ecgnn::simd::{self, SimdLevel};
+
+fn random_vec(len: usize, rng: &mut Rng) -> Vec<f64> {
+    (0..len).map(|_| rng.uniform(-3.0, 3.0)).collect()
+}
+
+/// Every level this CPU can run, the scalar reference included
+fn levels() -> impl Iterator<Item = SimdLevel> {
+    SimdLevel::ALL.into_iter().filter(|&level| level <= simd::detected_level())
+}
+
+/// Lengths covering empty input, partial vectors and every tail length
+const LENGTHS: [usize; 9] = [0, 1, 2, 3, 5, 7, 16, 33, 750];
+
+#[cfg(test)]
+mod simd_tests {
+    use super::*;
+
+    #[test]
+    fn test_elementwise_kernels_are_exact() {
+        let mut rng = Rng::new(1);
+        for len in LENGTHS {
+            let a = random_vec(len, &mut rng);
+            let b = random_vec(len, &mut rng);
+            let run = |level, kernel: fn(SimdLevel, &[f64], &[f64], &mut [f64])| {
+                let mut out = vec![0.0; len];
+                kernel(level, &a, &b, &mut out);
+                out
+            };
+
+            for level in levels() {
+                assert_eq!(run(level, simd::add), run(SimdLevel::Scalar, simd::add), "add at {:?}", level);
+                assert_eq!(run(level, simd::mul), run(SimdLevel::Scalar, simd::mul), "mul at {:?}", level);
+
+                let mut scaled = vec![0.0; len];
+                let mut expected = vec![0.0; len];
+                simd::scale(level, &a, -0.7, &mut scaled);
+                simd::scale(SimdLevel::Scalar, &a, -0.7, &mut expected);
+                assert_eq!(scaled, expected, "scale at {:?}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn test_relu_matches_scalar_bit_for_bit() {
+        let special = [-0.0, 0.0, f64::NAN, -1.0, 1.0, f64::INFINITY, f64::NEG_INFINITY];
+        let mut rng = Rng::new(2);
+        let mut input = random_vec(37, &mut rng);
+        input.extend(special);
+
+        let mut expected = vec![0.0; input.len()];
+        simd::relu(SimdLevel::Scalar, &input, &mut expected);
+        for level in levels() {
+            let mut output = vec![0.0; input.len()];
+            simd::relu(level, &input, &mut output);
+            let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+            assert_eq!(bits(&output), bits(&expected), "relu at {:?}", level);
+        }
+    }
+
+    #[test]
+    fn test_dot_and_axpy_agree_with_scalar() {
+        let mut rng = Rng::new(3);
+        for len in LENGTHS {
+            let a = random_vec(len, &mut rng);
+            let b = random_vec(len, &mut rng);
+            let expected = simd::dot(SimdLevel::Scalar, &a, &b);
+            let magnitude: f64 = a.iter().zip(&b).map(|(x, y)| (x * y).abs()).sum();
+
+            for level in levels() {
+                let dot = simd::dot(level, &a, &b);
+                assert!((dot - expected).abs() <= 1e-13 * (1.0 + magnitude), "dot at {:?}: {} != {}", level, dot, expected);
+
+                let mut y = b.clone();
+                let mut y_expected = b.clone();
+                simd::axpy(level, 0.3, &a, &mut y);
+                simd::axpy(SimdLevel::Scalar, 0.3, &a, &mut y_expected);
+                for (x, e) in y.iter().zip(&y_expected) {
+                    assert!((x - e).abs() <= 1e-15 * (1.0 + e.abs()), "axpy at {:?}", level);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sigmoid_matches_scalar() {
+        let mut input: Vec<f64> = (-800..=800).map(|i| i as f64 * 0.913).collect();
+        input.extend([0.0, -0.0, 1e-300, -1e-300, 745.0, -745.0, f64::INFINITY, f64::NEG_INFINITY]);
+
+        let mut expected = vec![0.0; input.len()];
+        simd::sigmoid(SimdLevel::Scalar, &input, &mut expected);
+        for level in levels() {
+            let mut output = vec![0.0; input.len()];
+            simd::sigmoid(level, &input, &mut output);
+            for ((x, y), e) in input.iter().zip(&output).zip(&expected) {
+                assert!((y - e).abs() <= 1e-15, "sigmoid({}) at {:?}: {} != {}", x, level, y, e);
+            }
+        }
+
+        let mut nan = [0.0; 4];
+        for level in levels() {
+            simd::sigmoid(level, &[f64::NAN; 4], &mut nan);
+            assert!(nan.iter().all(|y| y.is_nan()), "sigmoid(NaN) at {:?}", level);
+        }
+    }
+
+    #[test]
+    fn test_gemm_tiles_agree_with_scalar() {
+        let mut rng = Rng::new(4);
+        let depth = 37;
+        let a = random_vec(4 * depth, &mut rng);
+        let left = random_vec(4 * depth, &mut rng);
+        let right = random_vec(4 * depth, &mut rng);
+        let ldc = 11;
+
+        for (mr, nr) in [(4, 8), (4, 5), (3, 8), (1, 1), (2, 4)] {
+            let mut expected = vec![1.0; 4 * ldc];
+            simd::kernel_4x8(SimdLevel::Scalar, &a, &left, &right, &mut expected, ldc, mr, nr);
+
+            for level in levels() {
+                let mut c = vec![1.0; 4 * ldc];
+                simd::kernel_4x8(level, &a, &left, &right, &mut c, ldc, mr, nr);
+                for (i, (x, e)) in c.iter().zip(&expected).enumerate() {
+                    assert!((x - e).abs() <= 1e-12 * (1.0 + e.abs()), "{}x{} tile at {:?}, element {}", mr, nr, level, i);
+                }
+
+                // Only the mr x nr corner is written
+                for (i, &x) in c.iter().enumerate() {
+                    if i / ldc >= mr || i % ldc >= nr {
+                        assert_eq!(x, 1.0);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn test_gemm_tile_checks_output_bounds() {
+        let a = [0.0; 8];
+        let mut c = [0.0; 6];
+        simd::kernel_4x4(SimdLevel::Scalar, &a, &a, &mut c, 4, 2, 4);
+    }
+
+    #[test]
+    fn test_max_level_caps_active_level() {
+        simd::set_max_level(Some(SimdLevel::Scalar));
+        assert_eq!(simd::active_level(), SimdLevel::Scalar);
+        simd::set_max_level(None);
+        assert_eq!(simd::active_level(), simd::detected_level());
+    }
+}
@@ -0,0 +1,79 @@
+use ecgnn::split::*;
+
+// Three segments per record
+fn ids(records: &[&str]) -> Vec<String> {
+    records.iter().flat_map(|r| std::iter::repeat_n(r.to_string(), 3)).collect()
+}
+
+fn records_in(record_ids: &[String], indices: &[usize]) -> Vec<String> {
+    unique_records(&select(record_ids, indices))
+}
+
+#[cfg(test)]
+mod split_tests {
+    use super::*;
+
+    #[test]
+    fn test_ds1_ds2_are_disjoint() {
+        assert_eq!(DS1.len(), 22);
+        assert_eq!(DS2.len(), 22);
+        assert!(DS1.iter().all(|r| !DS2.contains(r)));
+    }
+
+    #[test]
+    fn test_de_chazal_split() {
+        let record_ids = ids(&["100", "101", "102", "106", "234"]);
+        let split = de_chazal_split(&record_ids);
+
+        assert_eq!(records_in(&record_ids, &split.train), vec!["101", "106"]);
+        assert_eq!(records_in(&record_ids, &split.test), vec!["100", "234"]);
+        // Paced record 102 is in neither set
+        assert_eq!(split.train.len() + split.test.len(), 12);
+    }
+
+    #[test]
+    fn test_patient_holdout_keeps_records_together() {
+        let record_ids = ids(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        let split = patient_holdout_split(&record_ids, 0.2, 7);
+
+        let train = records_in(&record_ids, &split.train);
+        let test = records_in(&record_ids, &split.test);
+        assert_eq!(test.len(), 2);
+        assert_eq!(train.len(), 8);
+        assert!(test.iter().all(|r| !train.contains(r)));
+        assert_eq!(split.train.len() + split.test.len(), record_ids.len());
+    }
+
+    #[test]
+    fn test_patient_holdout_is_seeded() {
+        let record_ids = ids(&["a", "b", "c", "d", "e", "f", "g", "h"]);
+        assert_eq!(patient_holdout_split(&record_ids, 0.25, 1), patient_holdout_split(&record_ids, 0.25, 1));
+
+        let different = (2..20).any(|seed| {
+            patient_holdout_split(&record_ids, 0.25, seed) != patient_holdout_split(&record_ids, 0.25, 1)
+        });
+        assert!(different);
+    }
+
+    #[test]
+    fn test_patient_holdout_holds_out_at_least_one_record() {
+        let record_ids = ids(&["a", "b", "c"]);
+        let split = patient_holdout_split(&record_ids, 0.01, 3);
+        assert_eq!(records_in(&record_ids, &split.test).len(), 1);
+        assert_eq!(records_in(&record_ids, &split.train).len(), 2);
+    }
+
+    #[test]
+    fn test_leave_one_patient_out() {
+        let record_ids = ids(&["b", "a", "c"]);
+        let folds = leave_one_patient_out(&record_ids);
+
+        assert_eq!(folds.len(), 3);
+        assert_eq!(folds[0].0, "a");
+        for (held_out, split) in &folds {
+            assert_eq!(records_in(&record_ids, &split.test), vec![held_out.clone()]);
+            assert_eq!(split.test.len(), 3);
+            assert_eq!(split.train.len(), 6);
+        }
+    }
+}
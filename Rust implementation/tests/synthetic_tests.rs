@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use ecgnn::synthetic::*;
+
+    fn short_config() -> EcgSynConfig {
+        EcgSynConfig {
+            duration: 20.0,
+            ..EcgSynConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_ecg_length_and_leads() {
+        let config = EcgSynConfig {
+            leads: vec![Morphology::lead_ii(), Morphology::lead_v1()],
+            ..short_config()
+        };
+        let ecg = generate_ecg(&config);
+
+        assert_eq!(ecg.leads.len(), 2);
+        assert_eq!(ecg.len(), 20 * 360);
+        assert_eq!(ecg.leads[0].len(), ecg.leads[1].len());
+        assert!(ecg.leads[0].iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn test_generate_ecg_is_reproducible() {
+        let a = generate_ecg(&short_config());
+        let b = generate_ecg(&short_config());
+        assert_eq!(a.leads, b.leads);
+        assert_eq!(a.beats, b.beats);
+
+        let c = generate_ecg(&EcgSynConfig { seed: 7, ..short_config() });
+        assert_ne!(a.leads, c.leads);
+    }
+
+    #[test]
+    fn test_beat_count_follows_heart_rate() {
+        let slow = generate_ecg(&EcgSynConfig { heart_rate: 60.0, ..short_config() });
+        let fast = generate_ecg(&EcgSynConfig { heart_rate: 120.0, ..short_config() });
+
+        // 20 s at 60 and 120 bpm, allowing for HRV and the partial first/last beats
+        assert!((slow.beats.len() as i64 - 20).abs() <= 2, "got {} beats", slow.beats.len());
+        assert!((fast.beats.len() as i64 - 40).abs() <= 2, "got {} beats", fast.beats.len());
+    }
+
+    #[test]
+    fn test_r_peak_annotations_sit_on_local_maxima() {
+        let config = EcgSynConfig {
+            noise_std: 0.0,
+            respiration_amplitude: 0.0,
+            ..short_config()
+        };
+        let ecg = generate_ecg(&config);
+        let signal = &ecg.leads[0];
+
+        for beat in &ecg.beats {
+            let lo = beat.sample.saturating_sub(10);
+            let hi = (beat.sample + 10).min(signal.len() - 1);
+            let peak = (lo..=hi).fold(lo, |best, i| if signal[i] > signal[best] { i } else { best });
+            assert!((peak as i64 - beat.sample as i64).abs() <= 3);
+            assert!(signal[peak] > 0.8);
+        }
+    }
+
+    #[test]
+    fn test_pvcs_are_labelled_and_followed_by_normal_beat() {
+        let config = EcgSynConfig {
+            pvc_probability: 0.3,
+            duration: 60.0,
+            ..EcgSynConfig::default()
+        };
+        let ecg = generate_ecg(&config);
+
+        let pvcs = ecg.beats.iter().filter(|b| b.kind == BeatKind::Ventricular).count();
+        assert!(pvcs > 0);
+        for pair in ecg.beats.windows(2) {
+            if pair[0].kind == BeatKind::Ventricular {
+                assert_eq!(pair[1].kind, BeatKind::Normal);
+            }
+        }
+        assert_eq!(BeatKind::Ventricular.symbol(), 'V');
+    }
+
+    #[test]
+    fn test_afib_episode_is_annotated_and_irregular() {
+        let config = EcgSynConfig {
+            duration: 60.0,
+            episodes: vec![Episode {
+                rhythm: Rhythm::AtrialFibrillation,
+                start: 20.0,
+                duration: 30.0,
+                heart_rate: 90.0,
+            }],
+            ..EcgSynConfig::default()
+        };
+        let ecg = generate_ecg(&config);
+
+        let rhythms: Vec<Rhythm> = ecg.rhythms.iter().map(|r| r.rhythm).collect();
+        assert_eq!(rhythms, vec![Rhythm::AtrialFibrillation, Rhythm::Normal]);
+        assert_eq!(ecg.rhythm_at(30 * 360), Rhythm::AtrialFibrillation);
+        assert_eq!(ecg.rhythm_at(5 * 360), Rhythm::Normal);
+
+        let rr_cv = |from: usize, to: usize| {
+            let rr: Vec<f64> = ecg.beats.windows(2)
+                .filter(|p| p[0].sample >= from && p[1].sample < to)
+                .map(|p| (p[1].sample - p[0].sample) as f64)
+                .collect();
+            let mean = rr.iter().sum::<f64>() / rr.len() as f64;
+            let var = rr.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / rr.len() as f64;
+            var.sqrt() / mean
+        };
+        assert!(rr_cv(22 * 360, 48 * 360) > 3.0 * rr_cv(0, 18 * 360));
+    }
+
+    #[test]
+    fn test_segment_labels_match_rows() {
+        let config = EcgSynConfig {
+            leads: vec![Morphology::lead_ii(), Morphology::lead_v1()],
+            pvc_probability: 0.1,
+            ..short_config()
+        };
+        let ecg = generate_ecg(&config);
+        let rows = ecg.to_rows();
+        let labels = ecg.segment_labels(250);
+
+        assert_eq!(rows.len(), ecg.len());
+        assert_eq!(rows[10], vec![10.0, ecg.leads[0][10], ecg.leads[1][10]]);
+        assert_eq!(labels.len(), rows.len() / 250);
+
+        for (s, label) in labels.iter().enumerate() {
+            let has_pvc = ecg.beats.iter()
+                .any(|b| b.sample / 250 == s && b.kind == BeatKind::Ventricular);
+            assert_eq!(*label == 1.0, has_pvc);
+        }
+    }
+}